@@ -30,7 +30,9 @@ def local(ctx):
             'batch_size': 4096,
             'max_batch_delay': 10
         },
-        'execution': "cairo_native"
+        'execution': "cairo_native",
+        # The benchmark client signs with the development key of the genesis account
+        'account': {'dev': True}
     }
     try:
         ret = LocalBench(bench_params, node_params).run(debug=True).result()
@@ -115,7 +117,8 @@ def remote(ctx):
             'sync_retry_nodes': 3,
             'batch_size': 500_000,
             'max_batch_delay': 100
-        }
+        },
+        'account': {'dev': True}
     }
     try:
         Bench(ctx).run(bench_params, node_params, debug=False)
@@ -0,0 +1,121 @@
+use array::ArrayTrait;
+use starknet::ContractAddress;
+
+#[derive(Drop, Serde)]
+struct Call {
+    to: ContractAddress,
+    selector: felt252,
+    calldata: Array<felt252>
+}
+
+#[starknet::interface]
+trait IAccount<TContractState> {
+    fn get_public_key(self: @TContractState) -> felt252;
+    fn is_valid_signature(
+        self: @TContractState, hash: felt252, signature: Array<felt252>
+    ) -> felt252;
+    fn __validate__(self: @TContractState, calls: Array<Call>) -> felt252;
+    fn __validate_declare__(self: @TContractState, class_hash: felt252) -> felt252;
+    fn __validate_deploy__(
+        self: @TContractState,
+        class_hash: felt252,
+        contract_address_salt: felt252,
+        public_key: felt252
+    ) -> felt252;
+    fn __execute__(ref self: TContractState, calls: Array<Call>) -> Array<Span<felt252>>;
+}
+
+#[starknet::contract]
+mod account {
+    use array::{ArrayTrait, SpanTrait};
+    use box::BoxTrait;
+    use ecdsa::check_ecdsa_signature;
+    use option::OptionTrait;
+    use starknet::{call_contract_syscall, get_caller_address, get_tx_info, SyscallResultTrait};
+    use zeroable::Zeroable;
+    use super::Call;
+
+    #[storage]
+    struct Storage {
+        public_key: felt252,
+    }
+
+    #[constructor]
+    fn constructor(ref self: ContractState, public_key: felt252) {
+        self.public_key.write(public_key);
+    }
+
+    #[external(v0)]
+    impl AccountImpl of super::IAccount<ContractState> {
+        fn get_public_key(self: @ContractState) -> felt252 {
+            self.public_key.read()
+        }
+
+        fn is_valid_signature(
+            self: @ContractState, hash: felt252, signature: Array<felt252>
+        ) -> felt252 {
+            if self.is_valid(hash, signature.span()) {
+                starknet::VALIDATED
+            } else {
+                0
+            }
+        }
+
+        fn __validate__(self: @ContractState, calls: Array<Call>) -> felt252 {
+            self.validate_transaction()
+        }
+
+        fn __validate_declare__(self: @ContractState, class_hash: felt252) -> felt252 {
+            self.validate_transaction()
+        }
+
+        fn __validate_deploy__(
+            self: @ContractState,
+            class_hash: felt252,
+            contract_address_salt: felt252,
+            public_key: felt252
+        ) -> felt252 {
+            self.validate_transaction()
+        }
+
+        fn __execute__(ref self: ContractState, mut calls: Array<Call>) -> Array<Span<felt252>> {
+            // Only the sequencer runs transactions, other contracts can't make the account call
+            assert(get_caller_address().is_zero(), 'Account: invalid caller');
+            let mut results = ArrayTrait::new();
+            loop {
+                match calls.pop_front() {
+                    Option::Some(call) => {
+                        let result = call_contract_syscall(
+                            call.to, call.selector, call.calldata.span()
+                        )
+                            .unwrap_syscall();
+                        results.append(result);
+                    },
+                    Option::None(()) => {
+                        break;
+                    },
+                };
+            };
+            results
+        }
+    }
+
+    #[generate_trait]
+    impl InternalImpl of InternalTrait {
+        fn validate_transaction(self: @ContractState) -> felt252 {
+            let tx_info = get_tx_info().unbox();
+            assert(
+                self.is_valid(tx_info.transaction_hash, tx_info.signature),
+                'Account: invalid signature'
+            );
+            starknet::VALIDATED
+        }
+
+        fn is_valid(self: @ContractState, hash: felt252, signature: Span<felt252>) -> bool {
+            if signature.len() != 2 {
+                return false;
+            }
+            check_ecdsa_signature(hash, self.public_key.read(), *signature.at(0), *signature.at(1))
+        }
+    }
+}
@@ -0,0 +1,24 @@
+#[starknet::contract]
+mod ExecutionInfo {
+    use box::BoxTrait;
+    use starknet::{get_block_info, get_tx_info, SyscallResultTrait};
+    use starknet::syscalls::get_block_hash_syscall;
+
+    #[storage]
+    struct Storage {}
+
+    #[external(v0)]
+    fn block_timestamp(self: @ContractState) -> u64 {
+        get_block_info().unbox().block_timestamp
+    }
+
+    #[external(v0)]
+    fn transaction_hash(self: @ContractState) -> felt252 {
+        get_tx_info().unbox().transaction_hash
+    }
+
+    #[external(v0)]
+    fn block_hash(self: @ContractState, block_number: u64) -> felt252 {
+        get_block_hash_syscall(block_number).unwrap_syscall()
+    }
+}
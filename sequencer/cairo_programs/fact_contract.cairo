@@ -1,11 +1,17 @@
 #[starknet::contract]
-mod Factorial{
+mod Factorial {
+    #[storage]
+    struct Storage {}
 
-    #[external]
-    fn fact(n: felt252) -> felt252 {
+    #[external(v0)]
+    fn fact(self: @ContractState, n: felt252) -> felt252 {
+        fact_inner(n)
+    }
+
+    fn fact_inner(n: felt252) -> felt252 {
         match n {
             0 => 1,
-            _ => n * fact(n - 1)
+            _ => n * fact_inner(n - 1)
         }
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,17 @@
 #[starknet::contract]
 mod Fibonacci {
+    #[storage]
+    struct Storage {}
 
-    #[external]
-    fn fib(a: felt252, b: felt252, n: felt252) -> felt252 {
+    #[external(v0)]
+    fn fib(self: @ContractState, a: felt252, b: felt252, n: felt252) -> felt252 {
+        fib_inner(a, b, n)
+    }
+
+    fn fib_inner(a: felt252, b: felt252, n: felt252) -> felt252 {
         match n {
             0 => a,
-            _ => fib(b, a + b, n - 1),
+            _ => fib_inner(b, a + b, n - 1),
         }
     }
-}
\ No newline at end of file
+}
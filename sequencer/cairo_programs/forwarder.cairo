@@ -0,0 +1,32 @@
+#[starknet::contract]
+mod Forwarder {
+    use array::{ArrayTrait, SpanTrait};
+    use starknet::{ClassHash, ContractAddress, SyscallResultTrait};
+    use starknet::syscalls::{call_contract_syscall, deploy_syscall, library_call_syscall};
+
+    #[storage]
+    struct Storage {}
+
+    #[external(v0)]
+    fn forward(
+        self: @ContractState, address: ContractAddress, selector: felt252, calldata: Array<felt252>
+    ) -> Span<felt252> {
+        call_contract_syscall(address, selector, calldata.span()).unwrap_syscall()
+    }
+
+    #[external(v0)]
+    fn forward_library(
+        self: @ContractState, class_hash: ClassHash, selector: felt252, calldata: Array<felt252>
+    ) -> Span<felt252> {
+        library_call_syscall(class_hash, selector, calldata.span()).unwrap_syscall()
+    }
+
+    #[external(v0)]
+    fn deploy(
+        self: @ContractState, class_hash: ClassHash, salt: felt252, calldata: Array<felt252>
+    ) -> ContractAddress {
+        let (address, _) = deploy_syscall(class_hash, salt, calldata.span(), false)
+            .unwrap_syscall();
+        address
+    }
+}
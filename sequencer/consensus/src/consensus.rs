@@ -16,6 +16,8 @@ use mempool::ConsensusMempoolMessage;
 use network::{MessageHandler, Receiver as NetworkReceiver, Writer};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use store::Store;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
@@ -41,6 +43,9 @@ pub enum ConsensusMessage {
 pub struct Consensus;
 
 impl Consensus {
+    /// Spawns the consensus tasks. Committed blocks are sent through `tx_commit`, while
+    /// `tx_sequenced` gets every block as soon as it is stored, before it is committed. Returns a
+    /// counter of the blocks this node is waiting to receive from others in order to catch up.
     #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         name: PublicKey,
@@ -51,7 +56,8 @@ impl Consensus {
         rx_mempool: Receiver<Digest>,
         tx_mempool: Sender<ConsensusMempoolMessage>,
         tx_commit: Sender<Block>,
-    ) {
+        tx_sequenced: Sender<Block>,
+    ) -> Arc<AtomicUsize> {
         // NOTE: This log entry is used to compute performance.
         parameters.log();
 
@@ -92,6 +98,7 @@ impl Consensus {
             tx_loopback.clone(),
             parameters.sync_retry_delay,
         );
+        let pending_sync_requests = synchronizer.pending_requests();
 
         // Spawn the consensus core.
         Core::spawn(
@@ -107,6 +114,7 @@ impl Consensus {
             rx_loopback,
             tx_proposer,
             tx_commit,
+            tx_sequenced,
         );
 
         // Spawn the block proposer.
@@ -121,6 +129,8 @@ impl Consensus {
 
         // Spawn the helper module.
         Helper::spawn(committee, store, /* rx_requests */ rx_helper);
+
+        pending_sync_requests
     }
 }
 
@@ -35,6 +35,7 @@ pub struct Core {
     rx_loopback: Receiver<Block>,
     tx_proposer: Sender<ProposerMessage>,
     tx_commit: Sender<Block>,
+    tx_sequenced: Sender<Block>,
     round: Round,
     last_voted_round: Round,
     last_committed_round: Round,
@@ -59,6 +60,7 @@ impl Core {
         rx_loopback: Receiver<Block>,
         tx_proposer: Sender<ProposerMessage>,
         tx_commit: Sender<Block>,
+        tx_sequenced: Sender<Block>,
     ) {
         tokio::spawn(async move {
             Self {
@@ -73,6 +75,7 @@ impl Core {
                 rx_loopback,
                 tx_proposer,
                 tx_commit,
+                tx_sequenced,
                 round: 1,
                 last_voted_round: 0,
                 last_committed_round: 0,
@@ -329,6 +332,10 @@ impl Core {
         // Store the block only if we have already processed all its ancestors.
         self.store_block(block).await;
 
+        // Let the node know about the block before it gets committed. This is best effort: the
+        // consensus never waits for the node to catch up.
+        let _ = self.tx_sequenced.try_send(block.clone());
+
         self.cleanup_proposer(&b0, &b1, block).await;
 
         // Check if we can commit the head of the 2-chain.
@@ -10,6 +10,8 @@ use futures::stream::StreamExt as _;
 use log::{debug, error};
 use network::SimpleSender;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use store::Store;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -24,6 +26,8 @@ const TIMER_ACCURACY: u64 = 5_000;
 pub struct Synchronizer {
     store: Store,
     inner_channel: Sender<Block>,
+    /// Number of parent blocks requested to other nodes and not received yet.
+    pending_requests: Arc<AtomicUsize>,
 }
 
 impl Synchronizer {
@@ -37,7 +41,10 @@ impl Synchronizer {
         let mut network = SimpleSender::new();
         let (tx_inner, mut rx_inner): (_, Receiver<Block>) = channel(CHANNEL_CAPACITY);
 
+        let pending_requests = Arc::new(AtomicUsize::new(0));
+
         let store_copy = store.clone();
+        let pending_requests_copy = pending_requests.clone();
         tokio::spawn(async move {
             let mut waiting = FuturesUnordered::new();
             let mut pending = HashSet::new();
@@ -61,6 +68,7 @@ impl Synchronizer {
                                     .expect("Failed to measure time")
                                     .as_millis();
                                 requests.insert(parent.clone(), now);
+                                pending_requests_copy.store(requests.len(), Ordering::Relaxed);
                                 let address = committee
                                     .address(&author)
                                     .expect("Author of valid block is not in the committee");
@@ -75,6 +83,7 @@ impl Synchronizer {
                         Ok(block) => {
                             let _ = pending.remove(&block.digest());
                             let _ = requests.remove(block.parent());
+                            pending_requests_copy.store(requests.len(), Ordering::Relaxed);
                             if let Err(e) = tx_loopback.send(block).await {
                                 panic!("Failed to send message through core channel: {}", e);
                             }
@@ -109,9 +118,16 @@ impl Synchronizer {
         Self {
             store,
             inner_channel: tx_inner,
+            pending_requests,
         }
     }
 
+    /// Returns a counter of the parent blocks requested to other nodes and not received yet. A
+    /// non-zero value means this node is lagging behind the committee.
+    pub fn pending_requests(&self) -> Arc<AtomicUsize> {
+        self.pending_requests.clone()
+    }
+
     async fn waiter(mut store: Store, wait_on: Digest, deliver: Block) -> ConsensusResult<Block> {
         let _ = store.notify_read(wait_on.to_vec()).await?;
         Ok(deliver)
@@ -0,0 +1,120 @@
+use crate::contract_address::{calculate_contract_address, to_field_element};
+use cairo_felt::Felt252;
+use starknet_crypto::{get_public_key, rfc6979_generate_k, FieldElement};
+use types::DeployAccountTransaction;
+
+/// Entry point accounts check the transactions they send with, before they are executed.
+pub const VALIDATE_ENTRY_POINT: &str = "__validate__";
+/// Entry point accounts check the transactions declaring classes with, before the class is
+/// declared.
+pub const VALIDATE_DECLARE_ENTRY_POINT: &str = "__validate_declare__";
+/// Entry point accounts check the transactions deploying them with, once their constructor ran.
+pub const VALIDATE_DEPLOY_ENTRY_POINT: &str = "__validate_deploy__";
+/// Entry point accounts run the calls of the transactions they send with.
+pub const EXECUTE_ENTRY_POINT: &str = "__execute__";
+
+/// What `__validate__` returns for valid transactions: the short string `VALID`.
+pub fn validated() -> Felt252 {
+    Felt252::from_bytes_be(b"VALID")
+}
+
+/// A call an account makes on behalf of the transaction it sends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub to: Felt252,
+    pub selector: Felt252,
+    pub calldata: Vec<Felt252>,
+}
+
+/// Returns the calldata of an invoke transaction making the given calls, which is what
+/// `__execute__` takes: the number of calls, followed by the address, the selector, the number of
+/// arguments and the arguments of each.
+pub fn multicall_calldata(calls: &[Call]) -> Vec<Felt252> {
+    let mut calldata = vec![Felt252::new(calls.len())];
+    for call in calls {
+        calldata.push(call.to.clone());
+        calldata.push(call.selector.clone());
+        calldata.push(Felt252::new(call.calldata.len()));
+        calldata.extend(call.calldata.iter().cloned());
+    }
+    calldata
+}
+
+/// Returns the address the account of a deploy account transaction is deployed at. Accounts are
+/// deployed from zero, so clients know the address before sending the transaction.
+pub fn deploy_account_address(tx: &DeployAccountTransaction) -> Felt252 {
+    calculate_contract_address(
+        &tx.contract_address_salt,
+        &tx.class_hash,
+        &tx.constructor_calldata,
+        &Felt252::new(0),
+    )
+}
+
+/// Returns the public key accounts signed for with the given private key are deployed with.
+pub fn public_key(private_key: &Felt252) -> Felt252 {
+    let public_key = get_public_key(&to_field_element(private_key));
+    Felt252::from_bytes_be(&public_key.to_bytes_be())
+}
+
+/// Signs a transaction hash the way the bundled account checks it: an ECDSA signature on the
+/// STARK curve, made of `r` and `s`.
+pub fn sign(private_key: &Felt252, transaction_hash: &Felt252) -> Vec<Felt252> {
+    let private_key = to_field_element(private_key);
+    let message = to_field_element(transaction_hash);
+    let k = rfc6979_generate_k(&message, &private_key, None);
+    let signature =
+        starknet_crypto::sign(&private_key, &message, &k).expect("Transaction hashes are signable");
+    [signature.r, signature.s]
+        .iter()
+        .map(|felt: &FieldElement| Felt252::from_bytes_be(&felt.to_bytes_be()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{multicall_calldata, public_key, sign, Call};
+    use crate::contract_address::to_field_element;
+    use cairo_felt::Felt252;
+    use starknet_crypto::verify;
+
+    #[test]
+    fn signatures_verify_against_the_public_key() {
+        let private_key = Felt252::new(0x1234);
+        let hash = Felt252::new(0xabcd);
+        let signature = sign(&private_key, &hash);
+
+        let verifies = |public_key: &Felt252| {
+            verify(
+                &to_field_element(public_key),
+                &to_field_element(&hash),
+                &to_field_element(&signature[0]),
+                &to_field_element(&signature[1]),
+            )
+            .unwrap()
+        };
+        assert!(verifies(&public_key(&private_key)));
+        assert!(!verifies(&public_key(&Felt252::new(0x4321))));
+    }
+
+    #[test]
+    fn multicall_calldata_lists_the_calls() {
+        let calls = [
+            Call {
+                to: Felt252::new(1),
+                selector: Felt252::new(2),
+                calldata: vec![Felt252::new(3), Felt252::new(4)],
+            },
+            Call {
+                to: Felt252::new(5),
+                selector: Felt252::new(6),
+                calldata: vec![],
+            },
+        ];
+        let expected: Vec<Felt252> = [2, 1, 2, 2, 3, 4, 5, 6, 0]
+            .into_iter()
+            .map(Felt252::new)
+            .collect();
+        assert_eq!(expected, multicall_calldata(&calls));
+    }
+}
@@ -1,94 +1,458 @@
-use cairo_lang_sierra::{
-    extensions::core::{CoreLibfunc, CoreType},
-    program::Program,
+use crate::account::{deploy_account_address, VALIDATE_DEPLOY_ENTRY_POINT};
+use crate::contract_class::sierra_program;
+use crate::engine::{
+    check_nonce, validate, BlockContext, CallError, CallInfo, ContractCall, ExecutionContext,
+    ExecutionEngine, ExecutionResources, TransactionContext,
 };
-use cairo_native::metadata::syscall_handler::SyscallHandlerMeta;
+use crate::state::{CachedState, StateReader};
+use crate::transaction::TransactionExecution;
+use cairo_lang_sierra::program::{Function, Program};
 use num_bigint::BigUint;
-use serde_json::{json, Value};
-use std::sync::Arc;
+use program_cache::ProgramCache;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+use std::cell::Cell;
+use syscall_handler::SyscallHandler;
+use types::{ContractClass, DeployAccountTransaction, FlattenedSierraClass, SierraEntryPoint};
 
+pub mod program_cache;
 pub mod syscall_handler;
 
+/// Most calls that can be running at once, the outermost one included, as in Starknet. Calls
+/// made deeper than that fail, rather than overflow the stack of the thread running them.
+pub const MAX_CALL_DEPTH: usize = 50;
+
+thread_local! {
+    /// Calls running on this thread, nested into one another.
+    static CALL_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// Returns how many calls are running on this thread, nested into one another.
+fn call_depth() -> usize {
+    CALL_DEPTH.with(|depth| depth.get())
+}
+
 pub struct CairoNativeEngine {
-    fib_program: Arc<Program>,
-    fact_program: Arc<Program>,
-    erc20_program: Arc<Program>,
+    /// Id of the chain transactions are executed in
+    chain_id: cairo_felt::Felt252,
+    /// Programs of the classes run lately, already compiled
+    programs: ProgramCache,
 }
 
 impl CairoNativeEngine {
-    pub fn new(
-        fib_program: Arc<Program>,
-        fact_program: Arc<Program>,
-        erc20_program: Arc<Program>,
-    ) -> Self {
+    /// Creates an engine keeping up to `program_cache_size` compiled classes in memory.
+    pub fn new(chain_id: cairo_felt::Felt252, program_cache_size: usize) -> Self {
         Self {
-            fib_program,
-            fact_program,
-            erc20_program,
+            chain_id,
+            programs: ProgramCache::new(program_cache_size),
         }
     }
 
-    pub fn execute_fibonacci(&self, n: Vec<u32>) -> String {
-        let ret = execute_cairo_native_program(
-            &self.fib_program,
-            "fib_contract::fib_contract::Fibonacci::fib",
-            vec![
-                get_input_value_cairo_native(0),
-                get_input_value_cairo_native(1),
-                n,
-            ],
-            false,
-        );
-        format!("Output Fib Cairo Native: {:?}", ret)
+    /// Deploys a contract of the given class at the given address, running its constructor with
+    /// the given calldata and gas if the class has one. Nothing is deployed if the constructor
+    /// fails.
+    #[allow(clippy::too_many_arguments)]
+    pub fn deploy_contract(
+        &self,
+        contract_address: &cairo_felt::Felt252,
+        class_hash: &cairo_felt::Felt252,
+        constructor_calldata: &[cairo_felt::Felt252],
+        caller_address: &cairo_felt::Felt252,
+        initial_gas: u64,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        let class = match state.get_class(class_hash) {
+            Ok(Some(ContractClass::Sierra(class))) => class,
+            _ => return Err(CallError::InvalidClass),
+        };
+        state.push_layer();
+        state.set_class_hash_at(contract_address.clone(), class_hash.clone());
+
+        let result = match class.entry_points_by_type.constructor.first() {
+            Some(constructor) => self.run_call(
+                &ContractCall {
+                    contract_address: contract_address.clone(),
+                    entry_point_selector: constructor.selector.clone(),
+                    calldata: constructor_calldata.to_vec(),
+                    initial_gas,
+                },
+                caller_address,
+                class_hash,
+                &class,
+                constructor,
+                context,
+                state,
+            ),
+            None => Ok(CallInfo::default()),
+        };
+        match result {
+            Ok(call_info) => Ok(CallInfo {
+                state_diff: state.commit_layer(),
+                ..call_info
+            }),
+            Err(e) => {
+                state.discard_layer();
+                Err(e)
+            }
+        }
     }
 
-    pub fn execute_factorial(&self, n: Vec<u32>) -> String {
-        let ret = execute_cairo_native_program(
-            &self.fact_program,
-            "fact_contract::fact_contract::Factorial::fact",
-            vec![n],
-            false,
-        );
-        format!("Output Fact Cairo Native: {:?}", ret)
+    /// Calls an external function of the contract at the address of the call, on behalf of the
+    /// contract at `caller_address`.
+    fn call_contract(
+        &self,
+        call: &ContractCall,
+        caller_address: &cairo_felt::Felt252,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        let (class_hash, class) = load_class(state, &call.contract_address)?;
+        let entry_point = find_entry_point(
+            &class.entry_points_by_type.external,
+            &call.entry_point_selector,
+        )?;
+        self.run_call(
+            call,
+            caller_address,
+            &class_hash,
+            &class,
+            &entry_point,
+            context,
+            state,
+        )
     }
 
-    pub fn execute_erc20(
+    /// Runs an external function of the given class as the contract at the address of the call,
+    /// with its storage.
+    fn library_call(
         &self,
-        initial_supply: cairo_felt::Felt252,
-        symbol: cairo_felt::Felt252,
-        contract_address: cairo_felt::Felt252,
-    ) -> String {
-        let ret = execute_cairo_native_program(
-            &self.erc20_program,
-            "erc20::erc20::erc_20::__constructor::constructor",
-            vec![
-                cairo_native::easy::felt252_short_str("name").to_vec(), // name
-                cairo_native::easy::felt252_bigint(symbol.to_bigint()).to_vec(), // symbol
-                cairo_native::easy::felt252_bigint(0).to_vec(),         // decimals
-                cairo_native::easy::felt252_bigint(initial_supply.to_bigint()).to_vec(), // initial supply
-                cairo_native::easy::felt252_bigint(contract_address.to_bigint()).to_vec(), // contract address
-                cairo_native::easy::felt252_bigint(6).to_vec(),                            // ??
-            ],
-            true,
-        );
-        format!("Output ERC20 Cairo Native: {:?}", ret)
+        call: &ContractCall,
+        class_hash: &cairo_felt::Felt252,
+        caller_address: &cairo_felt::Felt252,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        let class = match state.get_class(class_hash) {
+            Ok(Some(ContractClass::Sierra(class))) => class,
+            _ => return Err(CallError::InvalidClass),
+        };
+        let entry_point = find_entry_point(
+            &class.entry_points_by_type.external,
+            &call.entry_point_selector,
+        )?;
+        self.run_call(
+            call,
+            caller_address,
+            class_hash,
+            &class,
+            &entry_point,
+            context,
+            state,
+        )
+    }
+
+    /// Runs an entry point of the given class on a layer of its own, so its writes are dropped
+    /// if it fails.
+    #[allow(clippy::too_many_arguments)]
+    fn run_call(
+        &self,
+        call: &ContractCall,
+        caller_address: &cairo_felt::Felt252,
+        class_hash: &cairo_felt::Felt252,
+        class: &FlattenedSierraClass,
+        entry_point: &SierraEntryPoint,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        state.push_layer();
+        let result = self
+            .run_entry_point(
+                call,
+                caller_address,
+                class_hash,
+                class,
+                entry_point,
+                context,
+                state,
+            )
+            .and_then(|(output, call_info)| {
+                // The gas of the calls the contract made is added to its own
+                let gas_consumed = gas_consumed(&output, call.initial_gas)
+                    .saturating_add(call_info.resources.gas_consumed);
+                Ok(CallInfo {
+                    retdata: parse_call_result(&output)?,
+                    resources: ExecutionResources { gas_consumed },
+                    ..call_info
+                })
+            });
+        match result {
+            Ok(call_info) => Ok(CallInfo {
+                state_diff: state.commit_layer(),
+                ..call_info
+            }),
+            Err(e) => {
+                state.discard_layer();
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs an entry point of the given class, compiling the class unless it was run lately.
+    /// Returns the output of the entry point along with what the calls it made did: the events
+    /// emitted and the messages sent, its own included, and the gas consumed.
+    #[allow(clippy::too_many_arguments)]
+    fn run_entry_point(
+        &self,
+        call: &ContractCall,
+        caller_address: &cairo_felt::Felt252,
+        class_hash: &cairo_felt::Felt252,
+        class: &FlattenedSierraClass,
+        entry_point: &SierraEntryPoint,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<(Value, CallInfo), CallError> {
+        // Calls into a class further up the call stack, itself included, take another instance
+        // of its program, as the one running can't be run again until it returns
+        let mut program = self.programs.take_or_compile(class_hash, || {
+            sierra_program(class).map_err(|_| CallError::InvalidClass)
+        })?;
+
+        let syscall_handler =
+            SyscallHandler::new(self, call, caller_address, context, std::mem::take(state));
+        let depth = call_depth();
+        CALL_DEPTH.with(|running| running.set(depth + 1));
+        let output = find_function(program.program(), entry_point)
+            .map(|function| function.id.clone())
+            .and_then(|function_id| {
+                program.run(
+                    &function_id,
+                    felts_arg(&call.calldata),
+                    call.initial_gas,
+                    Some(&syscall_handler),
+                )
+            });
+        CALL_DEPTH.with(|running| running.set(depth));
+        self.programs.put_back(class_hash, program);
+
+        let call_info = CallInfo {
+            events: syscall_handler.take_events(),
+            messages: syscall_handler.take_messages(),
+            resources: ExecutionResources {
+                gas_consumed: syscall_handler.nested_gas(),
+            },
+            ..Default::default()
+        };
+        *state = syscall_handler.into_state();
+        Ok((output?, call_info))
+    }
+}
+
+impl ExecutionEngine for CairoNativeEngine {
+    /// Calls the external function with the given selector of the Sierra class deployed at the
+    /// given address.
+    fn call(
+        &self,
+        call: &ContractCall,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        self.call_contract(call, &cairo_felt::Felt252::new(0), context, state)
+    }
+
+    /// The constructor and `__validate_deploy__` share as much gas as the maximal fee pays for.
+    /// Transactions with a nonce other than zero, that deploy at an address already taken, that
+    /// the account doesn't validate or that run out of gas are rejected, and nothing is deployed.
+    /// The fee is paid from the address of the account, see [crate::fee::FeeToken].
+    fn execute_deploy_account(
+        &self,
+        tx: &DeployAccountTransaction,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        let contract_address = deploy_account_address(tx);
+        check_nonce(state, &contract_address, &tx.nonce)?;
+        match state.get_class_hash_at(&contract_address) {
+            Ok(deployed) if deployed == cairo_felt::Felt252::new(0) => {}
+            Ok(_) => return Err("A contract is already deployed at the account address".into()),
+            Err(e) => return Err(format!("Failed to read the deployed contracts: {e}")),
+        }
+        let context = ExecutionContext {
+            block: block.clone(),
+            transaction: TransactionContext::from_deploy_account(tx, &contract_address),
+        };
+
+        let gas_limit = block.gas_limit(&tx.max_fee);
+
+        // The account is only kept if it validates the transaction deploying it
+        state.push_layer();
+        let execution = self
+            .deploy_contract(
+                &contract_address,
+                &tx.class_hash,
+                &tx.constructor_calldata,
+                &cairo_felt::Felt252::new(0),
+                gas_limit,
+                &context,
+                state,
+            )
+            .map_err(|e| format!("Deployment failed: {e}"))
+            .and_then(|deployment| {
+                let gas_left = gas_limit
+                    .checked_sub(deployment.resources.gas_consumed)
+                    .ok_or("Deployment failed: Out of gas")?;
+                let mut calldata = vec![tx.class_hash.clone(), tx.contract_address_salt.clone()];
+                calldata.extend(tx.constructor_calldata.iter().cloned());
+                let validation = validate(
+                    self,
+                    &ContractCall {
+                        contract_address: contract_address.clone(),
+                        entry_point_selector: selector_from_name(VALIDATE_DEPLOY_ENTRY_POINT),
+                        calldata,
+                        initial_gas: gas_left,
+                    },
+                    &context,
+                    state,
+                )?;
+                Ok(TransactionExecution {
+                    events: [deployment.events, validation.events].concat(),
+                    messages: [deployment.messages, validation.messages].concat(),
+                    gas_consumed: deployment.resources.gas_consumed
+                        + validation.resources.gas_consumed,
+                    gas_price: block.gas_price,
+                    ..Default::default()
+                })
+            });
+        match execution {
+            Ok(execution) => {
+                state.commit_layer();
+                Ok(execution)
+            }
+            Err(e) => {
+                state.discard_layer();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Returns the selector of an entry point: the Starknet keccak of its name.
+pub fn selector_from_name(name: &str) -> cairo_felt::Felt252 {
+    let mut hash: [u8; 32] = Keccak256::digest(name.as_bytes()).into();
+    // Starknet keccak keeps only the lowest 250 bits of the hash
+    hash[0] &= 0x03;
+    cairo_felt::Felt252::from_bytes_be(&hash)
+}
+
+/// Returns the Sierra class of the contract deployed at the given address, along with its hash.
+fn load_class(
+    state: &CachedState,
+    contract_address: &cairo_felt::Felt252,
+) -> Result<(cairo_felt::Felt252, FlattenedSierraClass), CallError> {
+    let class_hash = state
+        .get_class_hash_at(contract_address)
+        .map_err(|_| CallError::ContractNotFound)?;
+    match state.get_class(&class_hash) {
+        Ok(Some(ContractClass::Sierra(class))) => Ok((class_hash, class)),
+        _ => Err(CallError::ContractNotFound),
+    }
+}
+
+/// Resolves a selector through the entry points of a class.
+fn find_entry_point(
+    entry_points: &[SierraEntryPoint],
+    selector: &cairo_felt::Felt252,
+) -> Result<SierraEntryPoint, CallError> {
+    entry_points
+        .iter()
+        .find(|entry_point| &entry_point.selector == selector)
+        .cloned()
+        .ok_or(CallError::EntryPointNotFound)
+}
+
+/// Returns the function of the program an entry point refers to.
+fn find_function<'a>(
+    program: &'a Program,
+    entry_point: &SierraEntryPoint,
+) -> Result<&'a Function, CallError> {
+    program
+        .funcs
+        .iter()
+        .find(|function| function.id.id == entry_point.function_idx)
+        .ok_or(CallError::InvalidClass)
+}
+
+/// Entry points take their calldata as a single span of felts.
+fn felts_arg(calldata: &[cairo_felt::Felt252]) -> Vec<Vec<u32>> {
+    calldata
+        .iter()
+        .map(|felt| cairo_native::easy::felt252_bigint(felt.to_bigint()).to_vec())
+        .collect()
+}
+
+/// Entry points return a `PanicResult`, which is the last value of the output and looks like
+/// `[variant, payload]`. The payload holds the returned values when the variant is 0, or the
+/// panic data otherwise.
+fn parse_call_result(output: &Value) -> Result<Vec<cairo_felt::Felt252>, CallError> {
+    let result = output
+        .as_array()
+        .and_then(|values| values.last())
+        .and_then(Value::as_array)
+        .ok_or(CallError::Failed(vec![]))?;
+
+    let mut felts = vec![];
+    if let Some(payload) = result.get(1) {
+        collect_felts(payload, &mut felts);
+    }
+    match result.first().and_then(Value::as_u64) {
+        Some(0) => Ok(felts),
+        _ => Err(CallError::Failed(felts)),
     }
 }
 
+/// Collects the felts in the value, in order. Felts are serialized as their 8 little endian u32
+/// limbs, nested in as many arrays as the types containing them.
+fn collect_felts(value: &Value, felts: &mut Vec<cairo_felt::Felt252>) {
+    let Some(values) = value.as_array() else {
+        return;
+    };
+    if values.len() == 8 && values.iter().all(Value::is_u64) {
+        let limbs: Vec<u32> = values
+            .iter()
+            .filter_map(Value::as_u64)
+            .map(|limb| limb as u32)
+            .collect();
+        felts.push(cairo_felt::Felt252::from_bytes_be(
+            &BigUint::from_slice(&limbs).to_bytes_be(),
+        ));
+    } else {
+        values.iter().for_each(|value| collect_felts(value, felts));
+    }
+}
+
+/// The gas builtin is the first number of the output, holding what is left of the gas the
+/// program was run with.
+fn gas_consumed(output: &Value, initial_gas: u64) -> u64 {
+    output
+        .as_array()
+        .and_then(|values| values.iter().find_map(Value::as_u64))
+        .map_or(0, |gas_left| initial_gas.saturating_sub(gas_left))
+}
+
+#[cfg(test)]
 fn get_input_value_cairo_native(n: usize) -> Vec<u32> {
     let mut digits = BigUint::from(n).to_u32_digits();
     digits.resize(8, 0);
     digits
 }
 
+#[cfg(test)]
 fn execute_cairo_native_program(
     program: &Program,
     entry_point: &str,
     args: Vec<Vec<u32>>,
-    include_syscall_handler: bool,
+    syscall_handler: Option<&SyscallHandler>,
 ) -> Value {
-    let mut writer: Vec<u8> = Vec::new();
-    let mut res = serde_json::Serializer::new(&mut writer);
     // use params variable that is a deserializable variable
     let entry_point = match program
         .funcs
@@ -100,77 +464,15 @@ fn execute_cairo_native_program(
             panic!("No entry point found");
         }
     };
-
-    // Compile the program.
-    let (context, mut module, registry, mut metadata) =
-        cairo_native::easy::create_compiler(program).unwrap();
-
-    // Make the Starknet syscall handler available.
-    metadata
-        .insert(SyscallHandlerMeta::new(&syscall_handler::SyscallHandler))
-        .unwrap();
-
-    let required_initial_gas =
-        cairo_native::easy::get_required_initial_gas(program, &mut metadata, entry_point);
-
-    cairo_native::compile::<CoreType, CoreLibfunc>(
-        &context,
-        &module,
-        program,
-        &registry,
-        &mut metadata,
-        None,
-    )
-    .unwrap();
-
-    // Lower to LLVM.
-    cairo_native::easy::run_passes(&context, &mut module).unwrap();
-
-    // Create the JIT engine.
-    let engine = cairo_native::easy::create_engine(&module);
-
-    let params_input = if include_syscall_handler {
-        let system = metadata
-            .get::<SyscallHandlerMeta>()
-            .unwrap()
-            .as_ptr()
-            .addr();
-        // pedersen, range check, gas, system
-        json!([null, null, u64::MAX, system, [args]])
-    } else {
-        // _, gas, params
-        let mut params = json!([null, u64::MAX]);
-        for arg in args {
-            params.as_array_mut().unwrap().push(arg.into());
-        }
-        params
-    };
-
-    cairo_native::execute(
-        &engine,
-        &registry,
-        &entry_point.id,
-        params_input,
-        &mut res,
-        required_initial_gas,
-    )
-    .unwrap();
-
-    // The output expected as a string will be a json that looks like this:
-    // [null,9000,[0,[[55,0,0,0,0,0,0,0]]]]
-    let deserialized_result: String = String::from_utf8(writer).unwrap();
-    let deserialized_value = serde_json::from_str::<serde_json::Value>(&deserialized_result)
-        .expect("Failed to deserialize result");
-    println!("{}", deserialized_value);
-
-    deserialized_value
+    program_cache::CompiledProgram::new(program.clone())
+        .and_then(|mut program| program.run(&entry_point.id, args, u64::MAX, syscall_handler))
+        .unwrap()
 }
 
 #[cfg(test)]
 mod test {
     use std::{fs, io, path::Path, sync::Arc};
 
-    use cairo_lang_compiler::CompilerConfig;
     use cairo_lang_sierra::{
         extensions::core::{CoreLibfunc, CoreType},
         ProgramParser,
@@ -178,34 +480,191 @@ mod test {
     use cairo_native::metadata::syscall_handler::SyscallHandlerMeta;
     use serde_json::json;
 
-    use crate::cairo_native_engine::{execute_cairo_native_program, get_input_value_cairo_native};
+    use crate::account::{deploy_account_address, multicall_calldata, public_key, sign, Call};
+    use crate::cairo_native_engine::program_cache::{ProgramCache, DEFAULT_PROGRAM_CACHE_SIZE};
+    use crate::cairo_native_engine::{
+        execute_cairo_native_program, find_entry_point, gas_consumed, get_input_value_cairo_native,
+        parse_call_result, selector_from_name, CairoNativeEngine, MAX_CALL_DEPTH,
+    };
+    use crate::contract_address::calculate_contract_address;
+    use crate::contract_class::{compile_contract, sierra_program};
+    use crate::engine::{
+        revert_reason, BlockContext, CallError, ContractCall, ExecutionContext, ExecutionEngine,
+        MAX_CALL_GAS,
+    };
+    use crate::state::{CachedState, StateReader};
+    use types::{
+        ChainId, ContractClass, DeclareTransaction, DeclareTransactionV2, DeployAccountTransaction,
+        InvokeTransactionV1,
+    };
 
     use super::syscall_handler;
 
+    /// Private key of the accounts deployed by the tests.
+    const PRIVATE_KEY: u64 = 0x1234;
+
+    /// Declares the contract at the given path and deploys it at address 1.
+    fn deploy_contract(engine: &CairoNativeEngine, path: &str) -> CachedState {
+        let mut state = CachedState::default();
+        declare_and_deploy(engine, path, 1, &[], &mut state);
+        state
+    }
+
+    /// Declares the contract at the given path and deploys it at the given address, returning
+    /// the hash of its class.
+    fn declare_and_deploy(
+        engine: &CairoNativeEngine,
+        path: &str,
+        contract_address: u64,
+        constructor_calldata: &[cairo_felt::Felt252],
+        state: &mut CachedState,
+    ) -> cairo_felt::Felt252 {
+        let class = ContractClass::Sierra(compile_contract(Path::new(path)).unwrap());
+        let class_hash = class.class_hash();
+        state.declare_class(class_hash.clone(), class);
+        engine
+            .deploy_contract(
+                &cairo_felt::Felt252::new(contract_address),
+                &class_hash,
+                constructor_calldata,
+                &cairo_felt::Felt252::new(0),
+                MAX_CALL_GAS,
+                &ExecutionContext::default(),
+                state,
+            )
+            .unwrap();
+        class_hash
+    }
+
+    /// Deploys an account of the bundled class at the given address, which checks signatures
+    /// against the public key of [PRIVATE_KEY].
+    fn deploy_account(engine: &CairoNativeEngine, contract_address: u64, state: &mut CachedState) {
+        let public_key = public_key(&cairo_felt::Felt252::new(PRIVATE_KEY));
+        declare_and_deploy(
+            engine,
+            "../cairo_programs/account.cairo",
+            contract_address,
+            &[public_key],
+            state,
+        );
+    }
+
+    /// Returns the transaction the account at the given address sends to make the given call,
+    /// signed with [PRIVATE_KEY].
+    fn signed_invoke(
+        sender_address: u64,
+        nonce: u64,
+        max_fee: u64,
+        call: Call,
+    ) -> InvokeTransactionV1 {
+        let mut tx = InvokeTransactionV1 {
+            transaction_hash: cairo_felt::Felt252::new(0),
+            max_fee: cairo_felt::Felt252::new(max_fee),
+            signature: vec![],
+            nonce: cairo_felt::Felt252::new(nonce),
+            sender_address: cairo_felt::Felt252::new(sender_address),
+            calldata: multicall_calldata(&[call]),
+        };
+        tx.transaction_hash = tx.calculate_hash(&ChainId::default().to_felt());
+        tx.signature = sign(&cairo_felt::Felt252::new(PRIVATE_KEY), &tx.transaction_hash);
+        tx
+    }
+
     #[test]
     fn fib_10_cairo_native() {
-        let a = super::get_input_value_cairo_native(0_usize);
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/fib_contract.cairo");
 
-        let b = super::get_input_value_cairo_native(1_usize);
+        let fib_10 = engine
+            .call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(1),
+                    entry_point_selector: selector_from_name("fib"),
+                    calldata: vec![
+                        cairo_felt::Felt252::new(0),
+                        cairo_felt::Felt252::new(1),
+                        cairo_felt::Felt252::new(10),
+                    ],
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(vec![cairo_felt::Felt252::new(55)], fib_10.retdata);
+        assert!(fib_10.resources.gas_consumed > 0);
+    }
 
-        let n = super::get_input_value_cairo_native(10_usize);
+    #[test]
+    fn compiled_programs_are_cached() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/fib_contract.cairo");
+        let fib = |n: u64, state: &mut CachedState| {
+            engine
+                .call(
+                    &ContractCall {
+                        contract_address: cairo_felt::Felt252::new(1),
+                        entry_point_selector: selector_from_name("fib"),
+                        calldata: vec![
+                            cairo_felt::Felt252::new(0),
+                            cairo_felt::Felt252::new(1),
+                            cairo_felt::Felt252::new(n),
+                        ],
+                        initial_gas: MAX_CALL_GAS,
+                    },
+                    &ExecutionContext::default(),
+                    state,
+                )
+                .map(|call_info| call_info.retdata)
+        };
 
-        let sierra_program = cairo_lang_compiler::compile_cairo_project_at_path(
-            Path::new("../cairo_programs/fib_contract.cairo"),
-            CompilerConfig {
-                replace_ids: true,
-                ..Default::default()
-            },
-        )
-        .unwrap();
+        assert_eq!(Ok(vec![cairo_felt::Felt252::new(55)]), fib(10, &mut state));
+        assert_eq!(Ok(vec![cairo_felt::Felt252::new(89)]), fib(11, &mut state));
+        assert_eq!(1, engine.programs.len());
+    }
 
-        let fib_10 = execute_cairo_native_program(
-            &sierra_program,
-            "fib_contract::fib_contract::Fibonacci::fib",
-            vec![a, b, n],
-            false,
-        );
-        assert_eq!(fib_10[2][1][0][0].as_u64().unwrap(), 55);
+    #[test]
+    fn least_recently_used_program_is_evicted() {
+        let load = |path: &str| {
+            let class = compile_contract(Path::new(path)).unwrap();
+            move || sierra_program(&class)
+        };
+        let fib_hash = cairo_felt::Felt252::new(1);
+        let fact_hash = cairo_felt::Felt252::new(2);
+        let cache = ProgramCache::new(1);
+
+        let fib = cache
+            .take_or_compile(&fib_hash, load("../cairo_programs/fib_contract.cairo"))
+            .unwrap();
+        cache.put_back(&fib_hash, fib);
+        let fact = cache
+            .take_or_compile(&fact_hash, load("../cairo_programs/fact_contract.cairo"))
+            .unwrap();
+        cache.put_back(&fact_hash, fact);
+        assert_eq!(1, cache.len());
+
+        // The factorial program is cached, so it is not loaded again
+        let fact = cache
+            .take_or_compile(&fact_hash, || -> anyhow::Result<_> {
+                panic!("Program compiled twice")
+            })
+            .unwrap();
+        // While it is taken, the class gets another instance, kept along with the first one
+        let reentrant = cache
+            .take_or_compile(&fact_hash, load("../cairo_programs/fact_contract.cairo"))
+            .unwrap();
+        cache.put_back(&fact_hash, reentrant);
+        cache.put_back(&fact_hash, fact);
+        assert_eq!(1, cache.len());
+        for _ in 0..2 {
+            cache
+                .take_or_compile(&fact_hash, || -> anyhow::Result<_> {
+                    panic!("Program compiled three times")
+                })
+                .unwrap();
+        }
     }
 
     #[test]
@@ -224,8 +683,9 @@ mod test {
             cairo_native::easy::create_compiler(&program).unwrap();
 
         // Make the Starknet syscall handler available.
+        let syscall_handler = syscall_handler::SyscallHandler::default();
         metadata
-            .insert(SyscallHandlerMeta::new(&syscall_handler::SyscallHandler))
+            .insert(SyscallHandlerMeta::new(&syscall_handler))
             .unwrap();
 
         let required_initial_gas =
@@ -294,6 +754,7 @@ mod test {
         let program_src = fs::read_to_string("../cairo_programs/erc20.sierra").unwrap();
         let program = Arc::new(ProgramParser::new().parse(&program_src).unwrap());
 
+        let syscall_handler = syscall_handler::SyscallHandler::default();
         let _output = execute_cairo_native_program(
             &program,
             "erc20::erc20::erc_20::__constructor::constructor",
@@ -305,29 +766,617 @@ mod test {
                 cairo_native::easy::felt252_bigint(4).to_vec(),         // contract address
                 cairo_native::easy::felt252_bigint(6).to_vec(),         // ??
             ],
-            true,
+            Some(&syscall_handler),
         );
+
+        // The constructor mints the initial supply, emitting a single `Transfer` event.
+        assert_eq!(syscall_handler.take_events().len(), 1);
+
+        // The token metadata and the balance of the recipient are written to the storage.
+        let state = syscall_handler.into_state();
+        assert!(!state.state_diff().storage.is_empty());
     }
     #[test]
     fn fact_10_cairo_native() {
-        let n = super::get_input_value_cairo_native(10_usize);
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/fact_contract.cairo");
+        deploy_account(&engine, 2, &mut state);
+
+        let invoke = |contract_address: u64| {
+            let call = Call {
+                to: cairo_felt::Felt252::new(contract_address),
+                selector: selector_from_name("fact"),
+                calldata: vec![cairo_felt::Felt252::new(10)],
+            };
+            signed_invoke(2, 0, 0, call)
+        };
+
+        let fact_10 = engine
+            .execute_invoke(&invoke(1), &BlockContext::default(), &mut state)
+            .unwrap();
+        assert_eq!(None, fact_10.revert_reason);
+
+        let missing = engine
+            .execute_invoke(&invoke(3), &BlockContext::default(), &mut state)
+            .unwrap();
+        assert!(missing.revert_reason.is_some());
+    }
+
+    #[test]
+    fn accounts_only_send_the_transactions_they_validate() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/fact_contract.cairo");
+        deploy_account(&engine, 2, &mut state);
+        let fact_10 = Call {
+            to: cairo_felt::Felt252::new(1),
+            selector: selector_from_name("fact"),
+            calldata: vec![cairo_felt::Felt252::new(10)],
+        };
+        let block = BlockContext::default();
+
+        let valid = signed_invoke(2, 0, 0, fact_10.clone());
+        assert!(engine.execute_invoke(&valid, &block, &mut state).is_ok());
+
+        let forged = InvokeTransactionV1 {
+            signature: sign(&cairo_felt::Felt252::new(0x4321), &valid.transaction_hash),
+            ..valid.clone()
+        };
+        assert!(engine.execute_invoke(&forged, &block, &mut state).is_err());
+
+        state.increment_nonce(&cairo_felt::Felt252::new(2)).unwrap();
+        assert_eq!(
+            Some("Invalid nonce: expected 1, got 0".to_string()),
+            engine.execute_invoke(&valid, &block, &mut state).err()
+        );
+        let next = signed_invoke(2, 1, 0, fact_10.clone());
+        assert!(engine.execute_invoke(&next, &block, &mut state).is_ok());
+
+        // There is no account to validate transactions sent from elsewhere
+        let no_account = signed_invoke(3, 0, 0, fact_10);
+        assert!(engine
+            .execute_invoke(&no_account, &block, &mut state)
+            .is_err());
+    }
+
+    #[test]
+    fn accounts_only_declare_the_classes_they_validate() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        deploy_account(&engine, 2, &mut state);
+        let block = BlockContext::default();
+        let signed_declare = |nonce: u64| {
+            let mut tx = DeclareTransactionV2 {
+                transaction_hash: cairo_felt::Felt252::new(0),
+                max_fee: cairo_felt::Felt252::new(0),
+                signature: vec![],
+                nonce: cairo_felt::Felt252::new(nonce),
+                class_hash: cairo_felt::Felt252::new(0xc1a55),
+                sender_address: cairo_felt::Felt252::new(2),
+                compiled_class_hash: cairo_felt::Felt252::new(0),
+            };
+            tx.transaction_hash = tx.calculate_hash(&ChainId::default().to_felt());
+            tx.signature = sign(&cairo_felt::Felt252::new(PRIVATE_KEY), &tx.transaction_hash);
+            tx
+        };
+
+        let valid = signed_declare(0);
+        assert!(engine
+            .validate_declare(&DeclareTransaction::V2(valid.clone()), &block, &mut state)
+            .is_ok());
+
+        let forged = DeclareTransactionV2 {
+            signature: sign(&cairo_felt::Felt252::new(0x4321), &valid.transaction_hash),
+            ..valid
+        };
+        assert!(engine
+            .validate_declare(&DeclareTransaction::V2(forged), &block, &mut state)
+            .is_err());
+
+        assert_eq!(
+            Some("Invalid nonce: expected 0, got 1".to_string()),
+            engine
+                .validate_declare(
+                    &DeclareTransaction::V2(signed_declare(1)),
+                    &block,
+                    &mut state
+                )
+                .err()
+        );
+    }
+
+    #[test]
+    fn deploy_account_transactions_deploy_the_accounts_they_validate() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        let class = ContractClass::Sierra(
+            compile_contract(Path::new("../cairo_programs/account.cairo")).unwrap(),
+        );
+        let class_hash = class.class_hash();
+        state.declare_class(class_hash.clone(), class);
+        let public_key = public_key(&cairo_felt::Felt252::new(PRIVATE_KEY));
+        let mut tx = DeployAccountTransaction {
+            transaction_hash: cairo_felt::Felt252::new(0),
+            max_fee: cairo_felt::Felt252::new(MAX_CALL_GAS),
+            signature: vec![],
+            nonce: cairo_felt::Felt252::new(0),
+            contract_address_salt: cairo_felt::Felt252::new(7),
+            constructor_calldata: vec![public_key.clone()],
+            class_hash: class_hash.clone(),
+        };
+        tx.transaction_hash = tx.calculate_hash(&ChainId::default().to_felt());
+        tx.signature = sign(&cairo_felt::Felt252::new(PRIVATE_KEY), &tx.transaction_hash);
+        let contract_address = deploy_account_address(&tx);
+        let block = BlockContext {
+            gas_price: 1,
+            ..Default::default()
+        };
+
+        // Nothing is deployed unless the account validates the transaction
+        let forged = DeployAccountTransaction {
+            signature: sign(&cairo_felt::Felt252::new(0x4321), &tx.transaction_hash),
+            ..tx.clone()
+        };
+        assert!(engine
+            .execute_deploy_account(&forged, &block, &mut state)
+            .is_err());
+        assert_eq!(
+            cairo_felt::Felt252::new(0),
+            state.get_class_hash_at(&contract_address).unwrap()
+        );
+
+        // or if its maximal fee doesn't pay for the deployment
+        let mut underpaid = DeployAccountTransaction {
+            max_fee: cairo_felt::Felt252::new(1),
+            ..tx.clone()
+        };
+        underpaid.transaction_hash = underpaid.calculate_hash(&ChainId::default().to_felt());
+        underpaid.signature = sign(
+            &cairo_felt::Felt252::new(PRIVATE_KEY),
+            &underpaid.transaction_hash,
+        );
+        assert!(engine
+            .execute_deploy_account(&underpaid, &block, &mut state)
+            .is_err());
+
+        let execution = engine
+            .execute_deploy_account(&tx, &block, &mut state)
+            .unwrap();
+        assert!(execution.gas_consumed > 0);
+        assert_eq!(execution.gas_consumed, execution.fee());
+        assert_eq!(
+            class_hash,
+            state.get_class_hash_at(&contract_address).unwrap()
+        );
+        let call = ContractCall {
+            contract_address,
+            entry_point_selector: selector_from_name("get_public_key"),
+            calldata: vec![],
+            initial_gas: MAX_CALL_GAS,
+        };
+        assert_eq!(
+            vec![public_key],
+            engine
+                .call(&call, &ExecutionContext::default(), &mut state)
+                .unwrap()
+                .retdata
+        );
+
+        assert!(engine
+            .execute_deploy_account(&tx, &block, &mut state)
+            .is_err());
+    }
+
+    #[test]
+    fn transactions_only_get_the_gas_they_pay_for() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/fib_contract.cairo");
+        deploy_account(&engine, 2, &mut state);
+        let block = BlockContext {
+            gas_price: 2,
+            ..Default::default()
+        };
+        let invoke = |max_fee: u64| {
+            let call = Call {
+                to: cairo_felt::Felt252::new(1),
+                selector: selector_from_name("fib"),
+                calldata: vec![
+                    cairo_felt::Felt252::new(0),
+                    cairo_felt::Felt252::new(1),
+                    cairo_felt::Felt252::new(1000),
+                ],
+            };
+            signed_invoke(2, 0, max_fee, call)
+        };
+
+        let paid = engine
+            .execute_invoke(&invoke(u64::MAX), &block, &mut state)
+            .unwrap();
+        assert_eq!(None, paid.revert_reason);
+        assert!(paid.gas_consumed > 0);
+        assert_eq!(paid.gas_consumed * 2, paid.fee());
+
+        // Running out of gas costs all the gas the maximal fee pays for
+        let max_fee = paid.fee() / 2;
+        let unpaid = engine
+            .execute_invoke(&invoke(max_fee), &block, &mut state)
+            .unwrap();
+        assert!(unpaid.revert_reason.is_some());
+        assert_eq!(max_fee / 2, unpaid.gas_consumed);
+    }
 
-        let sierra_program = cairo_lang_compiler::compile_cairo_project_at_path(
-            Path::new("../cairo_programs/fact_contract.cairo"),
-            CompilerConfig {
-                replace_ids: true,
-                ..Default::default()
+    #[test]
+    fn failed_calls_keep_no_writes() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let class = ContractClass::Sierra(
+            compile_contract(Path::new("../cairo_programs/erc20.cairo")).unwrap(),
+        );
+        let class_hash = class.class_hash();
+        let mut state = CachedState::default();
+        state.declare_class(class_hash.clone(), class);
+        let deployment = engine
+            .deploy_contract(
+                &cairo_felt::Felt252::new(1),
+                &class_hash,
+                &[
+                    cairo_felt::Felt252::from_bytes_be(b"name"),
+                    cairo_felt::Felt252::from_bytes_be(b"symbol"),
+                    cairo_felt::Felt252::new(0),
+                    cairo_felt::Felt252::new(1000),
+                    cairo_felt::Felt252::new(0),
+                    cairo_felt::Felt252::new(2),
+                ],
+                &cairo_felt::Felt252::new(0),
+                MAX_CALL_GAS,
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(1, deployment.events.len());
+        assert_eq!(
+            Some(&class_hash),
+            deployment
+                .state_diff
+                .class_hashes
+                .get(&cairo_felt::Felt252::new(1))
+        );
+        let storage = state.state_diff().storage.clone();
+
+        // The caller holds no tokens
+        let transfer = engine.call(
+            &ContractCall {
+                contract_address: cairo_felt::Felt252::new(1),
+                entry_point_selector: selector_from_name("transfer"),
+                calldata: vec![
+                    cairo_felt::Felt252::new(3),
+                    cairo_felt::Felt252::new(1),
+                    cairo_felt::Felt252::new(0),
+                ],
+                initial_gas: MAX_CALL_GAS,
             },
-        )
-        .unwrap();
+            &ExecutionContext::default(),
+            &mut state,
+        );
+        assert_eq!("u256_sub Overflow", transfer.unwrap_err().to_string());
+        assert_eq!(storage, state.state_diff().storage);
+    }
+
+    fn felts(values: &[u64]) -> Vec<cairo_felt::Felt252> {
+        values
+            .iter()
+            .map(|n| cairo_felt::Felt252::new(*n))
+            .collect()
+    }
+
+    #[test]
+    fn contracts_call_other_contracts_as_their_caller() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        // The forwarder holds the 1000 tokens minted
+        let mut constructor_calldata = vec![
+            cairo_felt::Felt252::from_bytes_be(b"name"),
+            cairo_felt::Felt252::from_bytes_be(b"symbol"),
+        ];
+        constructor_calldata.extend(felts(&[0, 1000, 0, 2]));
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/erc20.cairo",
+            1,
+            &constructor_calldata,
+            &mut state,
+        );
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/forwarder.cairo",
+            2,
+            &[],
+            &mut state,
+        );
+
+        let mut calldata = felts(&[1]);
+        calldata.push(selector_from_name("transfer"));
+        calldata.extend(felts(&[3, 3, 100, 0]));
+        let transfer = engine
+            .call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(2),
+                    entry_point_selector: selector_from_name("forward"),
+                    calldata,
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(1, transfer.events.len());
+        assert_eq!(cairo_felt::Felt252::new(1), transfer.events[0].from_address);
+
+        let balance = engine
+            .call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(1),
+                    entry_point_selector: selector_from_name("balance_of"),
+                    calldata: felts(&[3]),
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(felts(&[100, 0]), balance.retdata);
+    }
+
+    #[test]
+    fn contracts_call_into_their_own_class() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/fib_contract.cairo",
+            1,
+            &[],
+            &mut state,
+        );
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/forwarder.cairo",
+            2,
+            &[],
+            &mut state,
+        );
+
+        // The forwarder forwards to itself the call to the Fibonacci contract
+        let mut calldata = felts(&[2]);
+        calldata.push(selector_from_name("forward"));
+        calldata.extend(felts(&[6, 1]));
+        calldata.push(selector_from_name("fib"));
+        calldata.extend(felts(&[3, 0, 1, 10]));
+        let fib_10 = engine
+            .call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(2),
+                    entry_point_selector: selector_from_name("forward"),
+                    calldata,
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(felts(&[2, 1, 55]), fib_10.retdata);
+    }
+
+    #[test]
+    fn calls_nested_too_deep_fail() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/fib_contract.cairo",
+            1,
+            &[],
+            &mut state,
+        );
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/forwarder.cairo",
+            2,
+            &[],
+            &mut state,
+        );
+
+        // The forwarder forwards to itself the given times the call to the Fibonacci contract
+        let mut forward_fib = |forwards: u64| {
+            let mut calldata = felts(&[1]);
+            calldata.push(selector_from_name("fib"));
+            calldata.extend(felts(&[3, 0, 1, 10]));
+            for _ in 0..forwards {
+                let mut forward = felts(&[2]);
+                forward.push(selector_from_name("forward"));
+                forward.extend(felts(&[calldata.len() as u64]));
+                forward.extend(calldata);
+                calldata = forward;
+            }
+            engine.call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(2),
+                    entry_point_selector: selector_from_name("forward"),
+                    calldata,
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                &mut state,
+            )
+        };
+
+        // The outermost forward and the Fibonacci call are nested calls too
+        let max_forwards = MAX_CALL_DEPTH as u64 - 2;
+        assert!(forward_fib(max_forwards).is_ok());
+        assert!(matches!(
+            forward_fib(max_forwards + 1),
+            Err(CallError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn deployed_contracts_get_derived_addresses() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = CachedState::default();
+        let fib_hash = declare_and_deploy(
+            &engine,
+            "../cairo_programs/fib_contract.cairo",
+            1,
+            &[],
+            &mut state,
+        );
+        declare_and_deploy(
+            &engine,
+            "../cairo_programs/forwarder.cairo",
+            2,
+            &[],
+            &mut state,
+        );
+
+        let deploy = ContractCall {
+            contract_address: cairo_felt::Felt252::new(2),
+            entry_point_selector: selector_from_name("deploy"),
+            calldata: vec![
+                fib_hash.clone(),
+                cairo_felt::Felt252::new(5),
+                cairo_felt::Felt252::new(0),
+            ],
+            initial_gas: MAX_CALL_GAS,
+        };
+        let deployment = engine
+            .call(&deploy, &ExecutionContext::default(), &mut state)
+            .unwrap();
+        let contract_address = calculate_contract_address(
+            &cairo_felt::Felt252::new(5),
+            &fib_hash,
+            &[],
+            &cairo_felt::Felt252::new(2),
+        );
+        assert_eq!(vec![contract_address.clone()], deployment.retdata);
+        assert_eq!(
+            fib_hash,
+            state.get_class_hash_at(&contract_address).unwrap()
+        );
+
+        assert_eq!(
+            "CONTRACT_ADDRESS_UNAVAILABLE",
+            engine
+                .call(&deploy, &ExecutionContext::default(), &mut state)
+                .unwrap_err()
+                .to_string()
+        );
+    }
 
-        let fact_10 = execute_cairo_native_program(
-            &sierra_program,
-            "fact_contract::fact_contract::Factorial::fact",
-            vec![n],
-            false,
+    #[test]
+    fn contracts_see_the_block_and_transaction_they_run_in() {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let mut state = deploy_contract(&engine, "../cairo_programs/execution_info.cairo");
+        let mut context = ExecutionContext::default();
+        context.block.block_number = 12;
+        context.block.block_timestamp = 1_690_000_000;
+        context.transaction.transaction_hash = cairo_felt::Felt252::new(0xabc);
+        let call = |entry_point: &str, calldata: &[u64], state: &mut CachedState| {
+            engine.call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(1),
+                    entry_point_selector: selector_from_name(entry_point),
+                    calldata: felts(calldata),
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &context,
+                state,
+            )
+        };
+
+        assert_eq!(
+            felts(&[1_690_000_000]),
+            call("block_timestamp", &[], &mut state).unwrap().retdata
+        );
+        assert_eq!(
+            felts(&[0xabc]),
+            call("transaction_hash", &[], &mut state).unwrap().retdata
+        );
+        // Only blocks at least 10 blocks old can be read
+        assert_eq!(
+            "Block number out of range",
+            call("block_hash", &[5], &mut state)
+                .unwrap_err()
+                .to_string()
         );
-        assert_eq!(fact_10[2][1][0][0].as_u64().unwrap(), 3628800);
+    }
+
+    #[test]
+    fn selector_from_name_matches_starknet() {
+        assert_eq!(
+            cairo_felt::Felt252::parse_bytes(
+                b"83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e",
+                16
+            )
+            .unwrap(),
+            selector_from_name("transfer")
+        );
+    }
+
+    #[test]
+    fn find_erc20_external_entry_point() {
+        let class = compile_contract(Path::new("../cairo_programs/erc20.cairo")).unwrap();
+        let external = &class.entry_points_by_type.external;
+
+        assert_eq!(
+            selector_from_name("balance_of"),
+            find_entry_point(external, &selector_from_name("balance_of"))
+                .unwrap()
+                .selector
+        );
+        assert_eq!(
+            Err(CallError::EntryPointNotFound),
+            find_entry_point(external, &selector_from_name("mint")).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn parse_call_result_returns_felts() {
+        let felt = |n: u64| json!([n, 0, 0, 0, 0, 0, 0, 0]);
+        let ok = json!([null, null, 9000, 1, [0, [[[felt(1), felt(2)]]]]]);
+        let panicked = json!([null, null, 9000, 1, [1, [[[felt(3)]]]]]);
+
+        assert_eq!(
+            Ok(vec![
+                cairo_felt::Felt252::new(1),
+                cairo_felt::Felt252::new(2)
+            ]),
+            parse_call_result(&ok)
+        );
+        assert_eq!(
+            Err(CallError::Failed(vec![cairo_felt::Felt252::new(3)])),
+            parse_call_result(&panicked)
+        );
+    }
+
+    #[test]
+    fn gas_consumed_and_revert_reason_are_read_from_the_output() {
+        let output = json!([null, null, 3800, 1, [1, [[]]]]);
+        assert_eq!(1200, gas_consumed(&output, 5000));
+
+        let panic_data = vec![
+            cairo_felt::Felt252::from_bytes_be(b"u256_sub Overflow"),
+            cairo_felt::Felt252::new(0x1f),
+        ];
+        assert_eq!("u256_sub Overflow, 0x1f", revert_reason(&panic_data));
     }
 
     #[test]
@@ -0,0 +1,282 @@
+use super::syscall_handler::SyscallHandler;
+use crate::engine::CallError;
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::FunctionId,
+    program::Program,
+    program_registry::ProgramRegistry,
+};
+use cairo_native::metadata::{syscall_handler::SyscallHandlerMeta, MetadataStorage};
+use log::debug;
+use melior::ExecutionEngine;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// How many compiled programs are kept in memory by default.
+pub const DEFAULT_PROGRAM_CACHE_SIZE: usize = 64;
+
+/// Builtins passed to the entry points. The runner provides them, so they are given as null.
+const BUILTINS: [&str; 6] = [
+    "Pedersen",
+    "RangeCheck",
+    "Bitwise",
+    "EcOp",
+    "Poseidon",
+    "SegmentArena",
+];
+
+/// A program compiled to machine code by Cairo Native, ready to run any of its functions.
+pub struct CompiledProgram {
+    program: Program,
+    registry: ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: MetadataStorage,
+    engine: ExecutionEngine,
+    /// Handler the program was compiled with. Each run passes its own one to the program.
+    _syscall_handler: Box<SyscallHandler<'static>>,
+}
+
+// SAFETY: the JIT engine and the metadata are only used through `&mut self`, so compiled
+// programs are never run by two threads at once.
+unsafe impl Send for CompiledProgram {}
+
+impl CompiledProgram {
+    /// Compiles the program down to machine code. Programs that can't be compiled come from
+    /// invalid classes.
+    pub fn new(program: Program) -> Result<Self, CallError> {
+        let (context, mut module, registry, mut metadata) =
+            cairo_native::easy::create_compiler(&program).map_err(|_| CallError::InvalidClass)?;
+
+        // Make the Starknet syscall handler available.
+        let syscall_handler = Box::<SyscallHandler>::default();
+        metadata
+            .insert(SyscallHandlerMeta::new(syscall_handler.as_ref()))
+            .ok_or(CallError::InvalidClass)?;
+
+        cairo_native::compile::<CoreType, CoreLibfunc>(
+            &context,
+            &module,
+            &program,
+            &registry,
+            &mut metadata,
+            None,
+        )
+        .map_err(|_| CallError::InvalidClass)?;
+
+        // Lower to LLVM.
+        cairo_native::easy::run_passes(&context, &mut module)
+            .map_err(|_| CallError::InvalidClass)?;
+
+        // Create the JIT engine.
+        let engine = cairo_native::easy::create_engine(&module);
+
+        Ok(Self {
+            program,
+            registry,
+            metadata,
+            engine,
+            _syscall_handler: syscall_handler,
+        })
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Runs a function of the program with the given gas. Functions run with a syscall handler
+    /// are contract entry points, which take their arguments as a single span. Failures of the
+    /// runner itself, even panics, fail the run rather than the caller.
+    pub fn run(
+        &mut self,
+        function_id: &FunctionId,
+        args: Vec<Vec<u32>>,
+        initial_gas: u64,
+        syscall_handler: Option<&SyscallHandler<'_>>,
+    ) -> Result<Value, CallError> {
+        let mut writer: Vec<u8> = Vec::new();
+        let mut res = serde_json::Serializer::new(&mut writer);
+
+        let entry_point = self
+            .program
+            .funcs
+            .iter()
+            .find(|function| &function.id == function_id)
+            .ok_or(CallError::EntryPointNotFound)?;
+        let required_initial_gas = cairo_native::easy::get_required_initial_gas(
+            &self.program,
+            &mut self.metadata,
+            entry_point,
+        );
+
+        // The handler has to outlive the execution, as the program is given a pointer to it
+        let syscall_handler_meta = syscall_handler.map(SyscallHandlerMeta::new);
+        let params_input = match &syscall_handler_meta {
+            Some(syscall_handler_meta) => {
+                let system = syscall_handler_meta.as_ptr().addr();
+                // Contract entry points take the builtins they use, the gas, the system and the
+                // calldata
+                let params = entry_point
+                    .signature
+                    .param_types
+                    .iter()
+                    .map(|param_type| {
+                        let generic_id = self
+                            .program
+                            .type_declarations
+                            .iter()
+                            .find(|declaration| &declaration.id == param_type)
+                            .map(|declaration| declaration.long_id.generic_id.0.as_str());
+                        match generic_id {
+                            Some("GasBuiltin") => json!(initial_gas),
+                            Some("System") => json!(system),
+                            Some(builtin) if BUILTINS.contains(&builtin) => Value::Null,
+                            _ => json!([args]),
+                        }
+                    })
+                    .collect();
+                Value::Array(params)
+            }
+            None => {
+                // _, gas, params
+                let mut params = json!([null, initial_gas]);
+                if let Some(params) = params.as_array_mut() {
+                    params.extend(args.into_iter().map(Value::from));
+                }
+                params
+            }
+        };
+
+        let executed = panic::catch_unwind(AssertUnwindSafe(|| {
+            cairo_native::execute(
+                &self.engine,
+                &self.registry,
+                function_id,
+                params_input,
+                &mut res,
+                required_initial_gas,
+            )
+        }));
+        if !matches!(executed, Ok(Ok(_))) {
+            return Err(execution_failed());
+        }
+
+        // The output expected as a string will be a json that looks like this:
+        // [null,9000,[0,[[55,0,0,0,0,0,0,0]]]]
+        let deserialized_value =
+            serde_json::from_slice::<Value>(&writer).map_err(|_| execution_failed())?;
+        debug!("{}", deserialized_value);
+
+        Ok(deserialized_value)
+    }
+}
+
+/// Runs that didn't get to return a result fail with a short string telling so.
+fn execution_failed() -> CallError {
+    CallError::Failed(vec![cairo_felt::Felt252::from_bytes_be(
+        b"Execution failed",
+    )])
+}
+
+/// Compiled programs of the contract classes, keyed by class hash, so classes are compiled once
+/// rather than on every call. When full, the least recently used class is evicted.
+///
+/// A program can't be run again until it returns, so each class keeps a pool of compiled
+/// instances. Contracts calling into a class further up the call stack, themselves included,
+/// take another instance, which is compiled the first time and then kept along with the others.
+///
+/// Programs are only kept in memory: the Cairo Native version we use compiles them just in time,
+/// and can't load shared objects compiled ahead of time, so restarted nodes compile every class
+/// again. Caching them on disk is left for when we upgrade to a version that can.
+pub struct ProgramCache {
+    capacity: usize,
+    entries: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    programs: HashMap<cairo_felt::Felt252, CacheEntry>,
+    /// Counts the lookups, to know which class was used the longest ago
+    lookups: u64,
+}
+
+struct CacheEntry {
+    /// Instances of the program not running at the moment
+    idle: Vec<CompiledProgram>,
+    last_used: u64,
+}
+
+impl ProgramCache {
+    /// Creates a cache keeping the programs of up to `capacity` classes. A capacity of zero
+    /// disables it.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Takes an idle compiled program of the given class out of the cache, compiling the program
+    /// `load` returns if there is none. Give it back with [ProgramCache::put_back] once it is done
+    /// running. Compilation happens without holding the cache, so other classes can be run
+    /// meanwhile.
+    pub fn take_or_compile<E: From<CallError>>(
+        &self,
+        class_hash: &cairo_felt::Felt252,
+        load: impl FnOnce() -> Result<Program, E>,
+    ) -> Result<CompiledProgram, E> {
+        if let Some(program) = self.take(class_hash) {
+            return Ok(program);
+        }
+        Ok(CompiledProgram::new(load()?)?)
+    }
+
+    /// Puts a program taken out of the cache back into the pool of its class, evicting the
+    /// least recently used class if there is no room for a new one.
+    pub fn put_back(&self, class_hash: &cairo_felt::Felt252, program: CompiledProgram) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.programs.contains_key(class_hash) && entries.programs.len() >= self.capacity {
+            let least_recently_used = entries
+                .programs
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(class_hash, _)| class_hash.clone());
+            if let Some(class_hash) = least_recently_used {
+                entries.programs.remove(&class_hash);
+            }
+        }
+        entries.lookups += 1;
+        let last_used = entries.lookups;
+        let entry = entries
+            .programs
+            .entry(class_hash.clone())
+            .or_insert_with(|| CacheEntry {
+                idle: Vec::new(),
+                last_used,
+            });
+        entry.last_used = last_used;
+        entry.idle.push(program);
+    }
+
+    fn take(&self, class_hash: &cairo_felt::Felt252) -> Option<CompiledProgram> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.lookups += 1;
+        let lookups = entries.lookups;
+        entries.programs.get_mut(class_hash).and_then(|entry| {
+            entry.last_used = lookups;
+            entry.idle.pop()
+        })
+    }
+
+    /// Number of classes whose programs are cached.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
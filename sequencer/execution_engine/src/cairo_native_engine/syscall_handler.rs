@@ -1,37 +1,294 @@
+use super::{call_depth, CairoNativeEngine, MAX_CALL_DEPTH};
+use crate::contract_address::calculate_contract_address;
+use crate::engine::{CallError, CallInfo, ContractCall, ExecutionContext, MAX_CALL_GAS};
+use crate::secp256::{secp256k1, secp256r1, Curve, Point};
+use crate::state::{CachedState, StateReader};
 use cairo_native::starknet::{
-    BlockInfo, ExecutionInfo, StarkNetSyscallHandler, SyscallResult, TxInfo, U256,
+    BlockInfo, ExecutionInfo, Secp256k1Point, StarkNetSyscallHandler, SyscallResult, TxInfo, U256,
 };
 use cairo_vm::felt::Felt252;
+use log::error;
+use num_bigint::BigUint;
+use std::cell::{Cell, RefCell};
+use types::{ChainId, Event, MsgToL1};
+
+/// Error returned to the contract when the state could not be accessed.
+const STATE_ERROR: &[u8] = b"STATE_ERROR";
+
+/// How many of the last blocks have hashes contracts can't read yet.
+const STORED_BLOCK_HASH_BUFFER: u64 = 10;
+
+/// Keccak absorbs its input in blocks of 17 words of 64 bits, already padded by the contract.
+const KECCAK_FULL_RATE_IN_WORDS: usize = 17;
+
+/// Gas every syscall consumes, on top of the gas of the calls it makes.
+const SYSCALL_GAS_COST: u64 = 100;
+/// Gas each block of keccak input consumes.
+const KECCAK_ROUND_GAS_COST: u64 = 1_000;
+/// Gas the secp256 syscalls doing point arithmetic consume.
+const SECP256_GAS_COST: u64 = 1_000;
+/// Gas the secp256 scalar multiplications consume.
+const SECP256_MUL_GAS_COST: u64 = 10_000;
+
+/// Converts a felt from the one used by Cairo Native to the one used by the sequencer.
+fn to_sequencer_felt(felt: &Felt252) -> cairo_felt::Felt252 {
+    cairo_felt::Felt252::from_bytes_be(&felt.to_bytes_be())
+}
+
+/// Converts a felt from the one used by the sequencer to the one used by Cairo Native.
+fn to_native_felt(felt: &cairo_felt::Felt252) -> Felt252 {
+    Felt252::from_bytes_be(&felt.to_bytes_be())
+}
+
+/// Cairo Native passes 256 bits integers as their little-endian bytes.
+fn u256_to_biguint(value: &U256) -> BigUint {
+    BigUint::from_bytes_le(&value.0)
+}
+
+fn biguint_to_u256(value: &BigUint) -> U256 {
+    let mut bytes = [0u8; 32];
+    let value = value.to_bytes_le();
+    bytes[..value.len()].copy_from_slice(&value);
+    U256(bytes)
+}
+
+fn to_native_point(point: Point) -> Secp256k1Point {
+    Secp256k1Point {
+        x: biguint_to_u256(&point.x),
+        y: biguint_to_u256(&point.y),
+    }
+}
+
+fn to_curve_point(point: &Secp256k1Point) -> Point {
+    Point {
+        x: u256_to_biguint(&point.x),
+        y: u256_to_biguint(&point.y),
+    }
+}
+
+/// Coordinates have to be below the modulus of the curve. Out of range ones make the syscall
+/// fail, rather than return no point.
+fn coordinate(curve: &Curve, value: &U256) -> SyscallResult<BigUint> {
+    let value = u256_to_biguint(value);
+    if value >= *curve.modulus() {
+        return Err(syscall_error(b"Invalid argument"));
+    }
+    Ok(value)
+}
+
+fn secp256_new(curve: &Curve, x: &U256, y: &U256) -> SyscallResult<Option<Secp256k1Point>> {
+    Ok(curve
+        .point(coordinate(curve, x)?, coordinate(curve, y)?)
+        .map(to_native_point))
+}
 
-#[derive(Debug)]
-pub struct SyscallHandler;
+fn secp256_get_point_from_x(
+    curve: &Curve,
+    x: &U256,
+    y_parity: bool,
+) -> SyscallResult<Option<Secp256k1Point>> {
+    Ok(curve
+        .point_from_x(coordinate(curve, x)?, y_parity)
+        .map(to_native_point))
+}
+
+/// Syscalls fail with a short string telling why.
+fn syscall_error(reason: &[u8]) -> Vec<Felt252> {
+    vec![Felt252::from_bytes_be(reason)]
+}
+
+/// Failed calls return the panic data of the called contract followed by `ENTRYPOINT_FAILED`,
+/// as Starknet does.
+fn call_error(e: CallError) -> Vec<Felt252> {
+    match e {
+        CallError::ContractNotFound => syscall_error(b"CONTRACT_NOT_DEPLOYED"),
+        CallError::InvalidClass => syscall_error(b"INVALID_CLASS"),
+        CallError::EntryPointNotFound => syscall_error(b"ENTRYPOINT_NOT_FOUND"),
+        CallError::Failed(panic_data) => panic_data
+            .iter()
+            .map(to_native_felt)
+            .chain(syscall_error(b"ENTRYPOINT_FAILED"))
+            .collect(),
+    }
+}
+
+/// Handles the syscalls of a contract being run, on top of the state of the transaction. Calls
+/// and deployments the contract makes are run by the engine on that same state.
+pub struct SyscallHandler<'a> {
+    /// Engine running the calls the contract makes. Without one, they fail.
+    engine: Option<&'a CairoNativeEngine>,
+    /// Address of the contract being executed, whose storage is accessed.
+    contract_address: cairo_felt::Felt252,
+    /// Address of the contract that called this one, or zero for calls from outside.
+    caller_address: cairo_felt::Felt252,
+    /// Selector of the entry point being executed.
+    entry_point_selector: cairo_felt::Felt252,
+    /// Id of the chain the transaction is executed in.
+    chain_id: cairo_felt::Felt252,
+    /// Block and transaction the contract is executed in.
+    context: ExecutionContext,
+    /// Gas the contract was run with.
+    initial_gas: u64,
+    /// Gas consumed by the syscalls of the contract and the calls it made.
+    nested_gas: Cell<u64>,
+    /// State the contract reads from and writes to.
+    state: RefCell<CachedState>,
+    /// Events emitted during the execution, in emission order.
+    events: RefCell<Vec<Event>>,
+    /// Messages sent to L1 during the execution, in sending order.
+    messages: RefCell<Vec<MsgToL1>>,
+}
+
+/// Handlers made outside of a call have as much gas as calls made from outside transactions.
+impl Default for SyscallHandler<'_> {
+    fn default() -> Self {
+        Self {
+            engine: None,
+            contract_address: cairo_felt::Felt252::new(0),
+            caller_address: cairo_felt::Felt252::new(0),
+            entry_point_selector: cairo_felt::Felt252::new(0),
+            chain_id: ChainId::default().to_felt(),
+            context: ExecutionContext::default(),
+            initial_gas: MAX_CALL_GAS,
+            nested_gas: Cell::default(),
+            state: RefCell::default(),
+            events: RefCell::default(),
+            messages: RefCell::default(),
+        }
+    }
+}
+
+impl<'a> SyscallHandler<'a> {
+    /// Creates the handler of the given call, made by the contract at `caller_address` in the
+    /// given context.
+    pub fn new(
+        engine: &'a CairoNativeEngine,
+        call: &ContractCall,
+        caller_address: &cairo_felt::Felt252,
+        context: &ExecutionContext,
+        state: CachedState,
+    ) -> Self {
+        Self {
+            engine: Some(engine),
+            contract_address: call.contract_address.clone(),
+            caller_address: caller_address.clone(),
+            entry_point_selector: call.entry_point_selector.clone(),
+            chain_id: engine.chain_id.clone(),
+            context: context.clone(),
+            initial_gas: call.initial_gas,
+            nested_gas: Cell::default(),
+            state: RefCell::new(state),
+            events: RefCell::default(),
+            messages: RefCell::default(),
+        }
+    }
+
+    /// Returns the events emitted so far, leaving the handler without events.
+    pub fn take_events(&self) -> Vec<Event> {
+        self.events.take()
+    }
+
+    /// Returns the messages sent so far, leaving the handler without messages.
+    pub fn take_messages(&self) -> Vec<MsgToL1> {
+        self.messages.take()
+    }
+
+    /// Returns the gas consumed by the syscalls of the contract and the calls it made so far.
+    pub fn nested_gas(&self) -> u64 {
+        self.nested_gas.get()
+    }
+
+    /// Returns the state, including the writes made during the execution.
+    pub fn into_state(self) -> CachedState {
+        self.state.into_inner()
+    }
+
+    /// Charges the gas of a syscall to the contract. Syscalls the contract doesn't have the gas
+    /// left for fail.
+    fn consume_gas(&self, gas: u64) -> SyscallResult<()> {
+        let nested_gas = self.nested_gas.get().saturating_add(gas);
+        if nested_gas > self.initial_gas {
+            return Err(syscall_error(b"Out of gas"));
+        }
+        self.nested_gas.set(nested_gas);
+        Ok(())
+    }
+
+    /// Runs a call the contract makes on the state of the handler, with the gas the calls made
+    /// so far left. The events and messages of the call are kept along with the ones of the
+    /// contract, and its gas is added to the one of the calls. Failed calls consume all of
+    /// their gas, as what they consumed until then isn't known. Calls nested deeper than
+    /// [MAX_CALL_DEPTH] fail.
+    fn nested_call(
+        &self,
+        call: impl FnOnce(&CairoNativeEngine, u64, &mut CachedState) -> Result<CallInfo, CallError>,
+    ) -> SyscallResult<CallInfo> {
+        let Some(engine) = self.engine else {
+            return Err(syscall_error(b"CALLS_NOT_SUPPORTED"));
+        };
+        if call_depth() >= MAX_CALL_DEPTH {
+            return Err(syscall_error(b"CALL_DEPTH_EXCEEDED"));
+        }
+        let initial_gas = self.initial_gas.saturating_sub(self.nested_gas.get());
+        if initial_gas == 0 {
+            return Err(syscall_error(b"Out of gas"));
+        }
+        // The contract is suspended until the call returns, so the call can have the state
+        let mut state = self.state.take();
+        let result = call(engine, initial_gas, &mut state);
+        self.state.replace(state);
+
+        let gas_consumed = match &result {
+            Ok(call_info) => call_info.resources.gas_consumed,
+            Err(CallError::Failed(_)) => initial_gas,
+            Err(_) => 0,
+        };
+        self.nested_gas
+            .set(self.nested_gas.get().saturating_add(gas_consumed));
+        let mut call_info = result.map_err(call_error)?;
+        self.events.borrow_mut().append(&mut call_info.events);
+        self.messages.borrow_mut().append(&mut call_info.messages);
+        Ok(call_info)
+    }
+}
 
-impl StarkNetSyscallHandler for SyscallHandler {
+impl StarkNetSyscallHandler for SyscallHandler<'_> {
     fn get_block_hash(&self, block_number: u64) -> SyscallResult<Felt252> {
         println!("Called `get_block_hash({block_number})` from MLIR.");
-        Ok(Felt252::from_bytes_be(b"get_block_hash ok"))
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        // The hashes of the last blocks are not available, as Starknet does
+        if block_number.saturating_add(STORED_BLOCK_HASH_BUFFER) > self.context.block.block_number {
+            return Err(syscall_error(b"Block number out of range"));
+        }
+        match self.state.borrow().get_block_hash(block_number) {
+            Ok(Some(block_hash)) => Ok(to_native_felt(&block_hash)),
+            Ok(None) => Err(syscall_error(b"Block number out of range")),
+            Err(_) => Err(syscall_error(STATE_ERROR)),
+        }
     }
 
     fn get_execution_info(&self) -> SyscallResult<cairo_native::starknet::ExecutionInfo> {
         println!("Called `get_execution_info()` from MLIR.");
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        let ExecutionContext { block, transaction } = &self.context;
         Ok(ExecutionInfo {
             block_info: BlockInfo {
-                block_number: 1234,
-                block_timestamp: 2345,
-                sequencer_address: 3456.into(),
+                block_number: block.block_number,
+                block_timestamp: block.block_timestamp,
+                sequencer_address: to_native_felt(&block.sequencer_address),
             },
             tx_info: TxInfo {
-                version: 4567.into(),
-                account_contract_address: 5678.into(),
-                max_fee: 6789,
-                signature: vec![1248.into(), 2486.into()],
-                transaction_hash: 9876.into(),
-                chain_id: 8765.into(),
-                nonce: 7654.into(),
+                version: to_native_felt(&transaction.version),
+                account_contract_address: to_native_felt(&transaction.sender_address),
+                max_fee: transaction.max_fee,
+                signature: transaction.signature.iter().map(to_native_felt).collect(),
+                transaction_hash: to_native_felt(&transaction.transaction_hash),
+                chain_id: to_native_felt(&self.chain_id),
+                nonce: to_native_felt(&transaction.nonce),
             },
-            caller_address: 6543.into(),
-            contract_address: 5432.into(),
-            entry_point_selector: 4321.into(),
+            caller_address: to_native_felt(&self.caller_address),
+            contract_address: to_native_felt(&self.contract_address),
+            entry_point_selector: to_native_felt(&self.entry_point_selector),
         })
     }
 
@@ -43,15 +300,56 @@ impl StarkNetSyscallHandler for SyscallHandler {
         deploy_from_zero: bool,
     ) -> SyscallResult<(Felt252, Vec<Felt252>)> {
         println!("Called `deploy({class_hash}, {contract_address_salt}, {calldata:?}, {deploy_from_zero})` from MLIR.");
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        let class_hash = to_sequencer_felt(&class_hash);
+        let calldata: Vec<_> = calldata.iter().map(to_sequencer_felt).collect();
+        let deployer_address = match deploy_from_zero {
+            true => cairo_felt::Felt252::new(0),
+            false => self.contract_address.clone(),
+        };
+        let contract_address = calculate_contract_address(
+            &to_sequencer_felt(&contract_address_salt),
+            &class_hash,
+            &calldata,
+            &deployer_address,
+        );
+
+        // Contracts can't be deployed twice at the same address
+        match self.state.borrow().get_class_hash_at(&contract_address) {
+            Ok(deployed) if deployed == cairo_felt::Felt252::new(0) => {}
+            Ok(_) => return Err(syscall_error(b"CONTRACT_ADDRESS_UNAVAILABLE")),
+            Err(_) => return Err(syscall_error(STATE_ERROR)),
+        }
+        let call_info = self.nested_call(|engine, initial_gas, state| {
+            engine.deploy_contract(
+                &contract_address,
+                &class_hash,
+                &calldata,
+                &self.contract_address,
+                initial_gas,
+                &self.context,
+                state,
+            )
+        })?;
         Ok((
-            class_hash + contract_address_salt,
-            calldata.iter().map(|x| x + &Felt252::new(1)).collect(),
+            to_native_felt(&contract_address),
+            call_info.retdata.iter().map(to_native_felt).collect(),
         ))
     }
 
     fn replace_class(&self, class_hash: Felt252) -> SyscallResult<()> {
         println!("Called `replace_class({class_hash})` from MLIR.");
-        Ok(())
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        let class_hash = to_sequencer_felt(&class_hash);
+        let mut state = self.state.borrow_mut();
+        match state.get_class(&class_hash) {
+            Ok(Some(_)) => {
+                state.set_class_hash_at(self.contract_address.clone(), class_hash);
+                Ok(())
+            }
+            Ok(None) => Err(syscall_error(b"CLASS_NOT_DECLARED")),
+            Err(_) => Err(syscall_error(STATE_ERROR)),
+        }
     }
 
     fn library_call(
@@ -63,7 +361,24 @@ impl StarkNetSyscallHandler for SyscallHandler {
         println!(
             "Called `library_call({class_hash}, {function_selector}, {calldata:?})` from MLIR."
         );
-        Ok(calldata.iter().map(|x| x * &Felt252::new(3)).collect())
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        // The class is run as this contract, on behalf of its caller
+        let call_info = self.nested_call(|engine, initial_gas, state| {
+            let call = ContractCall {
+                contract_address: self.contract_address.clone(),
+                entry_point_selector: to_sequencer_felt(&function_selector),
+                calldata: calldata.iter().map(to_sequencer_felt).collect(),
+                initial_gas,
+            };
+            engine.library_call(
+                &call,
+                &to_sequencer_felt(&class_hash),
+                &self.caller_address,
+                &self.context,
+                state,
+            )
+        })?;
+        Ok(call_info.retdata.iter().map(to_native_felt).collect())
     }
 
     fn call_contract(
@@ -75,12 +390,30 @@ impl StarkNetSyscallHandler for SyscallHandler {
         println!(
             "Called `call_contract({address}, {entry_point_selector}, {calldata:?})` from MLIR."
         );
-        Ok(calldata.iter().map(|x| x * &Felt252::new(3)).collect())
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        let call_info = self.nested_call(|engine, initial_gas, state| {
+            let call = ContractCall {
+                contract_address: to_sequencer_felt(&address),
+                entry_point_selector: to_sequencer_felt(&entry_point_selector),
+                calldata: calldata.iter().map(to_sequencer_felt).collect(),
+                initial_gas,
+            };
+            engine.call_contract(&call, &self.contract_address, &self.context, state)
+        })?;
+        Ok(call_info.retdata.iter().map(to_native_felt).collect())
     }
 
     fn storage_read(&self, address_domain: u32, address: Felt252) -> SyscallResult<Felt252> {
         println!("Called `storage_read({address_domain}, {address})` from MLIR.");
-        Ok(address * &Felt252::new(3))
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        self.state
+            .borrow()
+            .get_storage_at(&self.contract_address, &to_sequencer_felt(&address))
+            .map(|value| to_native_felt(&value))
+            .map_err(|e| {
+                error!("Failed to read storage: {e}");
+                syscall_error(STATE_ERROR)
+            })
     }
 
     fn storage_write(
@@ -90,100 +423,129 @@ impl StarkNetSyscallHandler for SyscallHandler {
         value: Felt252,
     ) -> SyscallResult<()> {
         println!("Called `storage_write({address_domain}, {address}, {value})` from MLIR.");
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        self.state.borrow_mut().set_storage_at(
+            self.contract_address.clone(),
+            to_sequencer_felt(&address),
+            to_sequencer_felt(&value),
+        );
         Ok(())
     }
 
     fn emit_event(&self, keys: &[Felt252], data: &[Felt252]) -> SyscallResult<()> {
         println!("Called `emit_event({keys:?}, {data:?})` from MLIR.");
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        self.events.borrow_mut().push(Event {
+            from_address: self.contract_address.clone(),
+            keys: keys.iter().map(to_sequencer_felt).collect(),
+            data: data.iter().map(to_sequencer_felt).collect(),
+        });
         Ok(())
     }
 
     fn send_message_to_l1(&self, to_address: Felt252, payload: &[Felt252]) -> SyscallResult<()> {
         println!("Called `send_message_to_l1({to_address}, {payload:?})` from MLIR.");
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        self.messages.borrow_mut().push(MsgToL1 {
+            from_address: self.contract_address.clone(),
+            to_address: to_sequencer_felt(&to_address),
+            payload: payload.iter().map(to_sequencer_felt).collect(),
+        });
         Ok(())
     }
 
-    fn keccak(&self, input: &[u64]) -> SyscallResult<cairo_native::starknet::U256> {
+    fn keccak(&self, input: &[u64]) -> SyscallResult<U256> {
         println!("Called `keccak({input:?})` from MLIR.");
-        Ok(U256(Felt252::from(1234567890).to_le_bytes()))
+        let rounds = input.len() / KECCAK_FULL_RATE_IN_WORDS;
+        self.consume_gas(
+            SYSCALL_GAS_COST.saturating_add(KECCAK_ROUND_GAS_COST.saturating_mul(rounds as u64)),
+        )?;
+        if input.len() % KECCAK_FULL_RATE_IN_WORDS != 0 {
+            return Err(syscall_error(b"Invalid input length"));
+        }
+        let mut state = [0u64; 25];
+        for block in input.chunks(KECCAK_FULL_RATE_IN_WORDS) {
+            for (lane, word) in state.iter_mut().zip(block) {
+                *lane ^= word;
+            }
+            keccak::f1600(&mut state);
+        }
+        // The hash is made of the first four lanes, which read as a little-endian integer
+        let mut hash = [0u8; 32];
+        for (bytes, lane) in hash.chunks_mut(8).zip(state) {
+            bytes.copy_from_slice(&lane.to_le_bytes());
+        }
+        Ok(U256(hash))
     }
 
     fn secp256k1_add(
         &self,
-        _p0: cairo_native::starknet::Secp256k1Point,
-        _p1: cairo_native::starknet::Secp256k1Point,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        let point = secp256k1().add(&to_curve_point(&p0), &to_curve_point(&p1));
+        Ok(Some(to_native_point(point)))
     }
 
     fn secp256k1_get_point_from_x(
         &self,
-        _x: cairo_native::starknet::U256,
-        _y_parity: bool,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+        x: U256,
+        y_parity: bool,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        secp256_get_point_from_x(secp256k1(), &x, y_parity)
     }
 
-    fn secp256k1_get_xy(
-        &self,
-        _p: cairo_native::starknet::Secp256k1Point,
-    ) -> SyscallResult<(cairo_native::starknet::U256, cairo_native::starknet::U256)> {
-        todo!()
+    fn secp256k1_get_xy(&self, p: Secp256k1Point) -> SyscallResult<(U256, U256)> {
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        Ok((p.x, p.y))
     }
 
-    fn secp256k1_mul(
-        &self,
-        _p: cairo_native::starknet::Secp256k1Point,
-        _m: cairo_native::starknet::U256,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+    fn secp256k1_mul(&self, p: Secp256k1Point, m: U256) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_MUL_GAS_COST)?;
+        let point = secp256k1().mul(&to_curve_point(&p), &u256_to_biguint(&m));
+        Ok(Some(to_native_point(point)))
     }
 
-    fn secp256k1_new(
-        &self,
-        _x: cairo_native::starknet::U256,
-        _y: cairo_native::starknet::U256,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+    fn secp256k1_new(&self, x: U256, y: U256) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        secp256_new(secp256k1(), &x, &y)
     }
 
     fn secp256r1_add(
         &self,
-        _p0: cairo_native::starknet::Secp256k1Point,
-        _p1: cairo_native::starknet::Secp256k1Point,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        let point = secp256r1().add(&to_curve_point(&p0), &to_curve_point(&p1));
+        Ok(Some(to_native_point(point)))
     }
 
     fn secp256r1_get_point_from_x(
         &self,
-        _x: cairo_native::starknet::U256,
-        _y_parity: bool,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+        x: U256,
+        y_parity: bool,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        secp256_get_point_from_x(secp256r1(), &x, y_parity)
     }
 
-    fn secp256r1_get_xy(
-        &self,
-        _p: cairo_native::starknet::Secp256k1Point,
-    ) -> SyscallResult<(cairo_native::starknet::U256, cairo_native::starknet::U256)> {
-        todo!()
+    fn secp256r1_get_xy(&self, p: Secp256k1Point) -> SyscallResult<(U256, U256)> {
+        self.consume_gas(SYSCALL_GAS_COST)?;
+        Ok((p.x, p.y))
     }
 
-    fn secp256r1_mul(
-        &self,
-        _p: cairo_native::starknet::Secp256k1Point,
-        _m: cairo_native::starknet::U256,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+    fn secp256r1_mul(&self, p: Secp256k1Point, m: U256) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_MUL_GAS_COST)?;
+        let point = secp256r1().mul(&to_curve_point(&p), &u256_to_biguint(&m));
+        Ok(Some(to_native_point(point)))
     }
 
-    fn secp256r1_new(
-        &self,
-        _x: cairo_native::starknet::U256,
-        _y: cairo_native::starknet::U256,
-    ) -> SyscallResult<Option<cairo_native::starknet::Secp256k1Point>> {
-        todo!()
+    fn secp256r1_new(&self, x: U256, y: U256) -> SyscallResult<Option<Secp256k1Point>> {
+        self.consume_gas(SECP256_GAS_COST)?;
+        secp256_new(secp256r1(), &x, &y)
     }
 
     fn pop_log(&self) {
@@ -238,3 +600,90 @@ impl StarkNetSyscallHandler for SyscallHandler {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{biguint_to_u256, secp256k1, syscall_error, SyscallHandler, SYSCALL_GAS_COST};
+    use cairo_native::starknet::StarkNetSyscallHandler;
+    use cairo_vm::felt::Felt252;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn syscalls_consume_gas() {
+        let handler = SyscallHandler {
+            initial_gas: 2 * SYSCALL_GAS_COST,
+            ..Default::default()
+        };
+        assert_eq!(
+            Ok(()),
+            handler.storage_write(0, Felt252::new(1), Felt252::new(2))
+        );
+        assert_eq!(
+            Ok(Felt252::new(2)),
+            handler.storage_read(0, Felt252::new(1))
+        );
+        assert_eq!(2 * SYSCALL_GAS_COST, handler.nested_gas());
+
+        // Syscalls fail once the contract has no gas left for them
+        assert_eq!(
+            Err(syscall_error(b"Out of gas")),
+            handler.emit_event(&[Felt252::new(1)], &[])
+        );
+        assert_eq!(2 * SYSCALL_GAS_COST, handler.nested_gas());
+        assert!(handler.take_events().is_empty());
+    }
+
+    #[test]
+    fn keccak_hashes_padded_input() {
+        let handler = SyscallHandler::default();
+        // The empty input, padded to a full block
+        let mut input = [0u64; 17];
+        input[0] = 1;
+        input[16] = 0x8000000000000000;
+
+        let hash = handler.keccak(&input).unwrap();
+        assert_eq!(
+            BigUint::parse_bytes(
+                b"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+                16
+            ),
+            Some(BigUint::from_bytes_be(&hash.0))
+        );
+        assert_eq!(
+            Err(syscall_error(b"Invalid input length")),
+            handler.keccak(&input[1..])
+        );
+    }
+
+    #[test]
+    fn secp256_points_have_to_be_on_the_curve() {
+        let handler = SyscallHandler::default();
+        let x = BigUint::parse_bytes(
+            b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            16,
+        )
+        .unwrap();
+        let generator = handler
+            .secp256k1_get_point_from_x(biguint_to_u256(&x), false)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            Ok(Some(generator)),
+            handler.secp256k1_new(generator.x, generator.y)
+        );
+        assert_eq!(Ok(None), handler.secp256k1_new(generator.x, generator.x));
+        let zero = biguint_to_u256(&BigUint::default());
+        let infinity = handler.secp256k1_new(zero, zero).unwrap().unwrap();
+        assert_eq!(
+            Ok(Some(generator)),
+            handler.secp256k1_add(generator, infinity)
+        );
+        let modulus = secp256k1().modulus().clone();
+        assert_eq!(
+            Err(syscall_error(b"Invalid argument")),
+            handler.secp256k1_new(biguint_to_u256(&modulus), generator.y)
+        );
+        assert_eq!(Ok(None), handler.secp256r1_new(generator.x, generator.y));
+    }
+}
@@ -1,3 +1,11 @@
+use crate::cairo_native_engine::selector_from_name;
+use crate::engine::{
+    execute_call, BlockContext, CallError, CallInfo, ContractCall, ExecutionContext,
+    ExecutionEngine, ExecutionResources, TransactionContext,
+};
+use crate::genesis::{FACTORIAL_ADDRESS, FIBONACCI_ADDRESS};
+use crate::state::CachedState;
+use crate::transaction::TransactionExecution;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use cairo_vm::felt::Felt252;
 use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
@@ -7,6 +15,7 @@ use cairo_vm::types::relocatable::MaybeRelocatable;
 use cairo_vm::vm::runners::cairo_runner::CairoArg;
 use cairo_vm::vm::runners::cairo_runner::{CairoRunner, RunResources};
 use cairo_vm::vm::vm_core::VirtualMachine;
+use types::{DeclareTransaction, InvokeTransactionV1};
 
 pub struct CairoVMEngine {
     fib_program: CasmContractClass,
@@ -59,12 +68,130 @@ impl CairoVMEngine {
     }
 }
 
+impl ExecutionEngine for CairoVMEngine {
+    /// Contracts are not supported yet, so only the bundled Fibonacci and Factorial contracts can
+    /// be called (see [crate::genesis]), and the state is not touched. Calls run with their gas.
+    fn call(
+        &self,
+        call: &ContractCall,
+        _context: &ExecutionContext,
+        _state: &mut CachedState,
+    ) -> Result<CallInfo, CallError> {
+        let (program, builtins, entry_point, arguments) =
+            if call.contract_address == cairo_felt::Felt252::new(FIBONACCI_ADDRESS) {
+                (&self.fib_program, &self.fib_builtins, "fib", 3)
+            } else if call.contract_address == cairo_felt::Felt252::new(FACTORIAL_ADDRESS) {
+                (&self.fact_program, &self.fact_builtins, "fact", 1)
+            } else {
+                return Err(CallError::ContractNotFound);
+            };
+        if call.entry_point_selector != selector_from_name(entry_point) {
+            return Err(CallError::EntryPointNotFound);
+        }
+        if call.calldata.len() != arguments {
+            let reason: &[u8] = if call.calldata.len() < arguments {
+                b"Input too short for arguments"
+            } else {
+                b"Input too long for arguments"
+            };
+            return Err(CallError::Failed(vec![cairo_felt::Felt252::from_bytes_be(
+                reason,
+            )]));
+        }
+
+        let args: Vec<MaybeRelocatable> = call
+            .calldata
+            .iter()
+            .map(|felt| Felt252::from_bytes_be(&felt.to_bytes_be()).into())
+            .collect();
+        let initial_gas = usize::try_from(call.initial_gas).unwrap_or(usize::MAX);
+        let run = run_entrypoint_with_gas(program, builtins, 0, &args, initial_gas);
+        let retdata = run
+            .retdata
+            .iter()
+            .map(|felt| cairo_felt::Felt252::from_bytes_be(&felt.to_bytes_be()))
+            .collect();
+        if run.failed {
+            return Err(CallError::Failed(retdata));
+        }
+        Ok(CallInfo {
+            retdata,
+            resources: ExecutionResources {
+                gas_consumed: initial_gas.saturating_sub(run.gas_left) as u64,
+            },
+            ..Default::default()
+        })
+    }
+
+    /// There are no accounts to go through, so the calldata of the transaction is the call
+    /// itself, see [ContractCall::from_invoke_calldata]. Its signature and nonce aren't checked.
+    fn execute_invoke(
+        &self,
+        tx: &InvokeTransactionV1,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        let gas_limit = block.gas_limit(&tx.max_fee);
+        let Some(call) = ContractCall::from_invoke_calldata(&tx.calldata, gas_limit) else {
+            return Ok(TransactionExecution::reverted(
+                "Transaction contains invalid calldata",
+            ));
+        };
+        let context = ExecutionContext {
+            block: block.clone(),
+            transaction: TransactionContext::from_invoke(tx),
+        };
+        Ok(TransactionExecution {
+            gas_price: block.gas_price,
+            ..execute_call(self, &call, &context, state)
+        })
+    }
+
+    /// There are no accounts to validate declarations with, so their signature and nonce aren't
+    /// checked either.
+    fn validate_declare(
+        &self,
+        _tx: &DeclareTransaction,
+        _block: &BlockContext,
+        _state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        Ok(TransactionExecution::default())
+    }
+}
+
+/// What running an entry point returned.
+struct EntryPointRun {
+    /// Values returned, or the panic data if the entry point failed
+    retdata: Vec<Felt252>,
+    /// Gas left once the entry point returned
+    gas_left: usize,
+    failed: bool,
+}
+
+/// Runs an entry point with all the gas there is, returning what it returned.
 fn run_cairo_1_entrypoint(
     program_content: &CasmContractClass,
     program_builtins: &[BuiltinName],
     entrypoint_offset: usize,
     args: &[MaybeRelocatable],
 ) -> Vec<Felt252> {
+    run_entrypoint_with_gas(
+        program_content,
+        program_builtins,
+        entrypoint_offset,
+        args,
+        usize::MAX,
+    )
+    .retdata
+}
+
+fn run_entrypoint_with_gas(
+    program_content: &CasmContractClass,
+    program_builtins: &[BuiltinName],
+    entrypoint_offset: usize,
+    args: &[MaybeRelocatable],
+    initial_gas: usize,
+) -> EntryPointRun {
     let contract_class = program_content;
     let mut hint_processor =
         Cairo1HintProcessor::new(&contract_class.hints, RunResources::default());
@@ -97,7 +224,7 @@ fn run_cairo_1_entrypoint(
         .flat_map(|b| b.initial_stack())
         .collect();
 
-    let initial_gas = MaybeRelocatable::from(usize::MAX);
+    let initial_gas = MaybeRelocatable::from(initial_gas);
 
     let mut implicit_args = builtin_segment;
     implicit_args.extend([initial_gas]);
@@ -143,8 +270,16 @@ fn run_cairo_1_entrypoint(
         )
         .unwrap();
 
-    // Check return values
+    // Check return values: the gas left, the syscall pointer, whether the entry point failed,
+    // and where the returned values are
     let return_values = vm.get_return_values(5).unwrap();
+    let gas_left = return_values[0]
+        .get_int_ref()
+        .and_then(|gas_left| usize::try_from(gas_left.to_biguint()).ok())
+        .unwrap_or(0);
+    let failed = return_values[2]
+        .get_int_ref()
+        .map_or(false, |failure_flag| *failure_flag != Felt252::from(0));
     let retdata_start = return_values[3].get_relocatable().unwrap();
     let retdata_end = return_values[4].get_relocatable().unwrap();
     let retdata: Vec<Felt252> = vm
@@ -153,7 +288,11 @@ fn run_cairo_1_entrypoint(
         .iter()
         .map(|c| c.clone().into_owned())
         .collect();
-    retdata
+    EntryPointRun {
+        retdata,
+        gas_left,
+        failed,
+    }
 }
 
 fn get_casm_contract_builtins(
@@ -192,9 +331,13 @@ fn get_casm_contract_builtins(
 mod tests {
     use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 
+    use crate::cairo_native_engine::selector_from_name;
     use crate::cairovm_engine::{
         get_casm_contract_builtins, run_cairo_1_entrypoint, CairoVMEngine,
     };
+    use crate::engine::{CallError, ContractCall, ExecutionContext, ExecutionEngine, MAX_CALL_GAS};
+    use crate::genesis::{ERC20_ADDRESS, FIBONACCI_ADDRESS};
+    use crate::state::CachedState;
 
     #[test]
     fn fib_1_run_cairo_1_entrypoint() {
@@ -237,4 +380,44 @@ mod tests {
             "Output Fact Cairo VM: [3628800]"
         );
     }
+
+    #[test]
+    fn cairovm_engine_calls_bundled_contracts() {
+        let fib_program = include_bytes!("../../cairo_programs/fib_contract.casm");
+        let fact_program = include_bytes!("../../cairo_programs/fact_contract.casm");
+        let engine = CairoVMEngine::new(fib_program.to_vec(), fact_program.to_vec());
+        let mut state = CachedState::default();
+        let fib = |contract_address: u64, calldata: &[u64], state: &mut CachedState| {
+            engine.call(
+                &ContractCall {
+                    contract_address: cairo_felt::Felt252::new(contract_address),
+                    entry_point_selector: selector_from_name("fib"),
+                    calldata: calldata
+                        .iter()
+                        .map(|n| cairo_felt::Felt252::new(*n))
+                        .collect(),
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &ExecutionContext::default(),
+                state,
+            )
+        };
+
+        assert_eq!(
+            vec![cairo_felt::Felt252::new(55)],
+            fib(FIBONACCI_ADDRESS, &[0, 1, 10], &mut state)
+                .unwrap()
+                .retdata
+        );
+        assert_eq!(
+            "Input too short for arguments",
+            fib(FIBONACCI_ADDRESS, &[10], &mut state)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            Err(CallError::ContractNotFound),
+            fib(ERC20_ADDRESS, &[0, 1, 10], &mut state).map(|_| ())
+        );
+    }
 }
@@ -0,0 +1,73 @@
+use cairo_felt::Felt252;
+use num_bigint::BigUint;
+use starknet_crypto::{pedersen_hash, FieldElement};
+
+/// Prefix of the hash contract addresses are derived from.
+const CONTRACT_ADDRESS_PREFIX: &[u8] = b"STARKNET_CONTRACT_ADDRESS";
+
+/// Converts a felt to the field element the Starknet crypto primitives work with.
+pub(crate) fn to_field_element(felt: &Felt252) -> FieldElement {
+    let bytes = felt.to_bytes_be();
+    let mut buffer = [0u8; 32];
+    buffer[32 - bytes.len()..].copy_from_slice(&bytes);
+    FieldElement::from_bytes_be(&buffer).expect("Felts are field elements")
+}
+
+/// Hashes a list of felts the way Starknet does: chaining Pedersen hashes from zero, and then
+/// hashing in the length of the list.
+pub fn compute_hash_on_elements(elements: &[Felt252]) -> Felt252 {
+    let hash = elements.iter().fold(FieldElement::ZERO, |hash, element| {
+        pedersen_hash(&hash, &to_field_element(element))
+    });
+    let hash = pedersen_hash(&hash, &FieldElement::from(elements.len()));
+    Felt252::from_bytes_be(&hash.to_bytes_be())
+}
+
+/// Returns the address a contract of the given class gets when deployed with the given salt and
+/// constructor calldata. Contracts deployed from zero get the same address whoever deploys them.
+pub fn calculate_contract_address(
+    salt: &Felt252,
+    class_hash: &Felt252,
+    constructor_calldata: &[Felt252],
+    deployer_address: &Felt252,
+) -> Felt252 {
+    let hash = compute_hash_on_elements(&[
+        Felt252::from_bytes_be(CONTRACT_ADDRESS_PREFIX),
+        deployer_address.clone(),
+        salt.clone(),
+        class_hash.clone(),
+        compute_hash_on_elements(constructor_calldata),
+    ]);
+    // Addresses are below 2^251 - 256
+    let upper_bound = (BigUint::from(1u8) << 251u32) - 256u32;
+    Felt252::from_bytes_be(&(hash.to_biguint() % upper_bound).to_bytes_be())
+}
+
+#[cfg(test)]
+mod test {
+    use super::calculate_contract_address;
+    use cairo_felt::Felt252;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn contract_addresses_depend_on_the_deployment() {
+        let class_hash = Felt252::new(0x1234);
+        let calldata = [Felt252::new(1), Felt252::new(2)];
+        let address =
+            calculate_contract_address(&Felt252::new(7), &class_hash, &calldata, &Felt252::new(0));
+
+        assert!(address.to_biguint() < (BigUint::from(1u8) << 251u32) - 256u32);
+        assert_eq!(
+            address,
+            calculate_contract_address(&Felt252::new(7), &class_hash, &calldata, &Felt252::new(0))
+        );
+        assert_ne!(
+            address,
+            calculate_contract_address(&Felt252::new(8), &class_hash, &calldata, &Felt252::new(0))
+        );
+        assert_ne!(
+            address,
+            calculate_contract_address(&Felt252::new(7), &class_hash, &calldata, &Felt252::new(1))
+        );
+    }
+}
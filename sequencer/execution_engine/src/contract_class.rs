@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Result};
+use cairo_felt::Felt252;
+use cairo_lang_compiler::CompilerConfig;
+use cairo_lang_sierra::program::Program;
+use cairo_lang_starknet::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
+use cairo_lang_starknet::contract_class::{compile_path, ContractClass as SierraContractClass};
+use num_bigint::BigUint;
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+use std::path::Path;
+use types::FlattenedSierraClass;
+
+/// Version of the compiled classes, hashed in front of their contents.
+const COMPILED_CLASS_VERSION: &[u8] = b"COMPILED_CLASS_V1";
+
+/// Converts a class to the one the Cairo compiler works with.
+fn to_sierra_contract_class(class: &FlattenedSierraClass) -> Result<SierraContractClass> {
+    let mut class = serde_json::to_value(class)?;
+    // The ABI is only informative, and the compiler expects it parsed rather than as a string
+    class["abi"] = serde_json::Value::Null;
+    Ok(serde_json::from_value(class)?)
+}
+
+/// Compiles a Sierra class to CASM, the way the Cairo VM runs it.
+pub fn compile_sierra_class(class: &FlattenedSierraClass) -> Result<CasmContractClass> {
+    let class = to_sierra_contract_class(class)?;
+    CasmContractClass::from_contract_class(class, true).map_err(|e| anyhow!(e.to_string()))
+}
+
+/// Converts a number below the field prime to the field element the Starknet crypto primitives
+/// work with.
+fn to_field_element(value: &BigUint) -> FieldElement {
+    FieldElement::from_byte_slice_be(&value.to_bytes_be()).expect("CASM values are field elements")
+}
+
+/// Hashes the selectors of the entry points along with their offsets and the builtins they use.
+fn entry_points_hash(entry_points: &[CasmContractEntryPoint]) -> FieldElement {
+    let elements: Vec<FieldElement> = entry_points
+        .iter()
+        .flat_map(|entry_point| {
+            let builtins: Vec<FieldElement> = entry_point
+                .builtins
+                .iter()
+                .map(|builtin| {
+                    FieldElement::from_byte_slice_be(builtin.as_bytes())
+                        .expect("Builtin names are short strings")
+                })
+                .collect();
+            [
+                to_field_element(&entry_point.selector),
+                FieldElement::from(entry_point.offset),
+                poseidon_hash_many(&builtins),
+            ]
+        })
+        .collect();
+    poseidon_hash_many(&elements)
+}
+
+/// Returns the hash of the CASM a Sierra class compiles to, which declare transactions commit to.
+/// It is computed the way Starknet does, so it matches the one clients send.
+pub fn compiled_class_hash(class: &FlattenedSierraClass) -> Result<Felt252> {
+    let compiled_class = compile_sierra_class(class)?;
+    let entry_points = &compiled_class.entry_points_by_type;
+    let bytecode: Vec<FieldElement> = compiled_class
+        .bytecode
+        .iter()
+        .map(|word| to_field_element(&word.value))
+        .collect();
+    let hash = poseidon_hash_many(&[
+        FieldElement::from_byte_slice_be(COMPILED_CLASS_VERSION)
+            .expect("Short strings fit in a felt"),
+        entry_points_hash(&entry_points.external),
+        entry_points_hash(&entry_points.l1_handler),
+        entry_points_hash(&entry_points.constructor),
+        poseidon_hash_many(&bytecode),
+    ]);
+    Ok(Felt252::from_bytes_be(&hash.to_bytes_be()))
+}
+
+/// Returns the Sierra program of a class. Its functions are identified by the index the entry
+/// points of the class refer to, as the debug names are not part of the class.
+pub fn sierra_program(class: &FlattenedSierraClass) -> Result<Program> {
+    to_sierra_contract_class(class)?
+        .extract_sierra_program()
+        .map_err(|e| anyhow!(e.to_string()))
+}
+
+/// Compiles the Starknet contract at the given path to a Sierra class.
+pub fn compile_contract(path: &Path) -> Result<FlattenedSierraClass> {
+    let class = compile_path(
+        path,
+        None,
+        CompilerConfig {
+            replace_ids: true,
+            ..Default::default()
+        },
+    )?;
+    let mut class = serde_json::to_value(class)?;
+    class["abi"] = serde_json::Value::String(class["abi"].to_string());
+    Ok(serde_json::from_value(class)?)
+}
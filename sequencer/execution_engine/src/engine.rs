@@ -0,0 +1,450 @@
+use crate::account::{
+    self, EXECUTE_ENTRY_POINT, VALIDATE_DECLARE_ENTRY_POINT, VALIDATE_ENTRY_POINT,
+};
+use crate::cairo_native_engine::selector_from_name;
+use crate::state::{CachedState, StateReader};
+use crate::transaction::{TransactionExecution, DECLARE_GAS};
+use cairo_felt::Felt252;
+use sequencer::store::state::StateDiff;
+use std::fmt;
+use types::{DeclareTransaction, DeployAccountTransaction, Event, InvokeTransactionV1, MsgToL1};
+
+/// Gas calls made outside of transactions can consume, so they can't run forever.
+pub const MAX_CALL_GAS: u64 = 100_000_000;
+
+/// A call to an external function of a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCall {
+    pub contract_address: Felt252,
+    pub entry_point_selector: Felt252,
+    pub calldata: Vec<Felt252>,
+    /// Gas the call can consume before running out of it
+    pub initial_gas: u64,
+}
+
+impl ContractCall {
+    /// Reads the call out of the calldata of an invoke transaction run without going through its
+    /// sender account, which holds the address of the contract to call and the selector of the
+    /// external function to run, followed by the arguments of the function.
+    pub fn from_invoke_calldata(calldata: &[Felt252], initial_gas: u64) -> Option<Self> {
+        match calldata {
+            [contract_address, entry_point_selector, calldata @ ..] => Some(Self {
+                contract_address: contract_address.clone(),
+                entry_point_selector: entry_point_selector.clone(),
+                calldata: calldata.to_vec(),
+                initial_gas,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The block transactions are executed in, as contracts see it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockContext {
+    pub block_number: u64,
+    /// Seconds since the Unix epoch
+    pub block_timestamp: u64,
+    pub sequencer_address: Felt252,
+    /// Price of a unit of gas, in wei
+    pub gas_price: u64,
+}
+
+impl BlockContext {
+    /// Gas a transaction can consume: as much as its maximal fee pays for. Without a gas price,
+    /// as much as calls can, so transactions still can't run forever.
+    pub fn gas_limit(&self, max_fee: &Felt252) -> u64 {
+        let max_fee = u64::try_from(max_fee.to_biguint()).unwrap_or(u64::MAX);
+        max_fee.checked_div(self.gas_price).unwrap_or(MAX_CALL_GAS)
+    }
+}
+
+/// The transaction calls are made for, as contracts see it. Calls made from outside of a
+/// transaction see it zeroed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionContext {
+    pub version: Felt252,
+    pub transaction_hash: Felt252,
+    pub signature: Vec<Felt252>,
+    pub nonce: Felt252,
+    pub max_fee: u128,
+    /// Account that sent the transaction
+    pub sender_address: Felt252,
+}
+
+impl TransactionContext {
+    pub fn from_invoke(tx: &InvokeTransactionV1) -> Self {
+        Self {
+            version: Felt252::new(1),
+            transaction_hash: tx.transaction_hash.clone(),
+            signature: tx.signature.clone(),
+            nonce: tx.nonce.clone(),
+            max_fee: u128::try_from(tx.max_fee.to_biguint()).unwrap_or(u128::MAX),
+            sender_address: tx.sender_address.clone(),
+        }
+    }
+
+    pub fn from_declare(tx: &DeclareTransaction) -> Self {
+        match tx {
+            DeclareTransaction::V1(tx) => Self {
+                version: Felt252::new(1),
+                transaction_hash: tx.transaction_hash.clone(),
+                signature: tx.signature.clone(),
+                nonce: tx.nonce.clone(),
+                max_fee: u128::try_from(tx.max_fee.to_biguint()).unwrap_or(u128::MAX),
+                sender_address: tx.sender_address.clone(),
+            },
+            DeclareTransaction::V2(tx) => Self {
+                version: Felt252::new(2),
+                transaction_hash: tx.transaction_hash.clone(),
+                signature: tx.signature.clone(),
+                nonce: tx.nonce.clone(),
+                max_fee: u128::try_from(tx.max_fee.to_biguint()).unwrap_or(u128::MAX),
+                sender_address: tx.sender_address.clone(),
+            },
+        }
+    }
+
+    /// The account being deployed is the sender of the transaction deploying it.
+    pub fn from_deploy_account(tx: &DeployAccountTransaction, contract_address: &Felt252) -> Self {
+        Self {
+            version: Felt252::new(1),
+            transaction_hash: tx.transaction_hash.clone(),
+            signature: tx.signature.clone(),
+            nonce: tx.nonce.clone(),
+            max_fee: u128::try_from(tx.max_fee.to_biguint()).unwrap_or(u128::MAX),
+            sender_address: contract_address.clone(),
+        }
+    }
+}
+
+/// Everything about where a call is executed that contracts can ask for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionContext {
+    pub block: BlockContext,
+    pub transaction: TransactionContext,
+}
+
+/// Resources spent running a call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionResources {
+    pub gas_consumed: u64,
+}
+
+/// What a successful call did.
+#[derive(Debug, Clone, Default)]
+pub struct CallInfo {
+    /// Values returned by the called function
+    pub retdata: Vec<Felt252>,
+    /// Events emitted during the call, in emission order
+    pub events: Vec<Event>,
+    /// Messages sent to L1 during the call, in sending order
+    pub messages: Vec<MsgToL1>,
+    /// Writes made by the call
+    pub state_diff: StateDiff,
+    pub resources: ExecutionResources,
+}
+
+/// Reasons a call to a contract entry point can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// There is no contract the engine can run deployed at the called address
+    ContractNotFound,
+    /// The class of the contract can't be turned into a program
+    InvalidClass,
+    /// The contract has no external function with the given selector
+    EntryPointNotFound,
+    /// The execution panicked, with the given panic data
+    Failed(Vec<Felt252>),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::ContractNotFound => write!(f, "Contract not found"),
+            CallError::InvalidClass => write!(f, "Invalid contract class"),
+            CallError::EntryPointNotFound => write!(f, "Entry point not found"),
+            CallError::Failed(panic_data) => write!(f, "{}", revert_reason(panic_data)),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Runs contract calls. Engines are interchangeable: the node and the RPC endpoint only go
+/// through this trait, so new ones can be plugged in and compared with the existing ones.
+pub trait ExecutionEngine: Send + Sync {
+    /// Runs the call on top of the given state. The writes of a successful call are kept in the
+    /// state, and the ones of a failed call are dropped.
+    fn call(
+        &self,
+        call: &ContractCall,
+        context: &ExecutionContext,
+        state: &mut CachedState,
+    ) -> Result<CallInfo, CallError>;
+
+    /// Executes an invoke transaction in the given block, on top of the given state, through
+    /// its sender account: `__validate__` checks the transaction, and then `__execute__` runs
+    /// the calls in its calldata.
+    ///
+    /// Transactions with another nonce than their sender's, or that their sender doesn't
+    /// validate, are rejected with the reason why. Both entry points share as much gas as the
+    /// maximal fee pays for, and the transaction is reverted if `__execute__` fails or needs
+    /// more.
+    fn execute_invoke(
+        &self,
+        tx: &InvokeTransactionV1,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        check_nonce(state, &tx.sender_address, &tx.nonce)?;
+        let gas_limit = block.gas_limit(&tx.max_fee);
+        let context = ExecutionContext {
+            block: block.clone(),
+            transaction: TransactionContext::from_invoke(tx),
+        };
+
+        let validation = validate(
+            self,
+            &ContractCall {
+                contract_address: tx.sender_address.clone(),
+                entry_point_selector: selector_from_name(VALIDATE_ENTRY_POINT),
+                calldata: tx.calldata.clone(),
+                initial_gas: gas_limit,
+            },
+            &context,
+            state,
+        )?;
+
+        let call = ContractCall {
+            contract_address: tx.sender_address.clone(),
+            entry_point_selector: selector_from_name(EXECUTE_ENTRY_POINT),
+            calldata: tx.calldata.clone(),
+            initial_gas: gas_limit - validation.resources.gas_consumed,
+        };
+        let execution = execute_call(self, &call, &context, state);
+        Ok(TransactionExecution {
+            events: [validation.events, execution.events].concat(),
+            messages: [validation.messages, execution.messages].concat(),
+            gas_consumed: validation.resources.gas_consumed + execution.gas_consumed,
+            gas_price: block.gas_price,
+            ..execution
+        })
+    }
+
+    /// Checks a declare transaction in the given block, on top of the given state, through its
+    /// sender account: `__validate_declare__` is called with the hash of the class, before the
+    /// class is declared.
+    ///
+    /// Transactions with another nonce than their sender's, or that their sender doesn't
+    /// validate, are rejected with the reason why. The validation can consume as much gas as the
+    /// maximal fee pays for besides [DECLARE_GAS].
+    fn validate_declare(
+        &self,
+        tx: &DeclareTransaction,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        let (class_hash, max_fee) = match tx {
+            DeclareTransaction::V1(tx) => (&tx.class_hash, &tx.max_fee),
+            DeclareTransaction::V2(tx) => (&tx.class_hash, &tx.max_fee),
+        };
+        let context = ExecutionContext {
+            block: block.clone(),
+            transaction: TransactionContext::from_declare(tx),
+        };
+        let sender_address = &context.transaction.sender_address;
+        check_nonce(state, sender_address, &context.transaction.nonce)?;
+
+        let validation = validate(
+            self,
+            &ContractCall {
+                contract_address: sender_address.clone(),
+                entry_point_selector: selector_from_name(VALIDATE_DECLARE_ENTRY_POINT),
+                calldata: vec![class_hash.clone()],
+                initial_gas: block.gas_limit(max_fee).saturating_sub(DECLARE_GAS),
+            },
+            &context,
+            state,
+        )?;
+        Ok(TransactionExecution {
+            events: validation.events,
+            messages: validation.messages,
+            gas_consumed: validation.resources.gas_consumed,
+            gas_price: block.gas_price,
+            ..Default::default()
+        })
+    }
+
+    /// Executes a deploy account transaction in the given block, on top of the given state:
+    /// deploys the account at the address computed from the transaction, and then checks the
+    /// account validates the transaction with `__validate_deploy__`.
+    ///
+    /// By default they are rejected, as engines without accounts have nothing to deploy.
+    fn execute_deploy_account(
+        &self,
+        _tx: &DeployAccountTransaction,
+        _block: &BlockContext,
+        _state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        Err("Deploy account transactions are not supported by this engine".into())
+    }
+}
+
+/// Checks a transaction has the nonce of the account sending it.
+pub fn check_nonce(
+    state: &dyn StateReader,
+    sender_address: &Felt252,
+    nonce: &Felt252,
+) -> Result<(), String> {
+    let expected = state
+        .get_nonce_at(sender_address)
+        .map_err(|e| format!("Failed to read the nonce: {e}"))?;
+    if &expected != nonce {
+        return Err(format!("Invalid nonce: expected {expected}, got {nonce}"));
+    }
+    Ok(())
+}
+
+/// Runs a validation entry point of the account sending a transaction on a layer of its own,
+/// which is dropped along with the transaction if the account doesn't validate it within the
+/// initial gas of the call.
+pub fn validate<E: ExecutionEngine + ?Sized>(
+    engine: &E,
+    call: &ContractCall,
+    context: &ExecutionContext,
+    state: &mut CachedState,
+) -> Result<CallInfo, String> {
+    state.push_layer();
+    let validation = engine
+        .call(call, context, state)
+        .map_err(|e| format!("Validation failed: {e}"))
+        .and_then(|validation| {
+            // Accounts of older Cairo versions return nothing
+            let validated = match validation.retdata.as_slice() {
+                [] => true,
+                [retdata] => *retdata == account::validated(),
+                _ => false,
+            };
+            if validation.resources.gas_consumed > call.initial_gas {
+                Err("Validation failed: Out of gas".to_string())
+            } else if !validated {
+                Err("Validation failed: The transaction is not valid".to_string())
+            } else {
+                Ok(validation)
+            }
+        });
+    match validation {
+        Ok(validation) => {
+            state.commit_layer();
+            Ok(validation)
+        }
+        Err(e) => {
+            state.discard_layer();
+            Err(e)
+        }
+    }
+}
+
+/// Runs the call of a transaction on a layer of its own, which is dropped if the call fails or
+/// consumes more than its initial gas. Calls that fail after running are charged all of their
+/// gas, as what they consumed until then isn't known.
+pub fn execute_call<E: ExecutionEngine + ?Sized>(
+    engine: &E,
+    call: &ContractCall,
+    context: &ExecutionContext,
+    state: &mut CachedState,
+) -> TransactionExecution {
+    // Calls can only tell they consumed too much gas once they are done
+    state.push_layer();
+    let execution = match engine.call(call, context, state) {
+        Ok(call_info) if call_info.resources.gas_consumed > call.initial_gas => {
+            TransactionExecution {
+                gas_consumed: call.initial_gas,
+                ..TransactionExecution::reverted("Out of gas")
+            }
+        }
+        Ok(call_info) => TransactionExecution {
+            events: call_info.events,
+            messages: call_info.messages,
+            gas_consumed: call_info.resources.gas_consumed,
+            ..Default::default()
+        },
+        Err(e @ CallError::Failed(_)) => TransactionExecution {
+            gas_consumed: call.initial_gas,
+            ..TransactionExecution::reverted(e)
+        },
+        Err(e) => TransactionExecution::reverted(e),
+    };
+    match execution.revert_reason {
+        Some(_) => state.discard_layer(),
+        None => {
+            state.commit_layer();
+        }
+    }
+    execution
+}
+
+/// Panic data is usually made of short strings, like `'u256_sub Overflow'`. Those are shown as
+/// text and any other felt as hex.
+pub fn revert_reason(panic_data: &[Felt252]) -> String {
+    panic_data
+        .iter()
+        .map(|felt| {
+            let bytes: Vec<u8> = felt
+                .to_bytes_be()
+                .into_iter()
+                .skip_while(|byte| *byte == 0)
+                .collect();
+            match String::from_utf8(bytes) {
+                Ok(text)
+                    if !text.is_empty()
+                        && text.chars().all(|c| c.is_ascii_graphic() || c == ' ') =>
+                {
+                    text
+                }
+                _ => format!("0x{}", felt.to_str_radix(16)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BlockContext, ContractCall, MAX_CALL_GAS};
+    use cairo_felt::Felt252;
+
+    #[test]
+    fn invoke_calldata_starts_with_the_called_function() {
+        let call = ContractCall::from_invoke_calldata(
+            &[Felt252::new(1), Felt252::new(2), Felt252::new(3)],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(Felt252::new(1), call.contract_address);
+        assert_eq!(Felt252::new(2), call.entry_point_selector);
+        assert_eq!(vec![Felt252::new(3)], call.calldata);
+        assert_eq!(1000, call.initial_gas);
+
+        assert_eq!(
+            None,
+            ContractCall::from_invoke_calldata(&[Felt252::new(1)], 1000)
+        );
+    }
+
+    #[test]
+    fn transactions_get_the_gas_their_maximal_fee_pays_for() {
+        let block = BlockContext {
+            gas_price: 100,
+            ..Default::default()
+        };
+        assert_eq!(25, block.gas_limit(&Felt252::new(2599)));
+
+        let no_gas_price = BlockContext::default();
+        assert_eq!(
+            MAX_CALL_GAS,
+            no_gas_price.gas_limit(&Felt252::new(u64::MAX))
+        );
+    }
+}
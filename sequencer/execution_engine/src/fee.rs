@@ -0,0 +1,501 @@
+use crate::account::deploy_account_address;
+use crate::cairo_native_engine::selector_from_name;
+use crate::contract_address::to_field_element;
+use crate::engine::{BlockContext, ExecutionEngine};
+use crate::state::{CachedState, StateReader};
+use crate::transaction::{TransactionExecution, DECLARE_GAS};
+use cairo_felt::Felt252;
+use num_bigint::BigUint;
+use starknet_crypto::pedersen_hash;
+use types::{DeployAccountTransaction, Event, InvokeTransactionV1};
+
+/// The ERC20 contract fees are paid with, deployed at genesis (see [crate::genesis]).
+///
+/// Balances are read and written straight from the state, where the bundled ERC20 contract keeps
+/// them, so fees are charged the same way whatever the engine transactions are executed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeToken {
+    pub address: Felt252,
+}
+
+impl FeeToken {
+    pub fn new(address: Felt252) -> Self {
+        Self { address }
+    }
+
+    /// Returns the balance of the given account, in wei.
+    pub fn balance_of(
+        &self,
+        state: &dyn StateReader,
+        account: &Felt252,
+    ) -> Result<BigUint, String> {
+        let key = balance_key(account);
+        let read = |key: &Felt252| {
+            state
+                .get_storage_at(&self.address, key)
+                .map_err(|e| format!("Failed to read the fee token balance: {e}"))
+        };
+        let low = read(&key)?;
+        let high = read(&(key + Felt252::new(1)))?;
+        Ok((high.to_biguint() << 128u32) + low.to_biguint())
+    }
+
+    /// Checks the given account can pay the maximal fee of a transaction it sends.
+    pub fn check_balance(
+        &self,
+        state: &dyn StateReader,
+        account: &Felt252,
+        max_fee: &Felt252,
+    ) -> Result<(), String> {
+        if self.balance_of(state, account)? < max_fee.to_biguint() {
+            return Err("Balance too low to pay the maximal fee".into());
+        }
+        Ok(())
+    }
+
+    /// Executes an invoke transaction with the given engine, and then transfers the fee from the
+    /// sender to the sequencer of the block, see [FeeToken::charge].
+    pub fn execute_invoke(
+        &self,
+        engine: &dyn ExecutionEngine,
+        tx: &InvokeTransactionV1,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        self.charge(&tx.sender_address, &tx.max_fee, block, state, |state| {
+            engine.execute_invoke(tx, block, state)
+        })
+    }
+
+    /// Deploys an account with the given engine, and then transfers the fee from the account to
+    /// the sequencer of the block, see [FeeToken::charge]. The account pays for its own
+    /// deployment, so its address has to be funded beforehand.
+    pub fn execute_deploy_account(
+        &self,
+        engine: &dyn ExecutionEngine,
+        tx: &DeployAccountTransaction,
+        block: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        self.charge(
+            &deploy_account_address(tx),
+            &tx.max_fee,
+            block,
+            state,
+            |state| engine.execute_deploy_account(tx, block, state),
+        )
+    }
+
+    /// Declares a class with `declare`, and then transfers the fee of the declaration from the
+    /// sender to the sequencer of the block, see [FeeToken::charge]. Besides the gas `declare`
+    /// consumes validating it, declaring the class consumes [DECLARE_GAS]. Declarations whose
+    /// maximal fee doesn't pay for it are rejected.
+    pub fn execute_declare(
+        &self,
+        sender_address: &Felt252,
+        max_fee: &Felt252,
+        block: &BlockContext,
+        state: &mut CachedState,
+        declare: impl FnOnce(&mut CachedState) -> Result<TransactionExecution, String>,
+    ) -> Result<TransactionExecution, String> {
+        if block.gas_limit(max_fee) < DECLARE_GAS {
+            return Err("Maximal fee too low to declare a class".into());
+        }
+        self.charge(sender_address, max_fee, block, state, |state| {
+            let execution = declare(state)?;
+            Ok(TransactionExecution {
+                gas_consumed: execution.gas_consumed.saturating_add(DECLARE_GAS),
+                gas_price: block.gas_price,
+                ..execution
+            })
+        })
+    }
+
+    /// Executes a transaction with `execute`, and then transfers the fee from the sender to the
+    /// sequencer of the block. Transactions whose sender can't pay their maximal fee are
+    /// rejected, like the ones `execute` rejects. An execution that leaves the sender unable to
+    /// pay the fee is reverted, and the fee charged anyway.
+    pub fn charge(
+        &self,
+        sender_address: &Felt252,
+        max_fee: &Felt252,
+        block: &BlockContext,
+        state: &mut CachedState,
+        execute: impl FnOnce(&mut CachedState) -> Result<TransactionExecution, String>,
+    ) -> Result<TransactionExecution, String> {
+        self.check_balance(state, sender_address, max_fee)?;
+
+        state.push_layer();
+        let mut execution = match execute(state) {
+            Ok(execution) => execution,
+            Err(e) => {
+                state.discard_layer();
+                return Err(e);
+            }
+        };
+        match self.transfer(
+            state,
+            sender_address,
+            &block.sequencer_address,
+            execution.fee(),
+        ) {
+            Ok(event) => {
+                state.commit_layer();
+                execution.events.push(event);
+                Ok(execution)
+            }
+            Err(revert_reason) => {
+                state.discard_layer();
+                // The fee is below the maximal one, which the balance covers without the writes
+                // of the execution
+                let mut execution = TransactionExecution {
+                    gas_consumed: execution.gas_consumed,
+                    gas_price: execution.gas_price,
+                    ..TransactionExecution::reverted(revert_reason)
+                };
+                let event = self.transfer(
+                    state,
+                    sender_address,
+                    &block.sequencer_address,
+                    execution.fee(),
+                )?;
+                execution.events.push(event);
+                Ok(execution)
+            }
+        }
+    }
+
+    /// Moves the given amount from one account to the other, returning the event the token
+    /// emits for it.
+    fn transfer(
+        &self,
+        state: &mut CachedState,
+        sender: &Felt252,
+        recipient: &Felt252,
+        amount: u64,
+    ) -> Result<Event, String> {
+        let sender_balance = self.balance_of(state, sender)?;
+        if sender_balance < BigUint::from(amount) {
+            return Err("Balance too low to pay the fee".into());
+        }
+        self.set_balance(state, sender, sender_balance - amount);
+        // Read after the write, in case the sequencer pays itself
+        let recipient_balance = self.balance_of(state, recipient)?;
+        self.set_balance(state, recipient, recipient_balance + amount);
+
+        Ok(Event {
+            from_address: self.address.clone(),
+            keys: vec![selector_from_name("Transfer")],
+            data: vec![
+                sender.clone(),
+                recipient.clone(),
+                Felt252::new(amount), // amount, low
+                Felt252::new(0),      // amount, high
+            ],
+        })
+    }
+
+    fn set_balance(&self, state: &mut CachedState, account: &Felt252, balance: BigUint) {
+        let key = balance_key(account);
+        let low = &balance & BigUint::from(u128::MAX);
+        let high = balance >> 128u32;
+        state.set_storage_at(
+            self.address.clone(),
+            key.clone(),
+            Felt252::from_bytes_be(&low.to_bytes_be()),
+        );
+        state.set_storage_at(
+            self.address.clone(),
+            key + Felt252::new(1),
+            Felt252::from_bytes_be(&high.to_bytes_be()),
+        );
+    }
+}
+
+/// Returns where the ERC20 contract stores the balance of the given account: the Pedersen hash
+/// of the name of its `balances` map and the account. The low half of the `u256` balance is
+/// stored there, and the high half right after.
+fn balance_key(account: &Felt252) -> Felt252 {
+    let hash = pedersen_hash(
+        &to_field_element(&selector_from_name("balances")),
+        &to_field_element(account),
+    );
+    // Storage addresses are below 2^251 - 256
+    let upper_bound = (BigUint::from(1u8) << 251u32) - 256u32;
+    Felt252::from_bytes_be(
+        &(BigUint::from_bytes_be(&hash.to_bytes_be()) % upper_bound).to_bytes_be(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::FeeToken;
+    use crate::account::{deploy_account_address, multicall_calldata, public_key, sign, Call};
+    use crate::cairo_native_engine::program_cache::DEFAULT_PROGRAM_CACHE_SIZE;
+    use crate::cairo_native_engine::{selector_from_name, CairoNativeEngine};
+    use crate::engine::BlockContext;
+    use crate::genesis::{
+        genesis_state, AccountGenesis, FeeTokenGenesis, DEFAULT_ACCOUNT_PRIVATE_KEY,
+        DEFAULT_FEE_TOKEN_ADDRESS, FIBONACCI_ADDRESS,
+    };
+    use crate::state::{CachedState, StateReader};
+    use crate::transaction::{TransactionExecution, DECLARE_GAS};
+    use cairo_felt::Felt252;
+    use num_bigint::BigUint;
+    use types::{ChainId, DeployAccountTransaction, InvokeTransactionV1};
+
+    const SEQUENCER_ADDRESS: u64 = 0x5e9;
+
+    struct Setup {
+        engine: CairoNativeEngine,
+        fee_token: FeeToken,
+        /// The development account, holding the whole supply of the fee token
+        account: Felt252,
+        state: CachedState,
+        block: BlockContext,
+    }
+
+    /// The genesis state, with the given supply of the fee token minted to the development
+    /// account, and a block with the given gas price.
+    fn setup(initial_supply: u128, gas_price: u64) -> Setup {
+        let engine =
+            CairoNativeEngine::new(ChainId::default().to_felt(), DEFAULT_PROGRAM_CACHE_SIZE);
+        let account = AccountGenesis::dev();
+        let genesis = FeeTokenGenesis {
+            address: Felt252::new(DEFAULT_FEE_TOKEN_ADDRESS),
+            recipient: account.address.clone(),
+            initial_supply,
+        };
+        let genesis_diff = genesis_state(&engine, &genesis, &account).unwrap();
+        Setup {
+            engine,
+            fee_token: FeeToken::new(genesis.address),
+            account: account.address,
+            state: CachedState::new(Box::new(genesis_diff)),
+            block: BlockContext {
+                sequencer_address: Felt252::new(SEQUENCER_ADDRESS),
+                gas_price,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// An invoke transaction making the given call, signed with the development key.
+    fn invoke(sender_address: &Felt252, max_fee: u64, call: Call) -> InvokeTransactionV1 {
+        let mut tx = InvokeTransactionV1 {
+            transaction_hash: Felt252::new(max_fee),
+            max_fee: Felt252::new(max_fee),
+            signature: vec![],
+            nonce: Felt252::new(0),
+            sender_address: sender_address.clone(),
+            calldata: multicall_calldata(&[call]),
+        };
+        tx.signature = sign(
+            &Felt252::new(DEFAULT_ACCOUNT_PRIVATE_KEY),
+            &tx.transaction_hash,
+        );
+        tx
+    }
+
+    fn fib_call() -> Call {
+        Call {
+            to: Felt252::new(FIBONACCI_ADDRESS),
+            selector: selector_from_name("fib"),
+            calldata: vec![Felt252::new(0), Felt252::new(1), Felt252::new(10)],
+        }
+    }
+
+    #[test]
+    fn fees_are_paid_to_the_sequencer() {
+        let Setup {
+            engine,
+            fee_token,
+            account,
+            mut state,
+            block,
+        } = setup(100_000_000, 1);
+        let sequencer_address = Felt252::new(SEQUENCER_ADDRESS);
+
+        // The balance minted by the constructor is where the fees are charged from
+        assert_eq!(
+            Ok(BigUint::from(100_000_000u32)),
+            fee_token.balance_of(&state, &account)
+        );
+
+        let execution = fee_token
+            .execute_invoke(
+                &engine,
+                &invoke(&account, 10_000_000, fib_call()),
+                &block,
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(None, execution.revert_reason);
+        assert!(execution.fee() > 0);
+        assert_eq!(
+            Ok(BigUint::from(100_000_000 - execution.fee())),
+            fee_token.balance_of(&state, &account)
+        );
+        assert_eq!(
+            Ok(BigUint::from(execution.fee())),
+            fee_token.balance_of(&state, &sequencer_address)
+        );
+        assert_eq!(
+            Some(&fee_token.address),
+            execution.events.last().map(|event| &event.from_address)
+        );
+
+        // Senders have to be able to pay the maximal fee
+        assert_eq!(
+            Some("Balance too low to pay the maximal fee".to_string()),
+            fee_token
+                .execute_invoke(
+                    &engine,
+                    &invoke(&account, 200_000_000, fib_call()),
+                    &block,
+                    &mut state
+                )
+                .err()
+        );
+        assert!(fee_token
+            .execute_invoke(
+                &engine,
+                &invoke(&Felt252::new(0x404), 1, fib_call()),
+                &block,
+                &mut state
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn executions_draining_the_fee_are_reverted_and_still_charged() {
+        let Setup {
+            engine,
+            fee_token,
+            account,
+            mut state,
+            block,
+        } = setup(100_000_000, 1);
+        let recipient = Felt252::new(0x404);
+
+        // The account transfers its whole balance away, leaving nothing to pay the fee with
+        let transfer = Call {
+            to: fee_token.address.clone(),
+            selector: selector_from_name("transfer"),
+            calldata: vec![
+                recipient.clone(),
+                Felt252::new(100_000_000), // amount, low
+                Felt252::new(0),           // amount, high
+            ],
+        };
+        let execution = fee_token
+            .execute_invoke(
+                &engine,
+                &invoke(&account, 10_000_000, transfer),
+                &block,
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(
+            Some("Balance too low to pay the fee".to_string()),
+            execution.revert_reason
+        );
+        assert!(execution.fee() > 0);
+
+        // The fee is paid out of the balance the account had before the transfer
+        assert_eq!(
+            Ok(BigUint::from(100_000_000 - execution.fee())),
+            fee_token.balance_of(&state, &account)
+        );
+        assert_eq!(
+            Ok(BigUint::from(0u8)),
+            fee_token.balance_of(&state, &recipient)
+        );
+        assert_eq!(
+            Ok(BigUint::from(execution.fee())),
+            fee_token.balance_of(&state, &Felt252::new(SEQUENCER_ADDRESS))
+        );
+    }
+
+    #[test]
+    fn declarations_are_charged() {
+        let Setup {
+            fee_token,
+            account,
+            mut state,
+            block,
+            ..
+        } = setup(100_000_000, 2);
+
+        let execution = fee_token
+            .execute_declare(
+                &account,
+                &Felt252::new(2 * DECLARE_GAS),
+                &block,
+                &mut state,
+                |_| Ok(TransactionExecution::default()),
+            )
+            .unwrap();
+        assert_eq!(2 * DECLARE_GAS, execution.fee());
+        assert_eq!(
+            Ok(BigUint::from(100_000_000 - 2 * DECLARE_GAS)),
+            fee_token.balance_of(&state, &account)
+        );
+
+        // The maximal fee has to pay for the declaration
+        assert_eq!(
+            Some("Maximal fee too low to declare a class".to_string()),
+            fee_token
+                .execute_declare(
+                    &account,
+                    &Felt252::new(2 * DECLARE_GAS - 1),
+                    &block,
+                    &mut state,
+                    |_| Ok(TransactionExecution::default()),
+                )
+                .err()
+        );
+    }
+
+    #[test]
+    fn accounts_pay_for_their_deployment() {
+        let Setup {
+            engine,
+            fee_token,
+            account,
+            mut state,
+            block,
+        } = setup(100_000_000, 1);
+        let private_key = Felt252::new(0xb0b);
+        let mut tx = DeployAccountTransaction {
+            transaction_hash: Felt252::new(0),
+            max_fee: Felt252::new(10_000_000),
+            signature: vec![],
+            nonce: Felt252::new(0),
+            contract_address_salt: Felt252::new(7),
+            constructor_calldata: vec![public_key(&private_key)],
+            class_hash: state.get_class_hash_at(&account).unwrap(),
+        };
+        tx.transaction_hash = tx.calculate_hash(&ChainId::default().to_felt());
+        tx.signature = sign(&private_key, &tx.transaction_hash);
+        let contract_address = deploy_account_address(&tx);
+
+        // The address has to be funded before the account is deployed
+        assert_eq!(
+            Some("Balance too low to pay the maximal fee".to_string()),
+            fee_token
+                .execute_deploy_account(&engine, &tx, &block, &mut state)
+                .err()
+        );
+
+        fee_token.set_balance(&mut state, &contract_address, BigUint::from(10_000_000u32));
+        let execution = fee_token
+            .execute_deploy_account(&engine, &tx, &block, &mut state)
+            .unwrap();
+        assert!(execution.fee() > 0);
+        assert_eq!(
+            Ok(BigUint::from(10_000_000 - execution.fee())),
+            fee_token.balance_of(&state, &contract_address)
+        );
+    }
+}
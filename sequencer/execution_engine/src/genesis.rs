@@ -0,0 +1,132 @@
+use crate::account::public_key;
+use crate::cairo_native_engine::CairoNativeEngine;
+use crate::contract_class::compile_contract;
+use crate::engine::{ExecutionContext, MAX_CALL_GAS};
+use crate::state::CachedState;
+use anyhow::{anyhow, Result};
+use cairo_felt::Felt252;
+use sequencer::store::state::StateDiff;
+use std::path::Path;
+use types::ContractClass;
+
+/// Address of the bundled Fibonacci contract.
+pub const FIBONACCI_ADDRESS: u64 = 0x1001;
+/// Address of the bundled Factorial contract.
+pub const FACTORIAL_ADDRESS: u64 = 0x1002;
+/// Address of the bundled ERC20 contract.
+pub const ERC20_ADDRESS: u64 = 0x1003;
+/// Account the initial supply of the bundled ERC20 contract is minted to.
+pub const ERC20_RECIPIENT: u64 = 0x2001;
+/// Initial supply of the bundled ERC20 contract.
+pub const ERC20_INITIAL_SUPPLY: u64 = 1_000_000_000;
+/// Address the fee token is deployed at unless another one is configured.
+pub const DEFAULT_FEE_TOKEN_ADDRESS: u64 = 0x1004;
+/// Fee token supply unless another one is configured, in wei.
+pub const DEFAULT_FEE_TOKEN_SUPPLY: u128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// How the fee token, an ERC20 contract of the bundled class, is deployed at genesis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeTokenGenesis {
+    pub address: Felt252,
+    /// Account the whole supply is minted to
+    pub recipient: Felt252,
+    /// Supply minted, in wei
+    pub initial_supply: u128,
+}
+
+/// Address of the account deployed at genesis unless another one is configured: the sender of
+/// the transactions of the benchmark client.
+pub const DEFAULT_ACCOUNT_ADDRESS: u64 = 0x5701712;
+/// Private key of the development account, see [AccountGenesis::dev]. Anyone can sign for the
+/// account with it, so it is only fit for local chains.
+pub const DEFAULT_ACCOUNT_PRIVATE_KEY: u64 = 0xa11ce;
+
+/// How the account deployed at genesis, of the bundled account class, is set up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountGenesis {
+    pub address: Felt252,
+    /// Key the signatures of the transactions the account sends are checked against
+    pub public_key: Felt252,
+}
+
+impl AccountGenesis {
+    /// The development account: the one at `DEFAULT_ACCOUNT_ADDRESS` whose private key is
+    /// `DEFAULT_ACCOUNT_PRIVATE_KEY`, which the benchmark client signs with.
+    pub fn dev() -> Self {
+        Self {
+            address: Felt252::new(DEFAULT_ACCOUNT_ADDRESS),
+            public_key: public_key(&Felt252::new(DEFAULT_ACCOUNT_PRIVATE_KEY)),
+        }
+    }
+}
+
+/// Declares the contracts bundled with the sequencer and deploys them at their fixed addresses,
+/// so invoke transactions have contracts to call from the first block, along with the fee token
+/// and an account to send them. Returns the resulting changes to the empty state.
+pub fn genesis_state(
+    engine: &CairoNativeEngine,
+    fee_token: &FeeTokenGenesis,
+    account: &AccountGenesis,
+) -> Result<StateDiff> {
+    let mut state = CachedState::default();
+
+    let contracts = [
+        (
+            "../cairo_programs/fib_contract.cairo",
+            Felt252::new(FIBONACCI_ADDRESS),
+            vec![],
+        ),
+        (
+            "../cairo_programs/fact_contract.cairo",
+            Felt252::new(FACTORIAL_ADDRESS),
+            vec![],
+        ),
+        (
+            "../cairo_programs/erc20.cairo",
+            Felt252::new(ERC20_ADDRESS),
+            vec![
+                Felt252::from_bytes_be(b"name"),    // name
+                Felt252::from_bytes_be(b"symbol"),  // symbol
+                Felt252::new(0),                    // decimals
+                Felt252::new(ERC20_INITIAL_SUPPLY), // initial supply, low
+                Felt252::new(0),                    // initial supply, high
+                Felt252::new(ERC20_RECIPIENT),      // recipient
+            ],
+        ),
+        (
+            "../cairo_programs/erc20.cairo",
+            fee_token.address.clone(),
+            vec![
+                Felt252::from_bytes_be(b"Ether"),       // name
+                Felt252::from_bytes_be(b"ETH"),         // symbol
+                Felt252::new(18),                       // decimals
+                Felt252::new(fee_token.initial_supply), // initial supply, low
+                Felt252::new(0),                        // initial supply, high
+                fee_token.recipient.clone(),            // recipient
+            ],
+        ),
+        (
+            "../cairo_programs/account.cairo",
+            account.address.clone(),
+            vec![account.public_key.clone()],
+        ),
+    ];
+    for (path, contract_address, constructor_calldata) in contracts {
+        let class = ContractClass::Sierra(compile_contract(Path::new(path))?);
+        let class_hash = class.class_hash();
+        state.declare_class(class_hash.clone(), class);
+
+        engine
+            .deploy_contract(
+                &contract_address,
+                &class_hash,
+                &constructor_calldata,
+                &Felt252::new(0),
+                MAX_CALL_GAS,
+                &ExecutionContext::default(),
+                &mut state,
+            )
+            .map_err(|e| anyhow!("Failed to deploy {path}: {e}"))?;
+    }
+    Ok(state.into_state_diff())
+}
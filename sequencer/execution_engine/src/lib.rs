@@ -1,4 +1,13 @@
 #![feature(strict_provenance)]
 #![feature(iter_intersperse)]
+pub mod account;
 pub mod cairo_native_engine;
 pub mod cairovm_engine;
+pub mod contract_address;
+pub mod contract_class;
+pub mod engine;
+pub mod fee;
+pub mod genesis;
+pub mod secp256;
+pub mod state;
+pub mod transaction;
@@ -0,0 +1,216 @@
+use num_bigint::BigUint;
+use std::sync::OnceLock;
+
+/// A point of a curve in affine coordinates. As in Cairo, `(0, 0)` stands for the point at
+/// infinity, which can't be confused with a point of the curves below since neither goes through
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Point {
+    pub x: BigUint,
+    pub y: BigUint,
+}
+
+impl Point {
+    pub fn infinity() -> Self {
+        Self {
+            x: BigUint::default(),
+            y: BigUint::default(),
+        }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.x == BigUint::default() && self.y == BigUint::default()
+    }
+}
+
+/// A short Weierstrass curve `y^2 = x^3 + a * x + b` over the integers modulo the prime `p`.
+/// Both curves Starknet has syscalls for have `p = 3 (mod 4)`, which square roots rely on.
+#[derive(Debug, Clone)]
+pub struct Curve {
+    p: BigUint,
+    a: BigUint,
+    b: BigUint,
+}
+
+fn parse_hex(hex: &str) -> BigUint {
+    BigUint::parse_bytes(hex.as_bytes(), 16).expect("Invalid curve parameter")
+}
+
+/// The curve of Ethereum signatures.
+pub fn secp256k1() -> &'static Curve {
+    static CURVE: OnceLock<Curve> = OnceLock::new();
+    CURVE.get_or_init(|| Curve {
+        p: parse_hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"),
+        a: BigUint::from(0u8),
+        b: BigUint::from(7u8),
+    })
+}
+
+/// The curve of WebAuthn signatures, also known as P-256.
+pub fn secp256r1() -> &'static Curve {
+    static CURVE: OnceLock<Curve> = OnceLock::new();
+    CURVE.get_or_init(|| Curve {
+        p: parse_hex("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"),
+        a: parse_hex("ffffffff00000001000000000000000000000000fffffffffffffffffffffffc"),
+        b: parse_hex("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+    })
+}
+
+impl Curve {
+    /// Coordinates are only valid below the modulus.
+    pub fn modulus(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// Returns the point with the given coordinates, if it is on the curve.
+    pub fn point(&self, x: BigUint, y: BigUint) -> Option<Point> {
+        let point = Point { x, y };
+        (point.is_infinity() || self.mul_mod(&point.y, &point.y) == self.curve_rhs(&point.x))
+            .then_some(point)
+    }
+
+    /// Returns the point with the given x coordinate and a y coordinate of the given parity
+    /// (true for odd), if there is one.
+    pub fn point_from_x(&self, x: BigUint, y_parity: bool) -> Option<Point> {
+        let rhs = self.curve_rhs(&x);
+        // Since p = 3 (mod 4), a square root of rhs is rhs^((p + 1) / 4), if it has any
+        let y = rhs.modpow(&((&self.p + 1u8) >> 2), &self.p);
+        if self.mul_mod(&y, &y) != rhs {
+            return None;
+        }
+        let y = if y.bit(0) == y_parity {
+            y
+        } else {
+            (&self.p - y) % &self.p
+        };
+        Some(Point { x, y })
+    }
+
+    /// Adds two points of the curve.
+    pub fn add(&self, p0: &Point, p1: &Point) -> Point {
+        if p0.is_infinity() {
+            return p1.clone();
+        }
+        if p1.is_infinity() {
+            return p0.clone();
+        }
+        let slope = if p0.x == p1.x {
+            if (&p0.y + &p1.y) % &self.p == BigUint::default() {
+                return Point::infinity();
+            }
+            // Doubling: the slope is the one of the tangent, (3 * x^2 + a) / (2 * y)
+            let numerator = (self.mul_mod(&p0.x, &p0.x) * 3u8 + &self.a) % &self.p;
+            self.mul_mod(&numerator, &self.inverse(&(&p0.y * 2u8)))
+        } else {
+            let numerator = self.sub_mod(&p1.y, &p0.y);
+            self.mul_mod(&numerator, &self.inverse(&self.sub_mod(&p1.x, &p0.x)))
+        };
+        let x = self.sub_mod(&self.sub_mod(&self.mul_mod(&slope, &slope), &p0.x), &p1.x);
+        let y = self.sub_mod(&self.mul_mod(&slope, &self.sub_mod(&p0.x, &x)), &p0.y);
+        Point { x, y }
+    }
+
+    /// Multiplies a point of the curve by a scalar, by double and add.
+    pub fn mul(&self, point: &Point, scalar: &BigUint) -> Point {
+        let mut result = Point::infinity();
+        for i in (0..scalar.bits()).rev() {
+            result = self.add(&result, &result);
+            if scalar.bit(i) {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+
+    /// Returns `x^3 + a * x + b`, the square of the y coordinates of the points with the given x.
+    fn curve_rhs(&self, x: &BigUint) -> BigUint {
+        (self.mul_mod(&self.mul_mod(x, x), x) + self.mul_mod(&self.a, x) + &self.b) % &self.p
+    }
+
+    fn mul_mod(&self, lhs: &BigUint, rhs: &BigUint) -> BigUint {
+        (lhs * rhs) % &self.p
+    }
+
+    fn sub_mod(&self, lhs: &BigUint, rhs: &BigUint) -> BigUint {
+        (lhs + &self.p - rhs % &self.p) % &self.p
+    }
+
+    /// Inverse modulo p of a non zero value, by Fermat's little theorem.
+    fn inverse(&self, value: &BigUint) -> BigUint {
+        value.modpow(&(&self.p - 2u8), &self.p)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_hex, secp256k1, secp256r1, Point};
+
+    fn point(x: &str, y: &str) -> Point {
+        Point {
+            x: parse_hex(x),
+            y: parse_hex(y),
+        }
+    }
+
+    #[test]
+    fn secp256k1_arithmetic_matches_known_points() {
+        let curve = secp256k1();
+        let generator = point(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        );
+        let double = point(
+            "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a",
+        );
+        let order = parse_hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+
+        assert_eq!(
+            Some(generator.clone()),
+            curve.point(generator.x.clone(), generator.y.clone())
+        );
+        assert_eq!(None, curve.point(generator.x.clone(), double.y.clone()));
+        assert_eq!(double, curve.add(&generator, &generator));
+        assert_eq!(double, curve.mul(&generator, &2u8.into()));
+        assert_eq!(Point::infinity(), curve.mul(&generator, &order));
+        assert_eq!(generator, curve.mul(&generator, &(order + 1u8)));
+        assert_eq!(
+            Some(generator.clone()),
+            curve.point_from_x(generator.x.clone(), false)
+        );
+        assert_eq!(
+            Some(curve.modulus() - &generator.y),
+            curve.point_from_x(generator.x, true).map(|point| point.y)
+        );
+    }
+
+    #[test]
+    fn secp256r1_arithmetic_matches_known_points() {
+        let curve = secp256r1();
+        let generator = point(
+            "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+        );
+        let double = point(
+            "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+            "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+        );
+
+        assert_eq!(
+            Some(generator.clone()),
+            curve.point(generator.x.clone(), generator.y.clone())
+        );
+        assert_eq!(double, curve.add(&generator, &generator));
+        assert_eq!(double, curve.mul(&generator, &2u8.into()));
+        assert_eq!(
+            Some(generator.clone()),
+            curve.point_from_x(generator.x.clone(), true)
+        );
+        assert_eq!(double, curve.add(&double, &Point::infinity()));
+        let negated = Point {
+            x: double.x.clone(),
+            y: curve.modulus() - &double.y,
+        };
+        assert_eq!(Point::infinity(), curve.add(&double, &negated));
+    }
+}
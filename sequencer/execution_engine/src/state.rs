@@ -0,0 +1,324 @@
+use anyhow::Result;
+use cairo_felt::Felt252;
+use sequencer::store::{state::StateDiff, Store};
+use std::fmt::Debug;
+use types::{ContractClass, MaybePendingBlockWithTxs};
+
+/// Read access to the contracts state transactions are executed against.
+pub trait StateReader: Debug + Send {
+    fn get_storage_at(&self, contract_address: &Felt252, key: &Felt252) -> Result<Felt252>;
+    fn get_nonce_at(&self, contract_address: &Felt252) -> Result<Felt252>;
+    fn get_class_hash_at(&self, contract_address: &Felt252) -> Result<Felt252>;
+    /// Returns the class declared under the given hash, if it was declared.
+    fn get_class(&self, class_hash: &Felt252) -> Result<Option<ContractClass>>;
+    /// Returns the hash of the given block, if it is part of the state history.
+    fn get_block_hash(&self, block_number: u64) -> Result<Option<Felt252>>;
+}
+
+/// The state persisted by the sequencer store, as it was at the end of the given block.
+#[derive(Debug, Clone)]
+pub struct StoreState {
+    store: Store,
+    block_number: u64,
+}
+
+impl StoreState {
+    pub fn new(store: Store, block_number: u64) -> Self {
+        Self {
+            store,
+            block_number,
+        }
+    }
+}
+
+impl StateReader for StoreState {
+    fn get_storage_at(&self, contract_address: &Felt252, key: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .store
+            .get_storage_at(contract_address, key, self.block_number)?
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_nonce_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .store
+            .get_nonce(contract_address, self.block_number)?
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_class_hash_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .store
+            .get_class_hash_at(contract_address, self.block_number)?
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_class(&self, class_hash: &Felt252) -> Result<Option<ContractClass>> {
+        self.store.get_class(class_hash, self.block_number)
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<Option<Felt252>> {
+        if block_number > self.block_number {
+            return Ok(None);
+        }
+        Ok(match self.store.get_block_by_height(block_number)? {
+            Some(MaybePendingBlockWithTxs::Block(block)) => Some(block.block_hash),
+            _ => None,
+        })
+    }
+}
+
+/// A state diff can be read as a whole state, where everything it doesn't set is zero.
+impl StateReader for StateDiff {
+    fn get_storage_at(&self, contract_address: &Felt252, key: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .storage
+            .get(contract_address)
+            .and_then(|storage| storage.get(key))
+            .cloned()
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_nonce_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .nonces
+            .get(contract_address)
+            .cloned()
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_class_hash_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        Ok(self
+            .class_hashes
+            .get(contract_address)
+            .cloned()
+            .unwrap_or_else(|| Felt252::new(0)))
+    }
+
+    fn get_class(&self, class_hash: &Felt252) -> Result<Option<ContractClass>> {
+        Ok(self.declared_classes.get(class_hash).cloned())
+    }
+
+    /// Diffs hold no blocks.
+    fn get_block_hash(&self, _block_number: u64) -> Result<Option<Felt252>> {
+        Ok(None)
+    }
+}
+
+/// Keeps the writes of the executed transactions on top of a state, without modifying it. The
+/// writes are collected as a [StateDiff], to be persisted once the block is committed.
+///
+/// Writes can be grouped in layers, one per running call, so the ones of a failed call are
+/// discarded without touching the writes made before it.
+#[derive(Debug)]
+pub struct CachedState {
+    state: Box<dyn StateReader>,
+    diff: StateDiff,
+    /// Writes of the open layers, innermost last
+    layers: Vec<StateDiff>,
+}
+
+impl Default for CachedState {
+    fn default() -> Self {
+        Self::new(Box::<StateDiff>::default())
+    }
+}
+
+impl CachedState {
+    pub fn new(state: Box<dyn StateReader>) -> Self {
+        Self::with_state_diff(state, StateDiff::default())
+    }
+
+    /// Starts from changes already made on top of the state, which are kept in the diff.
+    pub fn with_state_diff(state: Box<dyn StateReader>, diff: StateDiff) -> Self {
+        Self {
+            state,
+            diff,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Where writes go: the innermost layer, if any is open.
+    fn writes(&mut self) -> &mut StateDiff {
+        self.layers.last_mut().unwrap_or(&mut self.diff)
+    }
+
+    /// Walks the writes from the newest to the oldest.
+    fn diffs(&self) -> impl Iterator<Item = &StateDiff> {
+        self.layers.iter().rev().chain(std::iter::once(&self.diff))
+    }
+
+    /// Opens a layer, recording the writes made from now on apart from the previous ones.
+    pub fn push_layer(&mut self) {
+        self.layers.push(StateDiff::default());
+    }
+
+    /// Closes the innermost layer, keeping its writes. Returns the writes made within it.
+    pub fn commit_layer(&mut self) -> StateDiff {
+        let layer = self.layers.pop().expect("No layer to commit");
+        self.writes().extend(layer.clone());
+        layer
+    }
+
+    /// Closes the innermost layer, dropping its writes.
+    pub fn discard_layer(&mut self) {
+        self.layers.pop().expect("No layer to discard");
+    }
+
+    pub fn set_storage_at(&mut self, contract_address: Felt252, key: Felt252, value: Felt252) {
+        self.writes()
+            .storage
+            .entry(contract_address)
+            .or_default()
+            .insert(key, value);
+    }
+
+    /// Increments the nonce of the contract by one.
+    pub fn increment_nonce(&mut self, contract_address: &Felt252) -> Result<()> {
+        let nonce = self.get_nonce_at(contract_address)? + Felt252::new(1);
+        self.writes().nonces.insert(contract_address.clone(), nonce);
+        Ok(())
+    }
+
+    pub fn set_class_hash_at(&mut self, contract_address: Felt252, class_hash: Felt252) {
+        self.writes()
+            .class_hashes
+            .insert(contract_address, class_hash);
+    }
+
+    pub fn declare_class(&mut self, class_hash: Felt252, class: ContractClass) {
+        self.writes().declared_classes.insert(class_hash, class);
+    }
+
+    /// Returns the changes made on top of the underlying state, leaving out the open layers.
+    pub fn state_diff(&self) -> &StateDiff {
+        &self.diff
+    }
+
+    /// Returns the changes made on top of the underlying state, including the writes of the open layers.
+    pub fn into_state_diff(mut self) -> StateDiff {
+        while !self.layers.is_empty() {
+            self.commit_layer();
+        }
+        self.diff
+    }
+}
+
+impl StateReader for CachedState {
+    fn get_storage_at(&self, contract_address: &Felt252, key: &Felt252) -> Result<Felt252> {
+        match self.diffs().find_map(|diff| {
+            diff.storage
+                .get(contract_address)
+                .and_then(|storage| storage.get(key))
+        }) {
+            Some(value) => Ok(value.clone()),
+            None => self.state.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_nonce_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        match self
+            .diffs()
+            .find_map(|diff| diff.nonces.get(contract_address))
+        {
+            Some(nonce) => Ok(nonce.clone()),
+            None => self.state.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(&self, contract_address: &Felt252) -> Result<Felt252> {
+        match self
+            .diffs()
+            .find_map(|diff| diff.class_hashes.get(contract_address))
+        {
+            Some(class_hash) => Ok(class_hash.clone()),
+            None => self.state.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_class(&self, class_hash: &Felt252) -> Result<Option<ContractClass>> {
+        match self
+            .diffs()
+            .find_map(|diff| diff.declared_classes.get(class_hash))
+        {
+            Some(class) => Ok(Some(class.clone())),
+            None => self.state.get_class(class_hash),
+        }
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<Option<Felt252>> {
+        self.state.get_block_hash(block_number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CachedState, StateReader};
+    use cairo_felt::Felt252;
+    use sequencer::store::state::StateDiff;
+
+    #[test]
+    fn cached_state_reads_its_own_writes() {
+        let contract_address = Felt252::new(1);
+        let mut state = StateDiff::default();
+        state
+            .nonces
+            .insert(contract_address.clone(), Felt252::new(4));
+        let mut cached_state = CachedState::new(Box::new(state));
+
+        cached_state.set_storage_at(contract_address.clone(), Felt252::new(2), Felt252::new(3));
+        cached_state.increment_nonce(&contract_address).unwrap();
+
+        assert_eq!(
+            Felt252::new(3),
+            cached_state
+                .get_storage_at(&contract_address, &Felt252::new(2))
+                .unwrap()
+        );
+        assert_eq!(
+            Felt252::new(5),
+            cached_state.get_nonce_at(&contract_address).unwrap()
+        );
+
+        // Only the writes are kept in the diff
+        let state_diff = cached_state.into_state_diff();
+        assert_eq!(
+            Some(&Felt252::new(5)),
+            state_diff.nonces.get(&contract_address)
+        );
+        assert!(state_diff.class_hashes.is_empty());
+    }
+
+    #[test]
+    fn discarded_layers_drop_their_writes_only() {
+        let contract_address = Felt252::new(1);
+        let mut cached_state = CachedState::default();
+        cached_state.set_storage_at(contract_address.clone(), Felt252::new(2), Felt252::new(3));
+
+        cached_state.push_layer();
+        cached_state.set_storage_at(contract_address.clone(), Felt252::new(2), Felt252::new(4));
+        cached_state.push_layer();
+        cached_state.increment_nonce(&contract_address).unwrap();
+        cached_state.discard_layer();
+        assert_eq!(
+            Felt252::new(0),
+            cached_state.get_nonce_at(&contract_address).unwrap()
+        );
+
+        let layer = cached_state.commit_layer();
+        assert_eq!(
+            Some(&Felt252::new(4)),
+            layer
+                .storage
+                .get(&contract_address)
+                .and_then(|storage| storage.get(&Felt252::new(2)))
+        );
+        assert!(layer.nonces.is_empty());
+        assert_eq!(
+            Felt252::new(4),
+            cached_state
+                .get_storage_at(&contract_address, &Felt252::new(2))
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use types::{Event, MsgToL1, TransactionExecutionStatus};
+
+/// Price of a unit of gas, in wei, unless another one is configured.
+pub const DEFAULT_GAS_PRICE: u64 = 1;
+
+/// Gas declaring a class consumes, whatever the class.
+pub const DECLARE_GAS: u64 = 10_000;
+
+/// What executing a transaction did, besides the state changes it made.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionExecution {
+    /// Events emitted during the execution, in emission order
+    pub events: Vec<Event>,
+    /// Messages sent to L1 during the execution, in sending order
+    pub messages: Vec<MsgToL1>,
+    /// Gas consumed by the execution
+    pub gas_consumed: u64,
+    /// Price the gas was consumed at, in wei
+    pub gas_price: u64,
+    /// Why the execution failed, if it did
+    pub revert_reason: Option<String>,
+}
+
+impl TransactionExecution {
+    pub fn reverted(revert_reason: impl ToString) -> Self {
+        Self {
+            revert_reason: Some(revert_reason.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Status the receipt of the transaction shows.
+    pub fn execution_status(&self) -> TransactionExecutionStatus {
+        match self.revert_reason {
+            Some(_) => TransactionExecutionStatus::Reverted,
+            None => TransactionExecutionStatus::Succeeded,
+        }
+    }
+
+    /// Fee charged for the gas consumed.
+    pub fn fee(&self) -> u64 {
+        self.gas_consumed.saturating_mul(self.gas_price)
+    }
+}
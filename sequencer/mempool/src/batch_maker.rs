@@ -1,15 +1,11 @@
 use crate::mempool::MempoolMessage;
 use crate::quorum_waiter::QuorumWaiterMessage;
 use bytes::Bytes;
-#[cfg(feature = "benchmark")]
-use crypto::Digest;
-use crypto::PublicKey;
-#[cfg(feature = "benchmark")]
+use crypto::{Digest, PublicKey};
 use ed25519_dalek::{Digest as _, Sha512};
 #[cfg(feature = "benchmark")]
 use log::info;
 use network::ReliableSender;
-#[cfg(feature = "benchmark")]
 use std::convert::TryInto as _;
 use std::net::SocketAddr;
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -22,6 +18,15 @@ pub mod batch_maker_tests;
 pub type Transaction = Vec<u8>;
 pub type Batch = Vec<Transaction>;
 
+/// Tells the rest of the node about the clients transactions going through the `BatchMaker`.
+#[derive(Debug)]
+pub enum TransactionNotification {
+    /// The transaction was received and waits to be sealed in a batch.
+    Received(Transaction),
+    /// The transactions were sealed in the batch with the given digest.
+    Sealed(Digest, Batch),
+}
+
 /// Assemble clients transactions into batches.
 pub struct BatchMaker {
     /// The preferred batch size (in bytes).
@@ -32,6 +37,9 @@ pub struct BatchMaker {
     rx_transaction: Receiver<Transaction>,
     /// Output channel to deliver sealed batches to the `QuorumWaiter`.
     tx_message: Sender<QuorumWaiterMessage>,
+    /// Output channel to notify received and sealed transactions. Notifications are best effort:
+    /// they are dropped rather than slowing the batch maker down.
+    tx_notification: Sender<TransactionNotification>,
     /// The network addresses of the other mempools.
     mempool_addresses: Vec<(PublicKey, SocketAddr)>,
     /// Holds the current batch.
@@ -48,6 +56,7 @@ impl BatchMaker {
         max_batch_delay: u64,
         rx_transaction: Receiver<Transaction>,
         tx_message: Sender<QuorumWaiterMessage>,
+        tx_notification: Sender<TransactionNotification>,
         mempool_addresses: Vec<(PublicKey, SocketAddr)>,
     ) {
         tokio::spawn(async move {
@@ -56,6 +65,7 @@ impl BatchMaker {
                 max_batch_delay,
                 rx_transaction,
                 tx_message,
+                tx_notification,
                 mempool_addresses,
                 current_batch: Batch::with_capacity(batch_size * 2),
                 current_batch_size: 0,
@@ -75,6 +85,9 @@ impl BatchMaker {
             tokio::select! {
                 // Assemble client transactions into batches of preset size.
                 Some(transaction) = self.rx_transaction.recv() => {
+                    let _ = self
+                        .tx_notification
+                        .try_send(TransactionNotification::Received(transaction.clone()));
                     self.current_batch_size += transaction.len();
                     self.current_batch.push(transaction);
                     if self.current_batch_size >= self.batch_size {
@@ -117,15 +130,16 @@ impl BatchMaker {
         let message = MempoolMessage::Batch(batch);
         let serialized = bincode::serialize(&message).expect("Failed to serialize our own batch");
 
+        // NOTE: This is one extra hash, the `Processor` computes the same digest when storing the
+        // batch.
+        let digest = Digest(
+            Sha512::digest(&serialized).as_slice()[..32]
+                .try_into()
+                .unwrap(),
+        );
+
         #[cfg(feature = "benchmark")]
         {
-            // NOTE: This is one extra hash that is only needed to print the following log entries.
-            let digest = Digest(
-                Sha512::digest(&serialized).as_slice()[..32]
-                    .try_into()
-                    .unwrap(),
-            );
-
             for id in tx_ids {
                 // NOTE: This log entry is used to compute performance.
                 info!(
@@ -139,6 +153,12 @@ impl BatchMaker {
             info!("Batch {:?} contains {} B", digest, size);
         }
 
+        if let MempoolMessage::Batch(batch) = message {
+            let _ = self
+                .tx_notification
+                .try_send(TransactionNotification::Sealed(digest, batch));
+        }
+
         // Broadcast the batch through the network.
         let (names, addresses): (Vec<_>, _) = self.mempool_addresses.iter().cloned().unzip();
         let bytes = Bytes::from(serialized.clone());
@@ -10,6 +10,7 @@ mod synchronizer;
 #[path = "tests/common.rs"]
 mod common;
 
+pub use crate::batch_maker::TransactionNotification;
 pub use crate::config::{Committee, Parameters};
 pub use crate::mempool::{ConsensusMempoolMessage, Mempool};
 
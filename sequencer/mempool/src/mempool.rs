@@ -1,4 +1,4 @@
-use crate::batch_maker::{Batch, BatchMaker, Transaction};
+use crate::batch_maker::{Batch, BatchMaker, Transaction, TransactionNotification};
 use crate::config::{Committee, Parameters};
 use crate::helper::Helper;
 use crate::processor::{Processor, SerializedBatchMessage};
@@ -12,6 +12,8 @@ use log::{info, warn};
 use network::{MessageHandler, Receiver as NetworkReceiver, Writer};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use store::Store;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
@@ -55,6 +57,10 @@ pub struct Mempool {
 }
 
 impl Mempool {
+    /// Spawns the mempool tasks. The transactions received from clients and the batches they are
+    /// sealed in are notified through `tx_notification`. Returns a counter of the batches this
+    /// node is waiting to receive from others in order to catch up.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         name: PublicKey,
         committee: Committee,
@@ -62,7 +68,9 @@ impl Mempool {
         store: Store,
         rx_consensus: Receiver<ConsensusMempoolMessage>,
         tx_consensus: Sender<Digest>,
-    ) {
+        rx_transactions: Receiver<Transaction>,
+        tx_notification: Sender<TransactionNotification>,
+    ) -> Arc<AtomicUsize> {
         // NOTE: This log entry is used to compute performance.
         parameters.log();
 
@@ -76,8 +84,8 @@ impl Mempool {
         };
 
         // Spawn all mempool tasks.
-        mempool.handle_consensus_messages(rx_consensus);
-        mempool.handle_clients_transactions();
+        let pending_batches = mempool.handle_consensus_messages(rx_consensus);
+        mempool.handle_clients_transactions(rx_transactions, tx_notification);
         mempool.handle_mempool_messages();
 
         info!(
@@ -88,10 +96,16 @@ impl Mempool {
                 .expect("Our public key is not in the committee")
                 .ip()
         );
+
+        pending_batches
     }
 
-    /// Spawn all tasks responsible to handle messages from the consensus.
-    fn handle_consensus_messages(&self, rx_consensus: Receiver<ConsensusMempoolMessage>) {
+    /// Spawn all tasks responsible to handle messages from the consensus. Returns a counter of the
+    /// batches the mempool is waiting to receive from other nodes.
+    fn handle_consensus_messages(
+        &self,
+        rx_consensus: Receiver<ConsensusMempoolMessage>,
+    ) -> Arc<AtomicUsize> {
         // The `Synchronizer` is responsible to keep the mempool in sync with the others. It handles the commands
         // it receives from the consensus (which are mainly notifications that we are out of sync).
         Synchronizer::spawn(
@@ -102,11 +116,15 @@ impl Mempool {
             self.parameters.sync_retry_delay,
             self.parameters.sync_retry_nodes,
             /* rx_message */ rx_consensus,
-        );
+        )
     }
 
     /// Spawn all tasks responsible to handle clients transactions.
-    fn handle_clients_transactions(&self) {
+    fn handle_clients_transactions(
+        &self,
+        mut rx_transactions: Receiver<Transaction>,
+        tx_notification: Sender<TransactionNotification>,
+    ) {
         let (tx_batch_maker, rx_batch_maker) = channel(CHANNEL_CAPACITY);
         let (tx_quorum_waiter, rx_quorum_waiter) = channel(CHANNEL_CAPACITY);
         let (tx_processor, rx_processor) = channel(CHANNEL_CAPACITY);
@@ -119,9 +137,23 @@ impl Mempool {
         address.set_ip("0.0.0.0".parse().unwrap());
         NetworkReceiver::spawn(
             address,
-            /* handler */ TxReceiverHandler { tx_batch_maker },
+            /* handler */
+            TxReceiverHandler {
+                tx_batch_maker: tx_batch_maker.clone(),
+            },
         );
 
+        // Transactions may also be submitted locally by other components of the node (such as the
+        // RPC endpoint). They are fed to the `BatchMaker` just like the ones coming from the network.
+        tokio::spawn(async move {
+            while let Some(transaction) = rx_transactions.recv().await {
+                tx_batch_maker
+                    .send(transaction)
+                    .await
+                    .expect("Failed to send transaction");
+            }
+        });
+
         // The transactions are sent to the `BatchMaker` that assembles them into batches. It then broadcasts
         // (in a reliable manner) the batches to all other mempools that share the same `id` as us. Finally,
         // it gathers the 'cancel handlers' of the messages and send them to the `QuorumWaiter`.
@@ -130,6 +162,7 @@ impl Mempool {
             self.parameters.max_batch_delay,
             /* rx_transaction */ rx_batch_maker,
             /* tx_message */ tx_quorum_waiter,
+            tx_notification,
             /* mempool_addresses */
             self.committee.broadcast_addresses(&self.name),
         );
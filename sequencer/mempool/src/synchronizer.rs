@@ -7,6 +7,8 @@ use futures::stream::StreamExt as _;
 use log::{debug, error};
 use network::SimpleSender;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use store::{Store, StoreError};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -44,9 +46,13 @@ pub struct Synchronizer {
     /// processing will resume when we get the missing batches in the store or we no longer need them.
     /// It also keeps the round number and a timestamp (`u128`) of each request we sent.
     pending: HashMap<Digest, (Round, Sender<()>, u128)>,
+    /// Number of batches in `pending`, shared with the rest of the node to report its sync status.
+    pending_batches: Arc<AtomicUsize>,
 }
 
 impl Synchronizer {
+    /// Spawns the synchronizer. Returns a counter of the batches it is waiting to receive from
+    /// other nodes.
     #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         name: PublicKey,
@@ -56,7 +62,9 @@ impl Synchronizer {
         sync_retry_delay: u64,
         sync_retry_nodes: usize,
         rx_message: Receiver<ConsensusMempoolMessage>,
-    ) {
+    ) -> Arc<AtomicUsize> {
+        let pending_batches = Arc::new(AtomicUsize::new(0));
+        let pending_batches_copy = pending_batches.clone();
         tokio::spawn(async move {
             Self {
                 name,
@@ -69,10 +77,12 @@ impl Synchronizer {
                 network: SimpleSender::new(),
                 round: Round::default(),
                 pending: HashMap::new(),
+                pending_batches: pending_batches_copy,
             }
             .run()
             .await;
         });
+        pending_batches
     }
 
     /// Helper function. It waits for a batch to become available in the storage
@@ -126,6 +136,7 @@ impl Synchronizer {
                             waiting.push(fut);
                             self.pending.insert(digest, (self.round, tx_cancel, now));
                         }
+                        self.pending_batches.store(self.pending.len(), Ordering::Relaxed);
 
                         // Send sync request to a single node. If this fails, we will send it
                         // to other nodes when a timer times out.
@@ -156,6 +167,7 @@ impl Synchronizer {
                             }
                         }
                         self.pending.retain(|_, (r, _, _)| r > &mut gc_round);
+                        self.pending_batches.store(self.pending.len(), Ordering::Relaxed);
                     }
                 },
 
@@ -164,6 +176,7 @@ impl Synchronizer {
                     Ok(Some(digest)) => {
                         // We got the batch, remove it from the pending list.
                         self.pending.remove(&digest);
+                        self.pending_batches.store(self.pending.len(), Ordering::Relaxed);
                     },
                     Ok(None) => {
                         // The sync request for this batch has been canceled.
@@ -1,11 +1,12 @@
 use super::*;
-use crate::common::transaction;
+use crate::common::{batch, batch_digest, transaction};
 use tokio::sync::mpsc::channel;
 
 #[tokio::test]
 async fn make_batch() {
     let (tx_transaction, rx_transaction) = channel(1);
     let (tx_message, mut rx_message) = channel(1);
+    let (tx_notification, _rx_notification) = channel(1);
     let dummy_addresses = vec![(PublicKey::default(), "127.0.0.1:0".parse().unwrap())];
 
     // Spawn a `BatchMaker` instance.
@@ -14,6 +15,7 @@ async fn make_batch() {
         /* max_batch_delay */ 1_000_000, // Ensure the timer is not triggered.
         rx_transaction,
         tx_message,
+        tx_notification,
         /* mempool_addresses */ dummy_addresses,
     );
 
@@ -34,6 +36,7 @@ async fn make_batch() {
 async fn batch_timeout() {
     let (tx_transaction, rx_transaction) = channel(1);
     let (tx_message, mut rx_message) = channel(1);
+    let (tx_notification, _rx_notification) = channel(1);
     let dummy_addresses = vec![(PublicKey::default(), "127.0.0.1:0".parse().unwrap())];
 
     // Spawn a `BatchMaker` instance.
@@ -42,6 +45,7 @@ async fn batch_timeout() {
         /* max_batch_delay */ 50, // Ensure the timer is triggered.
         rx_transaction,
         tx_message,
+        tx_notification,
         /* mempool_addresses */ dummy_addresses,
     );
 
@@ -56,3 +60,41 @@ async fn batch_timeout() {
         _ => panic!("Unexpected message"),
     }
 }
+
+#[tokio::test]
+async fn notify_transactions() {
+    let (tx_transaction, rx_transaction) = channel(1);
+    let (tx_message, mut rx_message) = channel(1);
+    let (tx_notification, mut rx_notification) = channel(3);
+    let dummy_addresses = vec![(PublicKey::default(), "127.0.0.1:0".parse().unwrap())];
+
+    // Spawn a `BatchMaker` instance.
+    BatchMaker::spawn(
+        /* max_batch_size */ 200,
+        /* max_batch_delay */ 1_000_000, // Ensure the timer is not triggered.
+        rx_transaction,
+        tx_message,
+        tx_notification,
+        /* mempool_addresses */ dummy_addresses,
+    );
+
+    // Send enough transactions to seal a batch.
+    tx_transaction.send(transaction()).await.unwrap();
+    tx_transaction.send(transaction()).await.unwrap();
+    rx_message.recv().await.unwrap();
+
+    // Ensure every transaction is notified, and then the batch they were sealed in.
+    for _ in 0..2 {
+        match rx_notification.recv().await.unwrap() {
+            TransactionNotification::Received(tx) => assert_eq!(tx, transaction()),
+            _ => panic!("Unexpected notification"),
+        }
+    }
+    match rx_notification.recv().await.unwrap() {
+        TransactionNotification::Sealed(digest, sealed) => {
+            assert_eq!(digest, batch_digest());
+            assert_eq!(sealed, batch());
+        }
+        _ => panic!("Unexpected notification"),
+    }
+}
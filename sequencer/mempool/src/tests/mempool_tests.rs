@@ -20,6 +20,8 @@ async fn handle_clients_transactions() {
     // Spawn a `Mempool` instance.
     let (_tx_consensus_to_mempool, rx_consensus_to_mempool) = channel(1);
     let (tx_mempool_to_consensus, mut rx_mempool_to_consensus) = channel(1);
+    let (_tx_transactions, rx_transactions) = channel(1);
+    let (tx_notification, _rx_notification) = channel(1);
     Mempool::spawn(
         name,
         committee.clone(),
@@ -27,6 +29,8 @@ async fn handle_clients_transactions() {
         store,
         rx_consensus_to_mempool,
         tx_mempool_to_consensus,
+        rx_transactions,
+        tx_notification,
     );
 
     // Spawn enough mempools' listeners to acknowledge our batches.
@@ -45,3 +49,48 @@ async fn handle_clients_transactions() {
     let received = rx_mempool_to_consensus.recv().await.unwrap();
     assert_eq!(batch_digest(), received);
 }
+
+#[tokio::test]
+async fn handle_local_transactions() {
+    let (name, _) = keys().pop().unwrap();
+    let committee = committee_with_base_port(11_500);
+    let parameters = Parameters {
+        batch_size: 200, // Two transactions.
+        ..Parameters::default()
+    };
+
+    // Create a new test store.
+    let path = ".db_test_handle_local_transactions";
+    let _ = fs::remove_dir_all(path);
+    let store = Store::new(path).unwrap();
+
+    // Spawn a `Mempool` instance.
+    let (_tx_consensus_to_mempool, rx_consensus_to_mempool) = channel(1);
+    let (tx_mempool_to_consensus, mut rx_mempool_to_consensus) = channel(1);
+    let (tx_transactions, rx_transactions) = channel(1);
+    let (tx_notification, _rx_notification) = channel(1);
+    Mempool::spawn(
+        name,
+        committee.clone(),
+        parameters,
+        store,
+        rx_consensus_to_mempool,
+        tx_mempool_to_consensus,
+        rx_transactions,
+        tx_notification,
+    );
+
+    // Spawn enough mempools' listeners to acknowledge our batches.
+    for (_, address) in committee.broadcast_addresses(&name) {
+        #[allow(clippy::let_underscore_future)]
+        let _ = listener(address, /* expected */ None);
+    }
+
+    // Submit enough transactions through the local channel to create a batch.
+    tx_transactions.send(transaction()).await.unwrap();
+    tx_transactions.send(transaction()).await.unwrap();
+
+    // Ensure the consensus got the batch digest.
+    let received = rx_mempool_to_consensus.recv().await.unwrap();
+    assert_eq!(batch_digest(), received);
+}
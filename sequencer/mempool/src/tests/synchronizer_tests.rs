@@ -17,7 +17,7 @@ async fn synchronize() {
     let store = Store::new(path).unwrap();
 
     // Spawn a `Synchronizer` instance.
-    Synchronizer::spawn(
+    let pending_batches = Synchronizer::spawn(
         name,
         committee.clone(),
         store.clone(),
@@ -41,4 +41,7 @@ async fn synchronize() {
 
     // Ensure the target receives the sync request.
     assert!(handle.await.is_ok());
+
+    // The batch is still missing, so the mempool reports it as pending.
+    assert_eq!(pending_batches.load(Ordering::Relaxed), 1);
 }
@@ -5,11 +5,17 @@ use bytes::BytesMut;
 use cairo_felt::Felt252;
 use clap::Parser;
 use env_logger::Env;
+use execution_engine::account::{multicall_calldata, sign, Call};
+use execution_engine::cairo_native_engine::selector_from_name;
+use execution_engine::genesis::{
+    DEFAULT_ACCOUNT_PRIVATE_KEY, ERC20_ADDRESS, FACTORIAL_ADDRESS, FIBONACCI_ADDRESS,
+};
 use futures::future::join_all;
 use futures::sink::SinkExt as _;
 use log::{info, warn};
 use rand::seq::SliceRandom;
 use rand::Rng;
+use rpc_endpoint::rpc::ChainId;
 use rpc_endpoint::rpc::InvokeTransaction;
 use rpc_endpoint::rpc::Transaction;
 use std::net::SocketAddr;
@@ -43,6 +49,9 @@ struct Cli {
     /// Running time of the client in seconds.
     #[clap(short, long, value_parser, value_name = "INT")]
     running_time: Option<u8>,
+    /// The id of the chain the transactions are meant for, as a short string.
+    #[clap(long, value_parser, value_name = "STRING")]
+    chain_id: Option<ChainId>,
 }
 
 #[tokio::main]
@@ -62,6 +71,7 @@ async fn main() -> Result<()> {
         rate: cli.rate,
         timeout: cli.timeout,
         nodes: cli.nodes,
+        chain_id: cli.chain_id.unwrap_or_default().to_felt(),
     };
 
     // Wait for all nodes to be online and synchronized.
@@ -80,6 +90,7 @@ struct Client {
     rate: u64,
     timeout: u64,
     nodes: Vec<SocketAddr>,
+    chain_id: Felt252,
 }
 
 impl Client {
@@ -119,10 +130,20 @@ impl Client {
             internal_counter = 0;
 
             for x in 0..burst {
-                let invoke_transaction = Transaction::new_invoke(
+                let mut invoke_transaction = Transaction::new_invoke(
                     counter + internal_counter,
-                    Self::create_execution_call_data(),
+                    multicall_calldata(&[Self::create_execution_call()]),
+                    &self.chain_id,
                 );
+                // Transactions are sent by the account deployed at genesis
+                if let Transaction::Invoke(InvokeTransaction::V1(transaction)) =
+                    &mut invoke_transaction
+                {
+                    transaction.signature = sign(
+                        &Felt252::new(DEFAULT_ACCOUNT_PRIVATE_KEY),
+                        &transaction.transaction_hash,
+                    );
+                }
 
                 if let Transaction::Invoke(InvokeTransaction::V1(transaction)) = &invoke_transaction
                 {
@@ -191,7 +212,7 @@ impl Client {
         sleep(Duration::from_millis(2 * self.timeout)).await;
     }
 
-    pub fn create_execution_call_data() -> Vec<Felt252> {
+    pub fn create_execution_call() -> Call {
         pub enum ExecutionType {
             Fibonacci,
             Factorial,
@@ -207,23 +228,29 @@ impl Client {
         let n: u16 = rand::thread_rng().gen();
         //let rand_program_input: u16 = rand::thread_rng();
         match options.choose(&mut rand::thread_rng()).unwrap() {
-            ExecutionType::Fibonacci => {
-                vec![Felt252::new(0), Felt252::new((n % 10000) + 1)]
-            }
-            ExecutionType::Factorial => {
-                vec![Felt252::new(1), Felt252::new((n % 2000) + 1)]
-            }
+            ExecutionType::Fibonacci => Call {
+                to: Felt252::new(FIBONACCI_ADDRESS),
+                selector: selector_from_name("fib"),
+                calldata: vec![
+                    Felt252::new(0),
+                    Felt252::new(1),
+                    Felt252::new((n % 10000) + 1),
+                ],
+            },
+            ExecutionType::Factorial => Call {
+                to: Felt252::new(FACTORIAL_ADDRESS),
+                selector: selector_from_name("fact"),
+                calldata: vec![Felt252::new((n % 2000) + 1)],
+            },
             ExecutionType::ERC20 => {
-                let initial_supply = Felt252::new((n % 5000) + 1);
-                let token_symbol = Felt252::new(512);
-                let contract_address = Felt252::new(rand::thread_rng().gen::<u128>());
-                // execution type felt, initial_supply, token symbol, contract address
-                vec![
-                    Felt252::new(2),
-                    initial_supply,
-                    token_symbol,
-                    contract_address,
-                ]
+                let amount = Felt252::new((n % 5000) + 1);
+                let spender = Felt252::new(rand::thread_rng().gen::<u128>());
+                Call {
+                    to: Felt252::new(ERC20_ADDRESS),
+                    selector: selector_from_name("approve"),
+                    // spender, amount as a u256
+                    calldata: vec![spender, amount, Felt252::new(0)],
+                }
             }
         }
     }
@@ -233,8 +260,9 @@ impl Client {
 mod test {
     use bytes::BufMut;
     use bytes::BytesMut;
+    use execution_engine::account::multicall_calldata;
     use rand::Rng;
-    use rpc_endpoint::rpc::Transaction;
+    use rpc_endpoint::rpc::{ChainId, Transaction};
 
     use crate::Client;
 
@@ -253,8 +281,11 @@ mod test {
             } else {
                 tx.put_u8(1u8); // Standard txs start with 1.
             };
-            let starknet_tx =
-                Transaction::new_invoke(762716321, Client::create_execution_call_data());
+            let starknet_tx = Transaction::new_invoke(
+                762716321,
+                multicall_calldata(&[Client::create_execution_call()]),
+                &ChainId::default().to_felt(),
+            );
             for b in starknet_tx.as_bytes() {
                 tx.put_u8(b);
             }
@@ -1,8 +1,14 @@
+use cairo_felt::Felt252;
 use consensus::{Committee as ConsensusCommittee, Parameters as ConsensusParameters};
 use crypto::{generate_keypair, generate_production_keypair, PublicKey, SecretKey};
+use execution_engine::genesis::{
+    AccountGenesis, FeeTokenGenesis, DEFAULT_ACCOUNT_ADDRESS, DEFAULT_FEE_TOKEN_ADDRESS,
+    DEFAULT_FEE_TOKEN_SUPPLY,
+};
 use mempool::{Committee as MempoolCommittee, Parameters as MempoolParameters};
 use rand::rngs::StdRng;
 use rand::SeedableRng as _;
+use rpc_endpoint::rpc::ChainId;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, OpenOptions};
@@ -17,6 +23,9 @@ pub enum ConfigError {
 
     #[error("Failed to write config file '{file}': {message}")]
     WriteError { file: String, message: String },
+
+    #[error("Invalid parameter '{name}': {message}")]
+    InvalidParameter { name: String, message: String },
 }
 
 pub trait Export: Serialize + DeserializeOwned {
@@ -61,6 +70,99 @@ pub struct Parameters {
     pub consensus: ConsensusParameters,
     pub mempool: MempoolParameters,
     pub execution: ExecutionParameters,
+    /// The id of the chain this node sequences, as a short string
+    #[serde(default)]
+    pub chain_id: ChainId,
+    /// How many compiled classes Cairo Native keeps in memory, `DEFAULT_PROGRAM_CACHE_SIZE` if
+    /// not set. Zero disables the cache
+    #[serde(default)]
+    pub program_cache_size: Option<usize>,
+    /// Price of a unit of gas, in wei, `DEFAULT_GAS_PRICE` if not set. Zero is rejected, as
+    /// transactions would be free
+    #[serde(default)]
+    pub gas_price: Option<u64>,
+    /// The token fees are paid with, deployed at genesis when executing with Cairo Native
+    #[serde(default)]
+    pub fee_token: FeeTokenParameters,
+    /// The account deployed at genesis when executing with Cairo Native
+    #[serde(default)]
+    pub account: AccountParameters,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeTokenParameters {
+    /// Address the token is deployed at
+    pub address: u64,
+    /// Account the whole supply is minted to, the account deployed at genesis if not set
+    pub recipient: Option<u64>,
+    /// Supply minted, in wei
+    pub initial_supply: u128,
+}
+
+impl Default for FeeTokenParameters {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_FEE_TOKEN_ADDRESS,
+            recipient: None,
+            initial_supply: DEFAULT_FEE_TOKEN_SUPPLY,
+        }
+    }
+}
+
+impl FeeTokenParameters {
+    pub fn to_genesis(&self, account: &AccountGenesis) -> FeeTokenGenesis {
+        FeeTokenGenesis {
+            address: Felt252::new(self.address),
+            recipient: self
+                .recipient
+                .map_or_else(|| account.address.clone(), Felt252::new),
+            initial_supply: self.initial_supply,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountParameters {
+    /// Address the account is deployed at
+    pub address: u64,
+    /// Public key the account checks signatures against, in hex. Required unless `dev` is set
+    pub public_key: Option<String>,
+    /// Whether the account may be deployed with the public key of `DEFAULT_ACCOUNT_PRIVATE_KEY`
+    /// when no other one is set. Anyone can sign with it, so only for local chains
+    pub dev: bool,
+}
+
+impl Default for AccountParameters {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ACCOUNT_ADDRESS,
+            public_key: None,
+            dev: false,
+        }
+    }
+}
+
+impl AccountParameters {
+    pub fn to_genesis(&self) -> Result<AccountGenesis, ConfigError> {
+        let invalid = |message: &str| ConfigError::InvalidParameter {
+            name: "account.public_key".to_string(),
+            message: message.to_string(),
+        };
+        let public_key = match &self.public_key {
+            Some(public_key) => {
+                Felt252::parse_bytes(public_key.trim_start_matches("0x").as_bytes(), 16)
+                    .ok_or_else(|| invalid("not a hex number"))?
+            }
+            None if self.dev => AccountGenesis::dev().public_key,
+            None => return Err(invalid("required unless 'account.dev' is set")),
+        };
+        Ok(AccountGenesis {
+            address: Felt252::new(self.address),
+            public_key,
+        })
+    }
 }
 
 impl Export for Parameters {}
@@ -1,29 +1,41 @@
 use crate::config::{Committee, ConfigError, Parameters, Secret};
 use crate::config::{ExecutionParameters, Export as _};
 use cairo_felt::Felt252;
-use cairo_lang_compiler::CompilerConfig;
-use cairo_lang_sierra::program::Program as SierraProgram;
-use cairo_lang_sierra::ProgramParser;
 use consensus::{Block, Consensus};
-use crypto::SignatureService;
+use crypto::{Hash as _, SignatureService};
+use execution_engine::account::deploy_account_address;
+use execution_engine::cairo_native_engine::program_cache::DEFAULT_PROGRAM_CACHE_SIZE;
 use execution_engine::cairo_native_engine::CairoNativeEngine;
 use execution_engine::cairovm_engine::CairoVMEngine;
+use execution_engine::contract_class::compiled_class_hash;
+use execution_engine::engine::{BlockContext, ExecutionEngine};
+use execution_engine::fee::FeeToken;
+use execution_engine::genesis::genesis_state;
+use execution_engine::state::{CachedState, StateReader, StoreState};
+use execution_engine::transaction::{TransactionExecution, DEFAULT_GAS_PRICE};
 use log::{error, info};
-use mempool::{Mempool, MempoolMessage};
-use num_bigint::BigUint;
+use mempool::{Mempool, MempoolMessage, TransactionNotification};
 use rpc_endpoint::new_server;
+use rpc_endpoint::pending::{PendingBlock, SharedPendingBlock};
 use rpc_endpoint::rpc::{
-    self, InvokeTransaction, InvokeTransactionReceipt, MaybePendingTransactionReceipt, Transaction,
-    TransactionReceipt,
+    self, ContractClass, DeclareTransaction, DeclareTransactionReceipt,
+    DeployAccountTransactionReceipt, InvokeTransaction, InvokeTransactionReceipt,
+    MaybePendingTransactionReceipt, PendingDeclareTransactionReceipt,
+    PendingDeployAccountTransactionReceipt, PendingInvokeTransactionReceipt,
+    PendingTransactionReceipt, Transaction, TransactionLifecycle, TransactionReceipt,
+    TransactionStage,
 };
+use rpc_endpoint::subscriptions::NewBlock;
+use rpc_endpoint::sync_progress::SyncProgress;
+use sequencer::store::state::StateDiff;
 use std::collections::hash_map::DefaultHasher;
-use std::convert::TryInto;
-use std::fs;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::path::Path;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use store::Store;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc::{channel, Receiver};
 
 /// The default channel capacity for this module.
@@ -32,52 +44,31 @@ pub const CHANNEL_CAPACITY: usize = 1_000;
 /// Default port offset for RPC endpoint
 const RPC_PORT_OFFSET: u16 = 1000;
 const ROUND_TIMEOUT_FOR_EMPTY_BLOCKS: u64 = 1500;
-
-enum ExecutionEngine {
-    Cairo(Box<CairoVMEngine>),
-    Sierra(CairoNativeEngine),
-}
-
-impl ExecutionEngine {
-    fn execute_fibonacci(&self, n: usize) {
-        let ret_msg = match self {
-            ExecutionEngine::Cairo(execution_program) => execution_program.execute_fibonacci(n),
-            ExecutionEngine::Sierra(execution_program) => {
-                execution_program.execute_fibonacci(get_input_value_cairo_native(n))
-            }
-        };
-        info!("{}", ret_msg)
-    }
-
-    fn execute_factorial(&self, n: usize) {
-        let ret_msg = match self {
-            ExecutionEngine::Cairo(execution_program) => execution_program.execute_factorial(n),
-            ExecutionEngine::Sierra(execution_program) => {
-                execution_program.execute_factorial(get_input_value_cairo_native(n))
-            }
-        };
-        info!("{}", ret_msg)
-    }
-
-    fn execute_erc20(&self, n: Felt252, symbol: Felt252, contract_address: Felt252) {
-        let ret_msg = match self {
-            ExecutionEngine::Cairo(_execution_program) => {
-                todo!("Cairo VM does not support ERC20 transactions")
-            }
-            ExecutionEngine::Sierra(execution_program) => {
-                execution_program.execute_erc20(n, symbol, contract_address)
-            }
-        };
-        info!("{}", ret_msg)
-    }
-}
+const SEQUENCER_ADDRESS: u64 = 12039102;
 
 pub struct Node {
     pub commit: Receiver<Block>,
+    /// Blocks stored by the consensus, before they get committed
+    sequenced: Receiver<Block>,
     pub store: Store,
     pub external_store: sequencer::store::Store,
-    execution_program: ExecutionEngine,
+    execution_program: Arc<dyn ExecutionEngine>,
     last_committed_round: u64,
+    sync_progress: SyncProgress,
+    /// Transactions hashed for other chains are rejected
+    chain_id: Felt252,
+    /// Stored blocks are announced to the RPC subscribers
+    new_blocks: broadcast::Sender<Arc<NewBlock>>,
+    /// Round of the last block committed by the consensus
+    committed_round: u64,
+    /// Sequenced blocks from rounds after the last committed one, in round order
+    sequenced_blocks: Vec<Block>,
+    /// Block built out of the sequenced blocks, served by the RPC endpoint
+    pending_block: SharedPendingBlock,
+    /// Price of a unit of gas in the blocks this node builds, in wei
+    gas_price: u64,
+    /// Token the fees are charged in, if there is one
+    fee_token: Option<FeeToken>,
 }
 
 impl Node {
@@ -87,9 +78,14 @@ impl Node {
         store_path: &str,
         parameters: Option<String>,
     ) -> Result<Self, ConfigError> {
+        let (tx_consensus_commit, mut rx_consensus_commit) = channel(CHANNEL_CAPACITY);
         let (tx_commit, rx_commit) = channel(CHANNEL_CAPACITY);
+        let (tx_sequenced, rx_sequenced) = channel(CHANNEL_CAPACITY);
         let (tx_consensus_to_mempool, rx_consensus_to_mempool) = channel(CHANNEL_CAPACITY);
         let (tx_mempool_to_consensus, rx_mempool_to_consensus) = channel(CHANNEL_CAPACITY);
+        let (tx_rpc_to_mempool, rx_rpc_to_mempool) = channel(CHANNEL_CAPACITY);
+        let (tx_mempool_notification, mut rx_mempool_notification) = channel(CHANNEL_CAPACITY);
+        let (tx_new_blocks, _) = broadcast::channel(CHANNEL_CAPACITY);
 
         // Read the committee and secret key from file.
         let committee = Committee::read(committee_file)?;
@@ -105,12 +101,21 @@ impl Node {
 
         // Make the data store.
         let store = Store::new(store_path).expect("Failed to create store");
-        let external_store =
+        let mut external_store =
             sequencer::store::Store::new(store_path, sequencer::store::EngineType::Sled)
                 .expect("Failed to create sequencer store");
 
+        let chain_id = parameters.chain_id.to_felt();
+        let gas_price = parameters.gas_price.unwrap_or(DEFAULT_GAS_PRICE);
+        if gas_price == 0 {
+            return Err(ConfigError::InvalidParameter {
+                name: "gas_price".to_string(),
+                message: "transactions would be free".to_string(),
+            });
+        }
+
         // Init the execution engine according to the parameters sent
-        let execution_engine = match parameters.execution {
+        let execution_engine: Arc<dyn ExecutionEngine> = match parameters.execution {
             ExecutionParameters::CairoVM => {
                 // Load the casm programs as bytes
                 let fib_casm_program: Vec<u8> =
@@ -118,42 +123,40 @@ impl Node {
                 let fact_casm_program: Vec<u8> =
                     include_bytes!("../../cairo_programs/fact_contract.casm").to_vec();
 
-                let cairovm_engine = CairoVMEngine::new(fib_casm_program, fact_casm_program);
-
-                // Read casm program bytes as CasmContractClass
-                ExecutionEngine::Cairo(Box::new(cairovm_engine))
+                Arc::new(CairoVMEngine::new(fib_casm_program, fact_casm_program))
             }
             ExecutionParameters::CairoNative => {
-                // Compile Cairo programs to Sierra
-                let fact_sierra_program: Arc<SierraProgram> =
-                    cairo_lang_compiler::compile_cairo_project_at_path(
-                        Path::new("../cairo_programs/fact_contract.cairo"),
-                        CompilerConfig {
-                            replace_ids: true,
-                            ..Default::default()
-                        },
-                    )
-                    .unwrap();
-
-                let fib_sierra_program: Arc<SierraProgram> =
-                    cairo_lang_compiler::compile_cairo_project_at_path(
-                        Path::new("../cairo_programs/fib_contract.cairo"),
-                        CompilerConfig {
-                            replace_ids: true,
-                            ..Default::default()
-                        },
-                    )
-                    .unwrap();
+                let engine = CairoNativeEngine::new(
+                    chain_id.clone(),
+                    parameters
+                        .program_cache_size
+                        .unwrap_or(DEFAULT_PROGRAM_CACHE_SIZE),
+                );
 
-                let program_src = fs::read_to_string("../cairo_programs/erc20.sierra").unwrap();
-                let program_parser = ProgramParser::new();
-                let erc20_sierra_program = Arc::new(program_parser.parse(&program_src).unwrap());
+                // Invoke transactions are sent by accounts, call contracts and pay fees, so the
+                // bundled contracts, an account and the fee token are deployed before the first
+                // block is stored.
+                if external_store.get_height().is_none() {
+                    let account = parameters.account.to_genesis()?;
+                    let genesis = genesis_state(
+                        &engine,
+                        &parameters.fee_token.to_genesis(&account),
+                        &account,
+                    )
+                    .expect("Failed to build the genesis state");
+                    external_store
+                        .apply_state_diff(0, genesis)
+                        .expect("Failed to store the genesis state");
+                }
 
-                ExecutionEngine::Sierra(CairoNativeEngine::new(
-                    fib_sierra_program,
-                    fact_sierra_program,
-                    erc20_sierra_program,
-                ))
+                Arc::new(engine)
+            }
+        };
+        // CairoVM runs no contracts to keep balances in, so transactions are free with it
+        let fee_token = match parameters.execution {
+            ExecutionParameters::CairoVM => None,
+            ExecutionParameters::CairoNative => {
+                Some(FeeToken::new(Felt252::new(parameters.fee_token.address)))
             }
         };
 
@@ -161,17 +164,27 @@ impl Node {
         let signature_service = SignatureService::new(secret_key);
 
         // Make a new mempool.
-        Mempool::spawn(
+        let pending_batches = Mempool::spawn(
             name,
             committee.clone().mempool,
             parameters.mempool,
             store.clone(),
             rx_consensus_to_mempool,
             tx_mempool_to_consensus,
+            rx_rpc_to_mempool,
+            tx_mempool_notification,
         );
 
+        // Transactions are tracked from the moment the mempool receives them.
+        let mut status_store = external_store.clone();
+        tokio::spawn(async move {
+            while let Some(notification) = rx_mempool_notification.recv().await {
+                record_mempool_notification(&mut status_store, notification);
+            }
+        });
+
         // Run the consensus core.
-        Consensus::spawn(
+        let pending_blocks = Consensus::spawn(
             name,
             committee.clone().consensus,
             parameters.consensus,
@@ -179,10 +192,31 @@ impl Node {
             store.clone(),
             rx_mempool_to_consensus,
             tx_consensus_to_mempool,
-            tx_commit,
+            tx_consensus_commit,
+            tx_sequenced,
         );
 
+        // Committed blocks are relayed to the node, keeping track of the last committed round to
+        // report how far behind the consensus the node is.
+        let sync_progress = SyncProgress::new(pending_blocks, pending_batches);
+        let committed_round = sync_progress.committed_round.clone();
+        tokio::spawn(async move {
+            while let Some(block) = rx_consensus_commit.recv().await {
+                committed_round.fetch_max(block.round, Ordering::Relaxed);
+                if tx_commit.send(block).await.is_err() {
+                    break;
+                }
+            }
+        });
+
         let external_store_clone = external_store.clone();
+        let rpc_execution_engine = execution_engine.clone();
+        let rpc_sync_progress = sync_progress.clone();
+        let rpc_chain_id = chain_id.clone();
+        let rpc_new_blocks = tx_new_blocks.clone();
+        let pending_block = SharedPendingBlock::default();
+        let rpc_pending_block = pending_block.clone();
+        let rpc_fee_token = fee_token.clone();
         tokio::spawn(async move {
             let port = committee
                 .mempool
@@ -191,7 +225,19 @@ impl Node {
                 .port()
                 + RPC_PORT_OFFSET;
 
-            let handle = new_server(port, external_store_clone).await;
+            let handle = new_server(
+                port,
+                external_store_clone,
+                tx_rpc_to_mempool,
+                rpc_execution_engine,
+                rpc_sync_progress,
+                rpc_chain_id,
+                rpc_new_blocks,
+                rpc_pending_block,
+                gas_price,
+                rpc_fee_token,
+            )
+            .await;
 
             match handle {
                 Ok(handle) => {
@@ -205,10 +251,19 @@ impl Node {
         info!("Node {} successfully booted", name);
         Ok(Self {
             commit: rx_commit,
+            sequenced: rx_sequenced,
             store,
             external_store,
             execution_program: execution_engine,
             last_committed_round: 0u64,
+            sync_progress,
+            chain_id,
+            new_blocks: tx_new_blocks,
+            committed_round: 0,
+            sequenced_blocks: vec![],
+            pending_block,
+            gas_price,
+            fee_token,
         })
     }
 
@@ -217,140 +272,492 @@ impl Node {
     }
 
     pub async fn analyze_block(&mut self) {
-        while let Some(block) = self.commit.recv().await {
-            let mut transactions = vec![];
-
-            // This is where we can further process committed block.
-            for p in block.payload {
-                let tx_batch = self.store.read(p.to_vec()).await.unwrap().unwrap();
-                info!("Batch is {} bytes long", tx_batch.len());
-
-                let list_of_tx: MempoolMessage =
-                    bincode::deserialize(&tx_batch).expect("Error trying to deserialize batch");
-
-                match list_of_tx {
-                    MempoolMessage::Batch(batch_txs) => {
-                        info!(
-                            "Batch message confirmed, with {} transactions!",
-                            batch_txs.len()
+        self.rebuild_pending_block().await;
+        loop {
+            tokio::select! {
+                Some(block) = self.commit.recv() => {
+                    self.committed_round = block.round;
+                    self.sequenced_blocks
+                        .retain(|sequenced| sequenced.round > block.round);
+                    let round = block.round;
+                    self.commit_block(block).await;
+                    self.sync_progress
+                        .stored_round
+                        .store(round, Ordering::Relaxed);
+                    // The pending transactions now run on top of the newly stored block
+                    self.rebuild_pending_block().await;
+                }
+                Some(block) = self.sequenced.recv() => {
+                    if block.round <= self.committed_round
+                        || self
+                            .sequenced_blocks
+                            .iter()
+                            .any(|sequenced| sequenced.digest() == block.digest())
+                    {
+                        continue;
+                    }
+                    self.sequenced_blocks.push(block.clone());
+                    self.sequenced_blocks.sort_by_key(|sequenced| sequenced.round);
+                    // Blocks sequenced after the ones in the pending block are executed on top
+                    // of it, while earlier ones change the order the transactions run in
+                    let is_last = self
+                        .sequenced_blocks
+                        .last()
+                        .map_or(false, |last| last.digest() == block.digest());
+                    let pending_block = self.pending_block.read().unwrap().clone();
+                    match pending_block {
+                        Some(pending_block) if is_last => {
+                            self.extend_pending_block(pending_block, &[block]).await
+                        }
+                        _ => self.rebuild_pending_block().await,
+                    }
+                }
+                else => break,
+            }
+        }
+    }
+
+    /// Executes the transactions of a committed block and stores them in a new block.
+    async fn commit_block(&mut self, block: Block) {
+        let mut transactions = vec![];
+        let mut transaction_executions = HashMap::new();
+        let block_context = self.next_block_context();
+        // Transactions are executed on top of the state of the last stored block.
+        let mut state = CachedState::new(Box::new(StoreState::new(
+            self.external_store.clone(),
+            self.external_store.get_height().unwrap_or(0),
+        )));
+
+        let mut revert_reasons = vec![];
+
+        // This is where we can further process committed block.
+        let (read_transactions, rejections) = self.read_transactions(&block).await;
+        for (transaction_hash, reason) in rejections {
+            self.update_transaction_status(
+                &transaction_hash,
+                TransactionStage::Rejected,
+                |status| status.reason = Some(reason),
+            );
+        }
+        for (tx, contract_class) in read_transactions {
+            let transaction_hash = tx.transaction_hash().clone();
+            self.update_transaction_status(
+                &transaction_hash,
+                TransactionStage::Committed,
+                |status| status.round = Some(block.round),
+            );
+
+            let execution =
+                match self.execute_transaction(&tx, contract_class, &block_context, &mut state) {
+                    Ok(execution) => execution,
+                    Err(reason) => {
+                        error!(
+                            "Transaction {} rejected: {reason}",
+                            transaction_hash.to_str_radix(16)
+                        );
+                        self.update_transaction_status(
+                            &transaction_hash,
+                            TransactionStage::Rejected,
+                            |status| status.reason = Some(reason),
                         );
+                        continue;
+                    }
+                };
+            revert_reasons.push((transaction_hash.clone(), execution.revert_reason.clone()));
+            transaction_executions.insert(transaction_hash, execution);
 
-                        for (i, tx_bytes) in batch_txs.into_iter().enumerate() {
-                            // Consensus codebase uses the first 9 bytes to track the transaction like this:
-                            //
-                            // - First byte can be 0 or 1 and represents whether it's a benchmarked tx or standard tx
-                            // - Next 8 bytes represent a transaction ID
-                            //
-                            // If it's a benchmarked tx, it then gets tracked in logs to compute metrics
-                            // So we need to strip that section in order to get the starknet transaction to execute
-                            #[cfg(feature = "benchmark")]
-                            let tx_bytes = &tx_bytes[9..];
-
-                            #[allow(clippy::needless_borrow)]
-                            let starknet_tx = rpc::Transaction::from_bytes(&tx_bytes);
-
-                            info!(
-                                "Message {i} in {:?} is of tx_type {:?}, executing",
-                                p, starknet_tx
-                            );
+            if let Err(e) = self.external_store.add_transaction(tx.clone()) {
+                error!("Failed to store a transaction: {e}");
+            }
+            transactions.push(tx);
+        }
+
+        if !transactions.is_empty()
+            || (block.round - self.last_committed_round) > ROUND_TIMEOUT_FOR_EMPTY_BLOCKS
+        {
+            info!("About to store block from round {}", block.round);
+            self.last_committed_round = block.round;
+            self.create_and_store_new_block(
+                &block_context,
+                transactions,
+                transaction_executions,
+                state.into_state_diff(),
+            );
+
+            let block_number = self.external_store.get_height().unwrap_or(0);
+            for (transaction_hash, revert_reason) in revert_reasons {
+                let stage = match revert_reason {
+                    Some(_) => TransactionStage::Reverted,
+                    None => TransactionStage::Executed,
+                };
+                self.update_transaction_status(&transaction_hash, stage, |status| {
+                    status.block_number = Some(block_number);
+                    status.reason = revert_reason;
+                });
+            }
+        }
+    }
+
+    /// Records the stage a transaction reached. Failing to do so doesn't stop the node.
+    fn update_transaction_status(
+        &mut self,
+        transaction_hash: &Felt252,
+        stage: TransactionStage,
+        update: impl FnOnce(&mut TransactionLifecycle),
+    ) {
+        if let Err(e) =
+            self.external_store
+                .update_transaction_status(transaction_hash, stage, update)
+        {
+            error!("Failed to update the status of a transaction: {e}");
+        }
+    }
 
-                            match &starknet_tx {
-                                Transaction::Invoke(InvokeTransaction::V1(tx)) => {
-                                    info!(
-                                        "tx hash serialized: {}, decimal {} (hex {})",
-                                        serde_json::to_string(&tx.transaction_hash).unwrap(),
-                                        &tx.transaction_hash,
-                                        &tx.transaction_hash.to_str_radix(16)
-                                    );
-
-                                    // first call data == Felt252::new(0) means we want to execute fibonacci
-                                    // first call data == Felt252::new(1) means we want to execute factorial
-                                    // first call data == Felt252::new(2) means we want to execute ERC20
-                                    let first_felt: u64 = tx
-                                        .calldata
-                                        .first()
-                                        .expect("Calldata in transaction was not correctly set")
-                                        .to_le_digits()[0];
-
-                                    match first_felt {
-                                        0 => {
-                                            let program_input = tx
-                                                .calldata
-                                                .get(1)
-                                                .expect("calldata was not correctly set");
-                                            let n: usize =
-                                                program_input.to_le_digits()[0].try_into().unwrap();
-                                            self.execution_program.execute_fibonacci(n);
-                                        }
-                                        1 => {
-                                            let program_input = tx
-                                                .calldata
-                                                .get(1)
-                                                .expect("calldata was not correctly set");
-                                            let n: usize =
-                                                program_input.to_le_digits()[0].try_into().unwrap();
-                                            self.execution_program.execute_factorial(n);
-                                        }
-                                        2 => {
-                                            self.execution_program.execute_erc20(
-                                                tx.calldata[1].clone(),
-                                                tx.calldata[2].clone(),
-                                                tx.calldata[3].clone(),
-                                            );
-                                        }
-                                        _ => error!("Transaction contains invalid calldata"),
-                                    };
-
-                                    let _ =
-                                        self.external_store.add_transaction(starknet_tx.clone());
-                                }
-                                _ => todo!(),
+    /// Rebuilds the pending block out of the sequenced blocks that are not committed yet,
+    /// executing their transactions on top of the last stored block.
+    async fn rebuild_pending_block(&mut self) {
+        let block_context = self.next_block_context();
+        let pending_block = PendingBlock {
+            block: rpc::PendingBlockWithTxs {
+                transactions: vec![],
+                timestamp: block_context.block_timestamp,
+                sequencer_address: block_context.sequencer_address,
+                parent_hash: self.latest_block_hash(),
+            },
+            receipts: vec![],
+            state_diff: StateDiff::default(),
+            parent_block_number: self.external_store.get_height().unwrap_or(0),
+        };
+        let sequenced_blocks = self.sequenced_blocks.clone();
+        self.extend_pending_block(pending_block, &sequenced_blocks)
+            .await;
+    }
+
+    /// Executes the transactions of the given sequenced blocks on top of the pending block, and
+    /// publishes the result to the RPC endpoint.
+    async fn extend_pending_block(&mut self, mut pending_block: PendingBlock, blocks: &[Block]) {
+        let block_context = BlockContext {
+            block_number: pending_block.parent_block_number + 1,
+            block_timestamp: pending_block.block.timestamp,
+            sequencer_address: pending_block.block.sequencer_address.clone(),
+            gas_price: self.gas_price,
+        };
+        let mut transactions = vec![];
+        let mut receipts = vec![];
+        let mut state = CachedState::with_state_diff(
+            Box::new(StoreState::new(
+                self.external_store.clone(),
+                pending_block.parent_block_number,
+            )),
+            pending_block.state_diff,
+        );
+
+        for block in blocks {
+            // Rejections are recorded once the transaction gets committed
+            let (read_transactions, _) = self.read_transactions(block).await;
+            for (tx, contract_class) in read_transactions {
+                let execution =
+                    match self.execute_transaction(&tx, contract_class, &block_context, &mut state)
+                    {
+                        Ok(execution) => execution,
+                        Err(_) => continue,
+                    };
+                let transaction_hash = tx.transaction_hash().clone();
+                let actual_fee = Felt252::new(execution.fee());
+                receipts.push(match &tx {
+                    Transaction::Declare(_) => {
+                        PendingTransactionReceipt::Declare(PendingDeclareTransactionReceipt {
+                            transaction_hash,
+                            actual_fee,
+                            execution_status: execution.execution_status(),
+                            revert_reason: execution.revert_reason,
+                            messages_sent: execution.messages,
+                            events: execution.events,
+                        })
+                    }
+                    Transaction::DeployAccount(_) => PendingTransactionReceipt::DeployAccount(
+                        PendingDeployAccountTransactionReceipt {
+                            transaction_hash,
+                            actual_fee,
+                            execution_status: execution.execution_status(),
+                            revert_reason: execution.revert_reason,
+                            messages_sent: execution.messages,
+                            events: execution.events,
+                        },
+                    ),
+                    _ => PendingTransactionReceipt::Invoke(PendingInvokeTransactionReceipt {
+                        transaction_hash,
+                        actual_fee,
+                        execution_status: execution.execution_status(),
+                        revert_reason: execution.revert_reason,
+                        messages_sent: execution.messages,
+                        events: execution.events,
+                    }),
+                });
+                transactions.push(tx);
+            }
+        }
+
+        pending_block.block.transactions.extend(transactions);
+        pending_block.receipts.extend(receipts);
+        pending_block.state_diff = state.into_state_diff();
+        *self.pending_block.write().unwrap() = Some(pending_block);
+    }
+
+    /// Reads the transactions in the batches of a block, along with the classes sent with the
+    /// declare ones. The transactions meant for other chains or of unsupported types are left
+    /// out, and returned apart with the reason why, for the caller to record.
+    async fn read_transactions(
+        &mut self,
+        block: &Block,
+    ) -> (
+        Vec<(Transaction, Option<ContractClass>)>,
+        Vec<(Felt252, String)>,
+    ) {
+        let mut transactions = vec![];
+        let mut rejections = vec![];
+        for p in &block.payload {
+            let tx_batch = self.store.read(p.to_vec()).await.unwrap().unwrap();
+            info!("Batch is {} bytes long", tx_batch.len());
+
+            let list_of_tx: MempoolMessage = match bincode::deserialize(&tx_batch) {
+                Ok(list_of_tx) => list_of_tx,
+                Err(e) => {
+                    error!("Batch {:?} can't be deserialized, skipping it: {e}", p);
+                    continue;
+                }
+            };
+
+            match list_of_tx {
+                MempoolMessage::Batch(batch_txs) => {
+                    info!(
+                        "Batch message confirmed, with {} transactions!",
+                        batch_txs.len()
+                    );
+
+                    for (i, tx_bytes) in batch_txs.into_iter().enumerate() {
+                        // Consensus codebase uses the first 9 bytes to track the transaction like this:
+                        //
+                        // - First byte can be 0 or 1 and represents whether it's a benchmarked tx or standard tx
+                        // - Next 8 bytes represent a transaction ID
+                        //
+                        // If it's a benchmarked tx, it then gets tracked in logs to compute metrics
+                        // So we need to strip that section in order to get the starknet transaction to execute
+                        #[cfg(feature = "benchmark")]
+                        let tx_bytes = &tx_bytes[9..];
+
+                        #[allow(clippy::needless_borrow)]
+                        let Some(starknet_tx) = rpc::Transaction::try_from_bytes(&tx_bytes) else {
+                            error!("Message {i} in {:?} is not a transaction, skipping it", p);
+                            continue;
+                        };
+
+                        info!("Message {i} in {:?} is of tx_type {:?}", p, starknet_tx);
+
+                        let expected_hash = match &starknet_tx {
+                            Transaction::Invoke(InvokeTransaction::V1(tx)) => {
+                                info!(
+                                    "tx hash serialized: {}, decimal {} (hex {})",
+                                    serde_json::to_string(&tx.transaction_hash).unwrap(),
+                                    &tx.transaction_hash,
+                                    &tx.transaction_hash.to_str_radix(16)
+                                );
+                                tx.calculate_hash(&self.chain_id)
+                            }
+                            Transaction::Declare(DeclareTransaction::V1(tx)) => {
+                                tx.calculate_hash(&self.chain_id)
                             }
+                            Transaction::Declare(DeclareTransaction::V2(tx)) => {
+                                tx.calculate_hash(&self.chain_id)
+                            }
+                            Transaction::DeployAccount(tx) => tx.calculate_hash(&self.chain_id),
+                            _ => {
+                                let transaction_hash = starknet_tx.transaction_hash().clone();
+                                error!(
+                                    "Transaction {} is of an unsupported type, skipping it",
+                                    transaction_hash.to_str_radix(16)
+                                );
+                                rejections.push((
+                                    transaction_hash,
+                                    "Transaction type not supported".to_string(),
+                                ));
+                                continue;
+                            }
+                        };
 
-                            transactions.push(starknet_tx);
+                        let transaction_hash = starknet_tx.transaction_hash().clone();
+                        if expected_hash != transaction_hash {
+                            error!(
+                                "Transaction {} is not meant for this chain, skipping it",
+                                transaction_hash.to_str_radix(16)
+                            );
+                            rejections.push((
+                                transaction_hash,
+                                "Transaction is not meant for this chain".to_string(),
+                            ));
+                            continue;
                         }
-                    }
-                    MempoolMessage::BatchRequest(_, _) => {
-                        info!("Batch Request message confirmed")
+
+                        #[allow(clippy::needless_borrow)]
+                        let contract_class = rpc::Transaction::contract_class_from_bytes(&tx_bytes);
+                        transactions.push((starknet_tx, contract_class));
                     }
                 }
+                MempoolMessage::BatchRequest(_, _) => {
+                    info!("Batch Request message confirmed")
+                }
             }
-            if !transactions.is_empty()
-                || (block.round - self.last_committed_round) > ROUND_TIMEOUT_FOR_EMPTY_BLOCKS
-            {
-                info!("About to store block from round {}", block.round);
-                self.last_committed_round = block.round;
-                self.create_and_store_new_block(transactions);
+        }
+        (transactions, rejections)
+    }
+
+    /// Executes a transaction in the given block on top of the given state, charging its fee and
+    /// bumping the sender nonce. Transactions that can't be included at all, including the ones
+    /// whose sender can't pay their maximal fee, are rejected with the reason why.
+    fn execute_transaction(
+        &self,
+        tx: &Transaction,
+        contract_class: Option<ContractClass>,
+        block_context: &BlockContext,
+        state: &mut CachedState,
+    ) -> Result<TransactionExecution, String> {
+        let (execution, sender_address) = match tx {
+            Transaction::Invoke(InvokeTransaction::V1(tx)) => {
+                let execution = match &self.fee_token {
+                    Some(fee_token) => fee_token.execute_invoke(
+                        self.execution_program.as_ref(),
+                        tx,
+                        block_context,
+                        state,
+                    )?,
+                    None => self
+                        .execution_program
+                        .execute_invoke(tx, block_context, state)?,
+                };
+                if let Some(revert_reason) = &execution.revert_reason {
+                    error!(
+                        "Transaction {} reverted: {revert_reason}",
+                        tx.transaction_hash.to_str_radix(16)
+                    );
+                }
+                (execution, tx.sender_address.clone())
             }
+            // Legacy classes aren't hashed the way Starknet does, see `ContractClass::class_hash`
+            Transaction::Declare(DeclareTransaction::V1(_)) => {
+                return Err("Legacy classes can't be declared".into())
+            }
+            Transaction::Declare(declare_tx @ DeclareTransaction::V2(tx)) => {
+                let class = match contract_class {
+                    Some(ContractClass::Sierra(class)) => class,
+                    _ => return Err("Declare transaction v2 must declare a Sierra class".into()),
+                };
+                let compiled_class_hash = compiled_class_hash(&class)
+                    .map_err(|e| format!("Invalid contract class: {e}"))?;
+                if compiled_class_hash != tx.compiled_class_hash {
+                    return Err("Compiled class hash doesn't match the declared class".into());
+                }
+                let execution = self.execute_declare(
+                    &tx.sender_address,
+                    &tx.max_fee,
+                    block_context,
+                    state,
+                    |state| {
+                        let validation = self.execution_program.validate_declare(
+                            declare_tx,
+                            block_context,
+                            state,
+                        )?;
+                        declare_class(state, &tx.class_hash, ContractClass::Sierra(class))?;
+                        Ok(validation)
+                    },
+                )?;
+                (execution, tx.sender_address.clone())
+            }
+            // Accounts pay for their own deployment, from their address
+            Transaction::DeployAccount(tx) => {
+                let execution = match &self.fee_token {
+                    Some(fee_token) => fee_token.execute_deploy_account(
+                        self.execution_program.as_ref(),
+                        tx,
+                        block_context,
+                        state,
+                    )?,
+                    None => {
+                        self.execution_program
+                            .execute_deploy_account(tx, block_context, state)?
+                    }
+                };
+                (execution, deploy_account_address(tx))
+            }
+            _ => return Err("Transaction type not supported".into()),
+        };
+        if let Err(e) = state.increment_nonce(&sender_address) {
+            error!("Failed to update the nonce: {e}");
         }
+        Ok(execution)
     }
 
-    fn create_and_store_new_block(&mut self, transactions: Vec<Transaction>) {
-        let height = self
-            .external_store
-            .get_height()
-            .expect("Height value not found")
-            + 1;
+    /// Validates and declares a class with `declare`, charging the sender the fee of the
+    /// declaration if fees are charged.
+    fn execute_declare(
+        &self,
+        sender_address: &Felt252,
+        max_fee: &Felt252,
+        block_context: &BlockContext,
+        state: &mut CachedState,
+        declare: impl FnOnce(&mut CachedState) -> Result<TransactionExecution, String>,
+    ) -> Result<TransactionExecution, String> {
+        match &self.fee_token {
+            Some(fee_token) => {
+                fee_token.execute_declare(sender_address, max_fee, block_context, state, declare)
+            }
+            None => declare(state),
+        }
+    }
 
-        let status = rpc_endpoint::rpc::BlockStatus::AcceptedOnL2;
+    /// Returns the context of the block to be stored next, as it would be built now.
+    fn next_block_context(&self) -> BlockContext {
+        BlockContext {
+            block_number: self.external_store.get_height().unwrap_or(0) + 1,
+            block_timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Timestamp failed")
+                .as_secs(),
+            sequencer_address: Felt252::new(SEQUENCER_ADDRESS),
+            gas_price: self.gas_price,
+        }
+    }
+
+    /// Returns the hash of the last stored block, or zero if there is none.
+    fn latest_block_hash(&self) -> Felt252 {
+        let height = self.external_store.get_height().unwrap_or(0);
         // TODO: store deserialization should be managed in store logic.
-        let parent_block = self.external_store.get_block_by_height(height - 1);
+        let block = self.external_store.get_block_by_height(height);
 
-        let parent_hash = parent_block.map_or(Felt252::new(0), |maybe_block| {
+        block.map_or(Felt252::new(0), |maybe_block| {
             maybe_block.map_or(Felt252::new(0), |block| match block {
                 rpc::MaybePendingBlockWithTxs::Block(block) => block.block_hash,
                 _ => Felt252::new(0),
             })
-        });
+        })
+    }
+
+    fn create_and_store_new_block(
+        &mut self,
+        block_context: &BlockContext,
+        transactions: Vec<Transaction>,
+        mut transaction_executions: HashMap<Felt252, TransactionExecution>,
+        state_diff: StateDiff,
+    ) {
+        let height = block_context.block_number;
+
+        let status = rpc_endpoint::rpc::BlockStatus::AcceptedOnL2;
+        let parent_hash = self.latest_block_hash();
         let new_root = Felt252::new(938938281);
 
-        let timestamp: u128 = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Timestamp failed")
-            .as_secs()
-            .into();
+        let timestamp: u128 = block_context.block_timestamp.into();
 
-        let sequencer_address = Felt252::new(12039102);
+        let sequencer_address = block_context.sequencer_address.clone();
 
         // TODO: This is quick and dirty hashing,
         //       Block hashing should be done in it's own module
@@ -362,11 +769,11 @@ impl Node {
         sequencer_address.hash(&mut state);
         transactions.iter().for_each(|tx| match &tx {
             Transaction::Invoke(InvokeTransaction::V1(invoke_tx)) => invoke_tx.hash(&mut state),
-            _ => todo!(),
+            _ => tx.transaction_hash().hash(&mut state),
         });
         let block_hash = Felt252::new(state.finish());
 
-        let block_with_txs = rpc::MaybePendingBlockWithTxs::Block(rpc::BlockWithTxs {
+        let block = rpc::BlockWithTxs {
             status,
             block_hash: block_hash.clone(),
             parent_hash,
@@ -375,35 +782,166 @@ impl Node {
             timestamp,
             sequencer_address,
             transactions: transactions.clone(),
-        });
+        };
+
+        // Failing to store parts of the block doesn't stop the node, but leaves the stored chain
+        // incomplete, so it is logged
+        if let Err(e) = self
+            .external_store
+            .add_block(rpc::MaybePendingBlockWithTxs::Block(block.clone()))
+        {
+            error!("Failed to store block {height}: {e}");
+        }
 
-        _ = self.external_store.add_block(block_with_txs);
+        if let Err(e) = self.external_store.apply_state_diff(height, state_diff) {
+            error!("Failed to store the state diff of block {height}: {e}");
+        }
 
-        _ = self.external_store.set_height(height);
+        if let Err(e) = self.external_store.set_height(height) {
+            error!("Failed to store the height {height}: {e}");
+        }
 
-        transactions.iter().for_each(|tx| match tx {
-            Transaction::Invoke(InvokeTransaction::V1(invoke_tx)) => {
-                let tx_receipt: InvokeTransactionReceipt = InvokeTransactionReceipt {
-                    transaction_hash: invoke_tx.transaction_hash.clone(),
-                    actual_fee: invoke_tx.max_fee.clone(),
+        let mut emitted_events = vec![];
+        transactions.iter().for_each(|tx| {
+            let transaction_hash = tx.transaction_hash().clone();
+            let execution = transaction_executions
+                .remove(&transaction_hash)
+                .unwrap_or_default();
+            let execution_status = execution.execution_status();
+            let actual_fee = Felt252::new(execution.fee());
+            let TransactionExecution {
+                events,
+                messages,
+                revert_reason,
+                ..
+            } = execution;
+            emitted_events.extend(events.iter().map(|event| rpc::EmittedEvent {
+                from_address: event.from_address.clone(),
+                keys: event.keys.clone(),
+                data: event.data.clone(),
+                block_hash: block_hash.clone(),
+                block_number: height,
+                transaction_hash: transaction_hash.clone(),
+            }));
+
+            let tx_receipt = match tx {
+                Transaction::Invoke(InvokeTransaction::V1(_)) => {
+                    TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                        transaction_hash,
+                        actual_fee,
+                        status: rpc::TransactionStatus::AcceptedOnL2,
+                        execution_status,
+                        revert_reason,
+                        block_hash: block_hash.clone(),
+                        block_number: height,
+                        messages_sent: messages,
+                        events,
+                    })
+                }
+                Transaction::Declare(_) => TransactionReceipt::Declare(DeclareTransactionReceipt {
+                    transaction_hash,
+                    actual_fee,
                     status: rpc::TransactionStatus::AcceptedOnL2,
+                    execution_status,
+                    revert_reason,
                     block_hash: block_hash.clone(),
                     block_number: height,
-                    messages_sent: vec![],
-                    events: vec![],
-                };
+                    messages_sent: messages,
+                    events,
+                }),
+                Transaction::DeployAccount(deploy_account_tx) => {
+                    TransactionReceipt::DeployAccount(DeployAccountTransactionReceipt {
+                        transaction_hash,
+                        actual_fee,
+                        status: rpc::TransactionStatus::AcceptedOnL2,
+                        execution_status,
+                        revert_reason,
+                        block_hash: block_hash.clone(),
+                        block_number: height,
+                        messages_sent: messages,
+                        events,
+                        contract_address: deploy_account_address(deploy_account_tx),
+                    })
+                }
+                // Transactions of other types are left out by `read_transactions`, and rejected
+                // by `execute_transaction`, so they never get to a block
+                _ => unreachable!("Only supported transactions are stored in blocks"),
+            };
 
-                _ = self.external_store.add_transaction_receipt(
-                    MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(tx_receipt)),
-                );
+            if let Err(e) = self
+                .external_store
+                .add_transaction_receipt(MaybePendingTransactionReceipt::Receipt(tx_receipt))
+            {
+                error!("Failed to store a transaction receipt of block {height}: {e}");
             }
-            _ => todo!(),
         });
+
+        if let Err(e) = self
+            .external_store
+            .add_events(height, emitted_events.clone())
+        {
+            error!("Failed to store the events of block {height}: {e}");
+        }
+
+        // Sending only fails when nobody is subscribed
+        _ = self.new_blocks.send(Arc::new(NewBlock {
+            block,
+            events: emitted_events,
+        }));
     }
 }
 
-fn get_input_value_cairo_native(n: usize) -> Vec<u32> {
-    let mut digits = BigUint::from(n).to_u32_digits();
-    digits.resize(8, 0);
-    digits
+/// Declares the class sent along with a declare transaction, checking it is the one the
+/// transaction commits to and that it wasn't declared before.
+fn declare_class(
+    state: &mut CachedState,
+    class_hash: &Felt252,
+    contract_class: ContractClass,
+) -> Result<(), String> {
+    if &contract_class.class_hash() != class_hash {
+        return Err("Class hash doesn't match the declared class".into());
+    }
+    match state.get_class(class_hash) {
+        Ok(None) => {}
+        Ok(Some(_)) => return Err("Class already declared".into()),
+        Err(e) => return Err(format!("Failed to read the declared classes: {e}")),
+    }
+    state.declare_class(class_hash.clone(), contract_class);
+    Ok(())
+}
+
+/// Records the stage the mempool notified for the transactions it refers to. Transactions that
+/// can't be parsed have no hash to be tracked by, and are left for the node to skip.
+fn record_mempool_notification(
+    store: &mut sequencer::store::Store,
+    notification: TransactionNotification,
+) {
+    let (stage, transactions, batch_digest) = match notification {
+        TransactionNotification::Received(tx) => (TransactionStage::Received, vec![tx], None),
+        TransactionNotification::Sealed(digest, batch) => (
+            TransactionStage::Batched,
+            batch,
+            Some(format!("{:?}", digest)),
+        ),
+    };
+
+    for tx_bytes in transactions {
+        // Benchmark transactions start with a 9 bytes header, see `Node::read_transactions`
+        #[cfg(feature = "benchmark")]
+        let tx_bytes = tx_bytes.get(9..).unwrap_or_default();
+
+        #[allow(clippy::needless_borrow)]
+        let transaction_hash = match rpc::Transaction::try_from_bytes(&tx_bytes) {
+            Some(transaction) => transaction.transaction_hash().clone(),
+            None => continue,
+        };
+        let result = store.update_transaction_status(&transaction_hash, stage, |status| {
+            if batch_digest.is_some() {
+                status.batch_digest = batch_digest.clone();
+            }
+        });
+        if let Err(e) = result {
+            error!("Failed to update the status of a transaction: {e}");
+        }
+    }
 }
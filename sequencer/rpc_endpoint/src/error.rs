@@ -0,0 +1,38 @@
+use jsonrpsee::types::{error::ErrorCode, ErrorObject, ErrorObjectOwned};
+use log::error;
+use std::fmt::Display;
+use types::StarknetError;
+
+/// Builds the JSON-RPC error object for the given Starknet error, using the code assigned to it
+/// by the Starknet API specification.
+pub(crate) fn starknet_error(error: StarknetError) -> ErrorObjectOwned {
+    ErrorObject::owned(i64::from(error) as i32, error.to_string(), None::<()>)
+}
+
+/// Builds the JSON-RPC error object for the given Starknet error, with the reason it was
+/// returned for as its data.
+pub(crate) fn starknet_error_with_reason(
+    error: StarknetError,
+    reason: impl Display,
+) -> ErrorObjectOwned {
+    ErrorObject::owned(
+        i64::from(error) as i32,
+        error.to_string(),
+        Some(reason.to_string()),
+    )
+}
+
+/// Logs an unexpected failure and reports it as a generic internal error.
+pub(crate) fn internal_error(e: impl Display) -> ErrorObjectOwned {
+    error!("error {}", e);
+    ErrorObject::from(ErrorCode::InternalError)
+}
+
+/// Reports a store failure, keeping the Starknet error the store rejected the request with, if
+/// any.
+pub(crate) fn store_error(e: anyhow::Error) -> ErrorObjectOwned {
+    match e.downcast::<StarknetError>() {
+        Ok(error) => starknet_error(error),
+        Err(e) => internal_error(e),
+    }
+}
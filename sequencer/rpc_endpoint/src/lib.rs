@@ -1,17 +1,57 @@
 use anyhow::Result;
+use cairo_felt::Felt252;
+use execution_engine::engine::ExecutionEngine;
+use execution_engine::fee::FeeToken;
 use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use pending::SharedPendingBlock;
 use rpc::StarknetRpcApiServer;
 use sequencer::store::Store;
 use starknet_backend::StarknetBackend;
+use std::sync::{Arc, Mutex};
+use subscriptions::NewBlock;
+use sync_progress::SyncProgress;
+use tokio::sync::{broadcast, mpsc::Sender};
 
+mod error;
+pub mod pending;
 pub mod rpc;
 pub mod starknet_backend;
+pub mod subscriptions;
+pub mod sync_progress;
 
-pub async fn new_server(port: u16, store: Store) -> Result<ServerHandle> {
+/// Starts the RPC server, serving both HTTP and WebSocket connections on the given port.
+/// Subscriptions are only available over WebSocket.
+#[allow(clippy::too_many_arguments)]
+pub async fn new_server(
+    port: u16,
+    store: Store,
+    tx_transactions: Sender<Vec<u8>>,
+    execution_engine: Arc<dyn ExecutionEngine>,
+    sync_progress: SyncProgress,
+    chain_id: Felt252,
+    new_blocks: broadcast::Sender<Arc<NewBlock>>,
+    pending_block: SharedPendingBlock,
+    gas_price: u64,
+    fee_token: Option<FeeToken>,
+) -> Result<ServerHandle> {
     let server = ServerBuilder::default()
         .build(format!("0.0.0.0:{}", port))
         .await?;
-    let server_handle = server.start(StarknetBackend { store }.into_rpc())?;
+    let server_handle = server.start(
+        StarknetBackend {
+            store,
+            tx_transactions,
+            execution_engine,
+            sync_progress,
+            sync_start: Mutex::default(),
+            chain_id,
+            new_blocks,
+            pending_block,
+            gas_price,
+            fee_token,
+        }
+        .into_rpc(),
+    )?;
 
     Ok(server_handle)
 }
@@ -0,0 +1,45 @@
+use crate::rpc::{PendingBlockWithTxs, PendingTransactionReceipt};
+use cairo_felt::Felt252;
+use sequencer::store::state::StateDiff;
+use std::sync::{Arc, RwLock};
+
+/// The block the node is building out of the batches the consensus already sequenced but did not
+/// commit yet. Its transactions are executed on top of the latest stored block at the time it was
+/// built.
+#[derive(Debug, Clone)]
+pub struct PendingBlock {
+    pub block: PendingBlockWithTxs,
+    /// Receipts of the pending transactions, in the same order
+    pub receipts: Vec<PendingTransactionReceipt>,
+    /// Changes the pending transactions make to the latest stored state
+    pub state_diff: StateDiff,
+    /// Number of the stored block the pending transactions are executed on top of
+    pub parent_block_number: u64,
+}
+
+/// The pending block, shared between the node building it and the RPC endpoint serving it. It is
+/// `None` until the node builds the first one.
+pub type SharedPendingBlock = Arc<RwLock<Option<PendingBlock>>>;
+
+impl PendingBlock {
+    /// Returns the receipt of the given pending transaction, if it is in this block.
+    pub fn receipt(&self, transaction_hash: &Felt252) -> Option<&PendingTransactionReceipt> {
+        self.receipts.iter().find(|receipt| match receipt {
+            PendingTransactionReceipt::Invoke(receipt) => {
+                &receipt.transaction_hash == transaction_hash
+            }
+            PendingTransactionReceipt::L1Handler(receipt) => {
+                &receipt.transaction_hash == transaction_hash
+            }
+            PendingTransactionReceipt::Declare(receipt) => {
+                &receipt.transaction_hash == transaction_hash
+            }
+            PendingTransactionReceipt::Deploy(receipt) => {
+                &receipt.transaction_hash == transaction_hash
+            }
+            PendingTransactionReceipt::DeployAccount(receipt) => {
+                &receipt.transaction_hash == transaction_hash
+            }
+        })
+    }
+}
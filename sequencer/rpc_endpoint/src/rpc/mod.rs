@@ -4,7 +4,7 @@
 //! using the openRPC specification.
 
 use cairo_felt::Felt252;
-use jsonrpsee::core::RpcResult;
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
 
 pub use types::*;
@@ -105,6 +105,15 @@ pub trait StarknetRpcApi {
         block_id: BlockId,
     ) -> RpcResult<FeeEstimate>;
 
+    /// Simulate the given transactions in order on top of the requested block, without
+    /// persisting their effects
+    #[method(name = "simulateTransactions")]
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+
     /// Get the details of a transaction by a given block id and index
     #[method(name = "getTransactionByBlockIdAndIndex")]
     fn get_transaction_by_block_id_and_index(
@@ -142,4 +151,37 @@ pub trait StarknetRpcApi {
         &self,
         transaction_hash: FeltParam,
     ) -> RpcResult<MaybePendingTransactionReceipt>;
+
+    /// Returns the stage of its lifecycle a transaction reached in this sequencer, from being
+    /// received by the mempool to being executed or rejected.
+    #[method(name = "getTransactionStatus")]
+    fn get_transaction_status(
+        &self,
+        transaction_hash: FeltParam,
+    ) -> RpcResult<TransactionLifecycle>;
+
+    /// Notifies every new block as soon as it is stored
+    #[subscription(
+        name = "subscribeNewHeads" => "subscriptionNewHeads",
+        unsubscribe = "unsubscribeNewHeads",
+        item = BlockWithTxHashes
+    )]
+    async fn subscribe_new_heads(&self) -> SubscriptionResult;
+
+    /// Notifies the status of a transaction, and every change of it until it is final
+    #[subscription(
+        name = "subscribeTransactionStatus" => "subscriptionTransactionStatus",
+        unsubscribe = "unsubscribeTransactionStatus",
+        item = TransactionStatus
+    )]
+    async fn subscribe_transaction_status(&self, transaction_hash: FeltParam)
+        -> SubscriptionResult;
+
+    /// Notifies the events emitted in new blocks that match the address and keys of the filter
+    #[subscription(
+        name = "subscribeEvents" => "subscriptionEvents",
+        unsubscribe = "unsubscribeEvents",
+        item = EmittedEvent
+    )]
+    async fn subscribe_events(&self, filter: EventFilter) -> SubscriptionResult;
 }
@@ -1,36 +1,279 @@
+use crate::error::{internal_error, starknet_error, starknet_error_with_reason, store_error};
+use crate::pending::{PendingBlock, SharedPendingBlock};
 use crate::rpc::{
-    serializable_types::FeltParam, BlockHashAndNumber, BlockId, BroadcastedDeclareTransaction,
-    BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction, BroadcastedTransaction,
-    ContractClass, DeclareTransactionResult, DeployAccountTransactionResult, EventFilterWithPage,
-    EventsPage, FeeEstimate, FunctionCall, InvokeTransactionResult, MaybePendingBlockWithTxHashes,
-    MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, StarknetRpcApiServer, StateUpdate,
-    SyncStatusType, Transaction,
+    serializable_types::FeltParam, BlockHashAndNumber, BlockId, BlockWithTxHashes,
+    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
+    BroadcastedInvokeTransaction, BroadcastedTransaction, ContractClass, DeclareTransaction,
+    DeclareTransactionResult, DeclareTransactionV2, DeployAccountTransaction,
+    DeployAccountTransactionResult, EventFilter, EventFilterWithPage, EventsPage, FeeEstimate,
+    FunctionCall, InvokeTransaction, InvokeTransactionResult, InvokeTransactionV1,
+    MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt,
+    SimulatedTransaction, StarknetError, StarknetRpcApiServer, StateUpdate, SyncStatus,
+    SyncStatusType, Transaction, TransactionLifecycle,
 };
+use crate::subscriptions::{next_block, next_status, NewBlock};
+use crate::sync_progress::SyncProgress;
 use cairo_felt::Felt252;
+use execution_engine::account::deploy_account_address;
+use execution_engine::contract_class::compiled_class_hash;
+use execution_engine::engine::{
+    BlockContext, CallError, ContractCall, ExecutionContext, ExecutionEngine, MAX_CALL_GAS,
+};
+use execution_engine::fee::FeeToken;
+use execution_engine::state::{CachedState, StateReader, StoreState};
+use execution_engine::transaction::TransactionExecution;
 use jsonrpsee::{
-    core::{async_trait, RpcResult},
+    core::{async_trait, RpcResult, SubscriptionResult},
     types::{error::ErrorCode, ErrorObject},
+    PendingSubscriptionSink, SubscriptionMessage,
+};
+use log::info;
+use sequencer::store::{
+    events::{EventQuery, MAX_EVENT_FILTER_KEYS},
+    Store,
 };
-use log::{error, info};
-use sequencer::store::Store;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc::Sender};
 
 pub struct StarknetBackend {
     pub(crate) store: Store,
+    /// Channel to submit serialized transactions to the mempool.
+    pub(crate) tx_transactions: Sender<Vec<u8>>,
+    /// Engine used to run calls, the same one the node executes transactions with.
+    pub(crate) execution_engine: Arc<dyn ExecutionEngine>,
+    /// How far behind the committee the node is.
+    pub(crate) sync_progress: SyncProgress,
+    /// The last stored block when the node started syncing, if it is syncing.
+    pub(crate) sync_start: Mutex<Option<BlockHashAndNumber>>,
+    /// Id of the chain this node sequences, included in the transaction hashes.
+    pub(crate) chain_id: Felt252,
+    /// Blocks stored by the node, feeding the subscriptions.
+    pub(crate) new_blocks: broadcast::Sender<Arc<NewBlock>>,
+    /// Block built by the node out of the sequenced batches that are not committed yet.
+    pub(crate) pending_block: SharedPendingBlock,
+    /// Price of a unit of gas in the blocks the node builds, in wei.
+    pub(crate) gas_price: u64,
+    /// Token the node charges fees in, if it charges any.
+    pub(crate) fee_token: Option<FeeToken>,
+}
+
+impl StarknetBackend {
+    /// Sends a serialized transaction to the mempool, see [Transaction::as_bytes] and
+    /// [Transaction::declare_as_bytes].
+    async fn submit_transaction(&self, transaction_bytes: Vec<u8>) -> RpcResult<()> {
+        // When benchmarking, the node expects every transaction to start with a 9 bytes header: a
+        // byte flagging sample (0) or standard (1) transactions, followed by an 8 bytes ID.
+        #[cfg(feature = "benchmark")]
+        let transaction_bytes = [&[1u8; 9][..], &transaction_bytes].concat();
+
+        self.tx_transactions
+            .send(transaction_bytes)
+            .await
+            .map_err(internal_error)
+    }
+
+    /// Returns the stored block the given block id refers to.
+    fn get_block(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxs> {
+        let block = match block_id {
+            BlockId::Number(0) => self.store.get_block_by_height(1),
+            BlockId::Number(height) => {
+                info!("block number requested is {}", &height);
+                self.store.get_block_by_height(height)
+            }
+            BlockId::Hash(hash) => self.store.get_block_by_hash(hash),
+            BlockId::Latest => match self.store.get_height() {
+                Some(0) | None => return Err(starknet_error(StarknetError::NoBlocks)),
+                Some(height) => self.store.get_block_by_height(height),
+            },
+            BlockId::Pending => {
+                return self
+                    .pending_block()
+                    .map(|pending| MaybePendingBlockWithTxs::PendingBlock(pending.block))
+                    .ok_or_else(|| starknet_error(StarknetError::BlockNotFound))
+            }
+        };
+        block
+            .map_err(internal_error)?
+            .ok_or_else(|| starknet_error(StarknetError::BlockNotFound))
+    }
+
+    /// Returns the block being built out of the sequenced batches, if the node built one yet.
+    fn pending_block(&self) -> Option<PendingBlock> {
+        self.pending_block.read().unwrap().clone()
+    }
+
+    /// Returns the state at the end of the given block. Writes are kept on top of it, without
+    /// being persisted.
+    fn state_at(&self, block_id: BlockId) -> RpcResult<CachedState> {
+        // The pending state is the one the pending block was built on, with the changes of the
+        // pending transactions. Blocks stored since then are left out, as they already hold them.
+        Ok(
+            match self
+                .pending_block()
+                .filter(|_| block_id == BlockId::Pending)
+            {
+                Some(pending) => CachedState::with_state_diff(
+                    Box::new(StoreState::new(
+                        self.store.clone(),
+                        pending.parent_block_number,
+                    )),
+                    pending.state_diff,
+                ),
+                None => CachedState::new(Box::new(StoreState::new(
+                    self.store.clone(),
+                    self.get_block_number(block_id)?,
+                ))),
+            },
+        )
+    }
+
+    /// Executes the transactions in order on top of a fork of the state at the given block, which
+    /// is thrown away afterwards. Fees are charged as the node does, unless gas is unlimited: then
+    /// transactions can consume more than their maximal fee pays for, and senders don't need the
+    /// funds to pay it. Transactions the engine rejects fail the whole simulation, with the reason
+    /// why as the data of the error.
+    fn simulate(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        unlimited_gas: bool,
+    ) -> RpcResult<Vec<TransactionExecution>> {
+        let block_context = self.block_context(block_id.clone())?;
+        let mut state = self.state_at(block_id)?;
+        transactions
+            .into_iter()
+            .map(|transaction| match transaction {
+                BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(tx)) => {
+                    let mut tx = InvokeTransactionV1::from_broadcasted(tx, &self.chain_id);
+                    if unlimited_gas {
+                        // Once hashed, so the signature is still the one of the transaction
+                        tx.max_fee = Felt252::new(u64::MAX);
+                    }
+                    let execution = match self.fee_token.as_ref().filter(|_| !unlimited_gas) {
+                        Some(fee_token) => fee_token.execute_invoke(
+                            self.execution_engine.as_ref(),
+                            &tx,
+                            &block_context,
+                            &mut state,
+                        ),
+                        None => {
+                            self.execution_engine
+                                .execute_invoke(&tx, &block_context, &mut state)
+                        }
+                    }
+                    .map_err(|reason| {
+                        starknet_error_with_reason(StarknetError::ContractError, reason)
+                    })?;
+                    state
+                        .increment_nonce(&tx.sender_address)
+                        .map_err(internal_error)?;
+                    Ok(execution)
+                }
+                BroadcastedTransaction::DeployAccount(tx) => {
+                    let mut tx = DeployAccountTransaction::from_broadcasted(tx, &self.chain_id);
+                    if unlimited_gas {
+                        // Once hashed, so the signature is still the one of the transaction
+                        tx.max_fee = Felt252::new(u64::MAX);
+                    }
+                    let execution = match self.fee_token.as_ref().filter(|_| !unlimited_gas) {
+                        Some(fee_token) => fee_token.execute_deploy_account(
+                            self.execution_engine.as_ref(),
+                            &tx,
+                            &block_context,
+                            &mut state,
+                        ),
+                        None => self.execution_engine.execute_deploy_account(
+                            &tx,
+                            &block_context,
+                            &mut state,
+                        ),
+                    }
+                    .map_err(|reason| {
+                        starknet_error_with_reason(StarknetError::ContractError, reason)
+                    })?;
+                    state
+                        .increment_nonce(&deploy_account_address(&tx))
+                        .map_err(internal_error)?;
+                    Ok(execution)
+                }
+                // Currently only InvokeTransactionV1 and DeployAccountTransaction are supported
+                _ => Err(ErrorObject::from(ErrorCode::InvalidParams)),
+            })
+            .collect()
+    }
+
+    /// Returns the block calls on top of the given one run in: the next one, as the sequencer of
+    /// the given block would build it now.
+    fn block_context(&self, block_id: BlockId) -> RpcResult<BlockContext> {
+        let sequencer_address = match self.get_block(block_id.clone()) {
+            Ok(MaybePendingBlockWithTxs::Block(block)) => block.sequencer_address,
+            Ok(MaybePendingBlockWithTxs::PendingBlock(block)) => block.sequencer_address,
+            Err(_) => Felt252::new(0),
+        };
+        Ok(BlockContext {
+            block_number: self.get_block_number(block_id)? + 1,
+            block_timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(internal_error)?
+                .as_secs(),
+            sequencer_address,
+            gas_price: self.gas_price,
+        })
+    }
+
+    /// Resolves a block id to the number of the block it refers to.
+    fn get_block_number(&self, block_id: BlockId) -> RpcResult<u64> {
+        match block_id {
+            BlockId::Number(block_number)
+                if block_number > self.store.get_height().unwrap_or(0) =>
+            {
+                Err(starknet_error(StarknetError::BlockNotFound))
+            }
+            BlockId::Number(block_number) => Ok(block_number),
+            BlockId::Latest => Ok(self.store.get_height().unwrap_or(0)),
+            // The pending block is built on top of the latest one at the time it was built
+            BlockId::Pending => Ok(self
+                .pending_block()
+                .map(|pending| pending.parent_block_number)
+                .unwrap_or_else(|| self.store.get_height().unwrap_or(0))),
+            BlockId::Hash(_) => match self.get_block(block_id)? {
+                MaybePendingBlockWithTxs::Block(block) => Ok(block.block_number),
+                MaybePendingBlockWithTxs::PendingBlock(_) => {
+                    Err(starknet_error(StarknetError::BlockNotFound))
+                }
+            },
+        }
+    }
 }
 
 #[async_trait]
 #[allow(unused_variables)]
 impl StarknetRpcApiServer for StarknetBackend {
     fn block_number(&self) -> RpcResult<u64> {
-        Ok(self.store.get_height().expect("Heigh not found"))
+        self.store
+            .get_height()
+            .ok_or_else(|| starknet_error(StarknetError::NoBlocks))
     }
 
     fn block_hash_and_number(&self) -> RpcResult<BlockHashAndNumber> {
-        unimplemented!();
+        match self.get_block(BlockId::Latest)? {
+            MaybePendingBlockWithTxs::Block(block) => Ok(BlockHashAndNumber {
+                block_hash: block.block_hash,
+                block_number: block.block_number,
+            }),
+            MaybePendingBlockWithTxs::PendingBlock(_) => {
+                Err(starknet_error(StarknetError::NoBlocks))
+            }
+        }
     }
 
     fn get_block_transaction_count(&self, block_id: BlockId) -> RpcResult<u128> {
-        unimplemented!();
+        let transaction_count = match self.get_block(block_id)? {
+            MaybePendingBlockWithTxs::Block(block) => block.transactions.len(),
+            MaybePendingBlockWithTxs::PendingBlock(block) => block.transactions.len(),
+        };
+        Ok(transaction_count as u128)
     }
 
     /// get the storage at a given address and key and at a given block
@@ -40,11 +283,47 @@ impl StarknetRpcApiServer for StarknetBackend {
         key: FeltParam,
         block_id: BlockId,
     ) -> RpcResult<Felt252> {
-        unimplemented!();
+        // Slots that were never written hold zero
+        self.state_at(block_id)?
+            .get_storage_at(&contract_address.0, &key.0)
+            .map_err(internal_error)
     }
 
+    /// Calls a function of a contract on top of the state at the given block. Nothing the call
+    /// writes is persisted.
     fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<String>> {
-        unimplemented!();
+        let context = ExecutionContext {
+            block: self.block_context(block_id.clone())?,
+            transaction: Default::default(),
+        };
+        let mut state = self.state_at(block_id)?;
+        let call_info = self
+            .execution_engine
+            .call(
+                &ContractCall {
+                    contract_address: request.contract_address,
+                    entry_point_selector: request.entry_point_selector,
+                    calldata: request.calldata,
+                    initial_gas: MAX_CALL_GAS,
+                },
+                &context,
+                &mut state,
+            )
+            .map_err(|e| match e {
+                CallError::ContractNotFound => starknet_error(StarknetError::ContractNotFound),
+                CallError::EntryPointNotFound => {
+                    starknet_error(StarknetError::InvalidMessageSelector)
+                }
+                CallError::InvalidClass | CallError::Failed(_) => {
+                    starknet_error(StarknetError::ContractError)
+                }
+            })?;
+
+        Ok(call_info
+            .retdata
+            .iter()
+            .map(|felt| format!("0x{}", felt.to_str_radix(16)))
+            .collect())
     }
 
     /// Get the contract class at a given contract address for a given block id
@@ -53,7 +332,8 @@ impl StarknetRpcApiServer for StarknetBackend {
         block_id: BlockId,
         contract_address: FeltParam,
     ) -> RpcResult<ContractClass> {
-        unimplemented!();
+        let class_hash = self.get_class_hash_at(block_id.clone(), contract_address)?;
+        self.get_class(block_id, FeltParam(class_hash))
     }
 
     /// Get the contract class hash in the given block for the contract deployed at the given
@@ -73,17 +353,68 @@ impl StarknetRpcApiServer for StarknetBackend {
         block_id: BlockId,
         contract_address: FeltParam,
     ) -> RpcResult<Felt252> {
-        unimplemented!();
+        if block_id == BlockId::Pending {
+            let deployed = self.pending_block().and_then(|pending| {
+                pending
+                    .state_diff
+                    .class_hashes
+                    .get(&contract_address.0)
+                    .cloned()
+            });
+            if let Some(class_hash) = deployed {
+                return Ok(class_hash);
+            }
+        }
+        let block_number = self.get_block_number(block_id)?;
+        self.store
+            .get_class_hash_at(&contract_address.0, block_number)
+            .map_err(internal_error)?
+            .ok_or_else(|| starknet_error(StarknetError::ContractNotFound))
     }
 
     /// Implementation of the `syncing` RPC Endpoint.
     async fn syncing(&self) -> RpcResult<SyncStatusType> {
-        unimplemented!();
+        let mut sync_start = self.sync_start.lock().unwrap();
+        if !self.sync_progress.is_syncing() {
+            *sync_start = None;
+            return Ok(SyncStatusType::NotSyncing);
+        }
+
+        let current = match self.get_block(BlockId::Latest) {
+            Ok(MaybePendingBlockWithTxs::Block(block)) => BlockHashAndNumber {
+                block_hash: block.block_hash,
+                block_number: block.block_number,
+            },
+            // Nothing was stored yet
+            _ => BlockHashAndNumber {
+                block_hash: Felt252::new(0),
+                block_number: 0,
+            },
+        };
+        let start = sync_start.get_or_insert_with(|| current.clone());
+        let highest_block_num = current.block_number + self.sync_progress.blocks_behind();
+
+        Ok(SyncStatusType::Syncing(SyncStatus {
+            starting_block_hash: start.block_hash.clone(),
+            starting_block_num: start.block_number,
+            current_block_hash: current.block_hash.clone(),
+            current_block_num: current.block_number,
+            // Hashes are computed when blocks are stored, so the ones ahead are not known yet
+            highest_block_hash: if highest_block_num == current.block_number {
+                current.block_hash
+            } else {
+                Felt252::new(0)
+            },
+            highest_block_num,
+        }))
     }
 
     /// Get the contract class definition in the given block associated with the given hash.
     fn get_class(&self, block_id: BlockId, class_hash: FeltParam) -> RpcResult<ContractClass> {
-        unimplemented!();
+        self.state_at(block_id)?
+            .get_class(&class_hash.0)
+            .map_err(internal_error)?
+            .ok_or_else(|| starknet_error(StarknetError::ClassHashNotFound))
     }
 
     /// Returns the specified block with transaction hashes.
@@ -91,39 +422,26 @@ impl StarknetRpcApiServer for StarknetBackend {
         &self,
         block_id: BlockId,
     ) -> RpcResult<MaybePendingBlockWithTxHashes> {
-        unimplemented!();
+        self.get_block(block_id)
+            .map(MaybePendingBlockWithTxHashes::from)
     }
 
     /// Get the nonce associated with the given address at the given block
     fn get_nonce(&self, block_id: BlockId, contract_address: FeltParam) -> RpcResult<Felt252> {
-        unimplemented!();
+        // Contracts that never sent a transaction have a zero nonce
+        self.state_at(block_id)?
+            .get_nonce_at(&contract_address.0)
+            .map_err(internal_error)
     }
 
     /// Get block information with full transactions given the block id
     fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxs> {
-        let block_bytes = match block_id {
-            BlockId::Number(0) => self.store.get_block_by_height(1),
-            BlockId::Number(height) => {
-                info!("block number requested is {}", &height);
-                self.store.get_block_by_height(height)
-            }
-            BlockId::Hash(hash) => self.store.get_block_by_hash(hash),
-            BlockId::Latest => self
-                .store
-                .get_block_by_height(self.store.get_height().expect("Height not found")),
-            _ => todo!(),
-        };
-        block_bytes
-            .map(|option| option.expect("Block not found"))
-            .map_err(|e| {
-                error!("error {}", e);
-                ErrorObject::from(ErrorCode::InternalError)
-            })
+        self.get_block(block_id)
     }
 
     /// Returns the chain id.
     fn chain_id(&self) -> RpcResult<Felt252> {
-        unimplemented!();
+        Ok(self.chain_id.clone())
     }
 
     /// Add an Invoke Transaction to invoke a contract function
@@ -139,7 +457,27 @@ impl StarknetRpcApiServer for StarknetBackend {
         &self,
         invoke_transaction: BroadcastedInvokeTransaction,
     ) -> RpcResult<InvokeTransactionResult> {
-        unimplemented!();
+        let invoke_tx_v1 = match invoke_transaction {
+            BroadcastedInvokeTransaction::V1(broadcasted_tx) => {
+                InvokeTransactionV1::from_broadcasted(broadcasted_tx, &self.chain_id)
+            }
+            // Currently only InvokeTransactionV1 are supported
+            BroadcastedInvokeTransaction::V0(_) => {
+                return Err(ErrorObject::from(ErrorCode::InvalidParams));
+            }
+        };
+        let transaction_hash = invoke_tx_v1.transaction_hash.clone();
+
+        self.submit_transaction(
+            Transaction::Invoke(InvokeTransaction::V1(invoke_tx_v1)).as_bytes(),
+        )
+        .await?;
+
+        info!(
+            "Invoke transaction 0x{} submitted to the mempool",
+            transaction_hash.to_str_radix(16)
+        );
+        Ok(InvokeTransactionResult { transaction_hash })
     }
 
     /// Add an Deploy Account Transaction
@@ -156,7 +494,29 @@ impl StarknetRpcApiServer for StarknetBackend {
         &self,
         deploy_account_transaction: BroadcastedDeployAccountTransaction,
     ) -> RpcResult<DeployAccountTransactionResult> {
-        unimplemented!();
+        let deploy_account_tx =
+            DeployAccountTransaction::from_broadcasted(deploy_account_transaction, &self.chain_id);
+        // Checked here as well as when executing, so clients learn about it right away
+        let declared = self
+            .state_at(BlockId::Pending)?
+            .get_class(&deploy_account_tx.class_hash)
+            .map_err(internal_error)?;
+        if declared.is_none() {
+            return Err(starknet_error(StarknetError::ClassHashNotFound));
+        }
+        let result = DeployAccountTransactionResult {
+            transaction_hash: deploy_account_tx.transaction_hash.clone(),
+            contract_address: deploy_account_address(&deploy_account_tx),
+        };
+
+        self.submit_transaction(Transaction::DeployAccount(deploy_account_tx).as_bytes())
+            .await?;
+
+        info!(
+            "Deploy account transaction 0x{} submitted to the mempool",
+            result.transaction_hash.to_str_radix(16)
+        );
+        Ok(result)
     }
 
     /// Estimate the fee associated with transaction
@@ -174,7 +534,36 @@ impl StarknetRpcApiServer for StarknetBackend {
         request: BroadcastedTransaction,
         block_id: BlockId,
     ) -> RpcResult<FeeEstimate> {
-        unimplemented!();
+        // The estimate shouldn't depend on the fee offered
+        let execution = self
+            .simulate(block_id, vec![request], true)?
+            .pop()
+            .expect("one execution per simulated transaction");
+        if let Some(revert_reason) = &execution.revert_reason {
+            return Err(starknet_error_with_reason(
+                StarknetError::ContractError,
+                revert_reason,
+            ));
+        }
+        Ok(fee_estimate(&execution))
+    }
+
+    /// Simulate the given transactions in order on top of the requested block, without
+    /// persisting their effects
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        Ok(self
+            .simulate(block_id, transactions, false)?
+            .into_iter()
+            .map(|execution| SimulatedTransaction {
+                fee_estimation: fee_estimate(&execution),
+                events: execution.events,
+                revert_reason: execution.revert_reason,
+            })
+            .collect())
     }
 
     // Returns the details of a transaction by a given block id and index
@@ -183,7 +572,14 @@ impl StarknetRpcApiServer for StarknetBackend {
         block_id: BlockId,
         index: usize,
     ) -> RpcResult<Transaction> {
-        unimplemented!();
+        let transactions = match self.get_block(block_id)? {
+            MaybePendingBlockWithTxs::Block(block) => block.transactions,
+            MaybePendingBlockWithTxs::PendingBlock(block) => block.transactions,
+        };
+        transactions
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| starknet_error(StarknetError::InvalidTransactionIndex))
     }
 
     /// Get the information about the result of executing the requested block
@@ -192,13 +588,45 @@ impl StarknetRpcApiServer for StarknetBackend {
     }
 
     /// Returns the transactions in the transaction pool, recognized by this sequencer
+    ///
+    /// These are the transactions of the pending block: already sequenced by the consensus, but
+    /// not committed yet.
     async fn pending_transactions(&self) -> RpcResult<Vec<Transaction>> {
-        unimplemented!();
+        Ok(self
+            .pending_block()
+            .map(|pending| pending.block.transactions)
+            .unwrap_or_default())
     }
 
     /// Returns all events matching the given filter
     async fn get_events(&self, filter: EventFilterWithPage) -> RpcResult<EventsPage> {
-        unimplemented!();
+        let EventFilterWithPage {
+            event_filter,
+            result_page_request,
+        } = filter;
+        if result_page_request.chunk_size == 0 {
+            return Err(ErrorObject::from(ErrorCode::InvalidParams));
+        }
+
+        let from_block = match event_filter.from_block {
+            Some(block_id) => self.get_block_number(block_id)?,
+            None => 0,
+        };
+        let to_block = match event_filter.to_block {
+            Some(block_id) => self.get_block_number(block_id)?,
+            None => self.store.get_height().unwrap_or(0),
+        };
+
+        self.store
+            .get_events(EventQuery {
+                from_block,
+                to_block,
+                address: event_filter.address,
+                keys: event_filter.keys.unwrap_or_default(),
+                chunk_size: result_page_request.chunk_size,
+                continuation_token: result_page_request.continuation_token,
+            })
+            .map_err(store_error)
     }
 
     /// Submit a new declare transaction to be added to the chain
@@ -214,7 +642,49 @@ impl StarknetRpcApiServer for StarknetBackend {
         &self,
         declare_transaction: BroadcastedDeclareTransaction,
     ) -> RpcResult<DeclareTransactionResult> {
-        unimplemented!();
+        let (declare_tx, contract_class, result) = match declare_transaction {
+            // Legacy classes aren't hashed the way Starknet does, so clients couldn't tell the
+            // hash their class would be declared under
+            BroadcastedDeclareTransaction::V1(_) => {
+                return Err(starknet_error_with_reason(
+                    StarknetError::InvalidContractClass,
+                    "Legacy classes can't be declared",
+                ))
+            }
+            BroadcastedDeclareTransaction::V2(broadcasted_tx) => {
+                // Checked here as well as when executing, so clients learn about it right away
+                let compiled_class_hash = compiled_class_hash(&broadcasted_tx.contract_class)
+                    .map_err(|_| starknet_error(StarknetError::InvalidContractClass))?;
+                if compiled_class_hash != broadcasted_tx.compiled_class_hash {
+                    return Err(starknet_error(StarknetError::InvalidContractClass));
+                }
+                let contract_class =
+                    ContractClass::Sierra(broadcasted_tx.contract_class.as_ref().clone());
+                let declare_tx =
+                    DeclareTransactionV2::from_broadcasted(broadcasted_tx, &self.chain_id);
+                let result = DeclareTransactionResult {
+                    transaction_hash: declare_tx.transaction_hash.clone(),
+                    class_hash: declare_tx.class_hash.clone(),
+                };
+                (DeclareTransaction::V2(declare_tx), contract_class, result)
+            }
+        };
+        let declared = self
+            .state_at(BlockId::Pending)?
+            .get_class(&result.class_hash)
+            .map_err(internal_error)?;
+        if declared.is_some() {
+            return Err(starknet_error(StarknetError::ClassAlreadyDeclared));
+        }
+
+        self.submit_transaction(Transaction::Declare(declare_tx).declare_as_bytes(&contract_class))
+            .await?;
+
+        info!(
+            "Declare transaction 0x{} submitted to the mempool",
+            result.transaction_hash.to_str_radix(16)
+        );
+        Ok(result)
     }
 
     /// Returns a transaction details from its hash.
@@ -229,11 +699,8 @@ impl StarknetRpcApiServer for StarknetBackend {
     fn get_transaction_by_hash(&self, transaction_hash: FeltParam) -> RpcResult<Transaction> {
         self.store
             .get_transaction(transaction_hash.0)
-            .map(|option| option.expect("Transaction not found"))
-            .map_err(|e| {
-                error!("error {}", e);
-                ErrorObject::from(ErrorCode::InternalError)
-            })
+            .map_err(internal_error)?
+            .ok_or_else(|| starknet_error(StarknetError::TransactionHashNotFound))
     }
 
     /// Returns the receipt of a transaction by transaction hash.
@@ -245,12 +712,111 @@ impl StarknetRpcApiServer for StarknetBackend {
         &self,
         transaction_hash: FeltParam,
     ) -> RpcResult<MaybePendingTransactionReceipt> {
+        if let Some(receipt) = self
+            .store
+            .get_transaction_receipt(transaction_hash.0.clone())
+            .map_err(internal_error)?
+        {
+            return Ok(receipt);
+        }
+        self.pending_block()
+            .and_then(|pending| pending.receipt(&transaction_hash.0).cloned())
+            .map(MaybePendingTransactionReceipt::PendingReceipt)
+            .ok_or_else(|| starknet_error(StarknetError::TransactionHashNotFound))
+    }
+
+    /// Returns the stage of its lifecycle a transaction reached in this sequencer.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_hash` - Transaction hash corresponding to the transaction.
+    fn get_transaction_status(
+        &self,
+        transaction_hash: FeltParam,
+    ) -> RpcResult<TransactionLifecycle> {
         self.store
-            .get_transaction_receipt(transaction_hash.0)
-            .map(|option| option.expect("Transaction not found"))
-            .map_err(|e| {
-                error!("error {}", e);
-                ErrorObject::from(ErrorCode::InternalError)
-            })
+            .get_transaction_status(&transaction_hash.0)
+            .map_err(internal_error)?
+            .ok_or_else(|| starknet_error(StarknetError::TransactionHashNotFound))
+    }
+
+    /// Notifies every new block as soon as it is stored
+    async fn subscribe_new_heads(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut new_blocks = self.new_blocks.subscribe();
+        let sink = pending.accept().await?;
+
+        while let Some(new_block) = next_block(&sink, &mut new_blocks).await {
+            let header = BlockWithTxHashes::from(new_block.block.clone());
+            sink.send(SubscriptionMessage::from_json(&header)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Notifies the status of a transaction, as `getTransactionStatus` returns it, and every
+    /// stage it reaches until a final one
+    async fn subscribe_transaction_status(
+        &self,
+        pending: PendingSubscriptionSink,
+        transaction_hash: FeltParam,
+    ) -> SubscriptionResult {
+        // Subscribe before looking the transaction up, so a stage recorded in between is not
+        // missed
+        let mut status_updates = self.store.subscribe_transaction_status();
+        let sink = pending.accept().await?;
+
+        let mut status = self.store.get_transaction_status(&transaction_hash.0)?;
+        let mut notified_stage = None;
+        loop {
+            if let Some(status) = status.filter(|status| Some(status.stage) > notified_stage) {
+                sink.send(SubscriptionMessage::from_json(&status)?).await?;
+                if status.stage.is_final() {
+                    return Ok(());
+                }
+                notified_stage = Some(status.stage);
+            }
+            status = match next_status(&sink, &mut status_updates, &self.store, &transaction_hash.0)
+                .await
+            {
+                Some(status) => Some(status),
+                None => return Ok(()),
+            };
+        }
+    }
+
+    /// Notifies the events emitted in new blocks that match the address and keys of the filter
+    async fn subscribe_events(
+        &self,
+        pending: PendingSubscriptionSink,
+        filter: EventFilter,
+    ) -> SubscriptionResult {
+        let query = EventQuery {
+            address: filter.address,
+            keys: filter.keys.unwrap_or_default(),
+            ..Default::default()
+        };
+        if query.keys.iter().map(Vec::len).sum::<usize>() > MAX_EVENT_FILTER_KEYS {
+            pending
+                .reject(starknet_error(StarknetError::TooManyKeysInFilter))
+                .await;
+            return Ok(());
+        }
+
+        let mut new_blocks = self.new_blocks.subscribe();
+        let sink = pending.accept().await?;
+
+        while let Some(new_block) = next_block(&sink, &mut new_blocks).await {
+            for event in new_block.events.iter().filter(|event| query.matches(event)) {
+                sink.send(SubscriptionMessage::from_json(event)?).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fee_estimate(execution: &TransactionExecution) -> FeeEstimate {
+    FeeEstimate {
+        gas_consumed: execution.gas_consumed,
+        gas_price: execution.gas_price,
+        overall_fee: execution.fee(),
     }
 }
@@ -0,0 +1,60 @@
+use crate::rpc::{BlockWithTxs, EmittedEvent, TransactionLifecycle};
+use cairo_felt::Felt252;
+use jsonrpsee::SubscriptionSink;
+use sequencer::store::Store;
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// A block the node just stored, along with the events emitted in it. Sent to the RPC endpoint to
+/// feed the subscriptions.
+#[derive(Debug, Clone)]
+pub struct NewBlock {
+    pub block: BlockWithTxs,
+    pub events: Vec<EmittedEvent>,
+}
+
+/// Waits for the next stored block. Returns `None` once the subscriber is gone or the node
+/// stopped storing blocks.
+///
+/// Subscribers that fall too far behind miss the oldest blocks rather than slowing the node down.
+pub(crate) async fn next_block(
+    sink: &SubscriptionSink,
+    new_blocks: &mut Receiver<Arc<NewBlock>>,
+) -> Option<Arc<NewBlock>> {
+    loop {
+        tokio::select! {
+            _ = sink.closed() => return None,
+            block = new_blocks.recv() => match block {
+                Ok(block) => return Some(block),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            },
+        }
+    }
+}
+
+/// Waits for the next status recorded for the given transaction. Returns `None` once the
+/// subscriber is gone or the store stopped recording statuses.
+///
+/// Subscribers that fall too far behind get the status the store holds by then.
+pub(crate) async fn next_status(
+    sink: &SubscriptionSink,
+    status_updates: &mut Receiver<TransactionLifecycle>,
+    store: &Store,
+    transaction_hash: &Felt252,
+) -> Option<TransactionLifecycle> {
+    loop {
+        tokio::select! {
+            _ = sink.closed() => return None,
+            status = status_updates.recv() => match status {
+                Ok(status) if &status.transaction_hash == transaction_hash => return Some(status),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => match store.get_transaction_status(transaction_hash) {
+                    Ok(Some(status)) => return Some(status),
+                    _ => continue,
+                },
+                Err(RecvError::Closed) => return None,
+            },
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Rounds the stored blocks can lag behind the consensus before the node is reported as
+/// syncing. Committed blocks take a moment to be executed and stored, even on a node that keeps
+/// up with the committee.
+pub const MAX_ROUNDS_BEHIND: u64 = 10;
+
+/// How far behind the rest of the committee the node is, as reported by the components that
+/// catch up with it. Shared between the node and the RPC endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgress {
+    /// Parent blocks requested by the consensus synchronizer and not received yet
+    pub pending_blocks: Arc<AtomicUsize>,
+    /// Batches requested by the mempool synchronizer and not received yet
+    pub pending_batches: Arc<AtomicUsize>,
+    /// Round of the last block committed by the consensus
+    pub committed_round: Arc<AtomicU64>,
+    /// Round of the last committed block the node is done executing and storing
+    pub stored_round: Arc<AtomicU64>,
+}
+
+impl SyncProgress {
+    pub fn new(pending_blocks: Arc<AtomicUsize>, pending_batches: Arc<AtomicUsize>) -> Self {
+        Self {
+            pending_blocks,
+            pending_batches,
+            committed_round: Arc::default(),
+            stored_round: Arc::default(),
+        }
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.pending_blocks.load(Ordering::Relaxed) > 0
+            || self.pending_batches.load(Ordering::Relaxed) > 0
+            || self.rounds_behind() > MAX_ROUNDS_BEHIND
+    }
+
+    /// Estimates how many L2 blocks are yet to be stored. Every block the consensus is waiting for,
+    /// and every round committed but not stored yet, may end up as an L2 block.
+    pub fn blocks_behind(&self) -> u64 {
+        self.pending_blocks.load(Ordering::Relaxed) as u64 + self.rounds_behind()
+    }
+
+    /// Rounds between the last block committed by the consensus and the last one stored.
+    fn rounds_behind(&self) -> u64 {
+        self.committed_round
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.stored_round.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SyncProgress, MAX_ROUNDS_BEHIND};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn nodes_storing_the_last_commits_are_not_syncing() {
+        let progress = SyncProgress::default();
+        progress.committed_round.store(100, Ordering::Relaxed);
+        progress.stored_round.store(99, Ordering::Relaxed);
+        assert!(!progress.is_syncing());
+
+        progress
+            .stored_round
+            .store(99 - MAX_ROUNDS_BEHIND, Ordering::Relaxed);
+        assert!(progress.is_syncing());
+        assert_eq!(MAX_ROUNDS_BEHIND + 1, progress.blocks_behind());
+
+        progress.stored_round.store(100, Ordering::Relaxed);
+        progress.pending_batches.store(1, Ordering::Relaxed);
+        assert!(progress.is_syncing());
+    }
+}
@@ -0,0 +1,161 @@
+use super::{Store, StoreEngine};
+use anyhow::{anyhow, Result};
+use cairo_felt::Felt252;
+use std::collections::HashSet;
+use types::{EmittedEvent, EventsPage, StarknetError};
+
+/// Maximum amount of events returned in a single page.
+pub const MAX_EVENTS_CHUNK_SIZE: u64 = 1024;
+/// Maximum amount of keys, across all positions, accepted in an event filter.
+pub const MAX_EVENT_FILTER_KEYS: usize = 256;
+
+const EVENTS_BY_ADDRESS: &str = "events_by_address";
+
+/// A query over the emitted events, with the block range already resolved to block numbers.
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// First block to look for events in, inclusive
+    pub from_block: u64,
+    /// Last block to look for events in, inclusive
+    pub to_block: u64,
+    /// Only return events emitted by this contract
+    pub address: Option<Felt252>,
+    /// Allowed values for each key position. An empty list matches any key in that position
+    pub keys: Vec<Vec<Felt252>>,
+    /// Maximum amount of events to return
+    pub chunk_size: u64,
+    /// Token returned by a previous query, to resume from where it stopped
+    pub continuation_token: Option<String>,
+}
+
+impl EventQuery {
+    /// Whether the event passes the address and keys filters. The block range is not checked.
+    pub fn matches(&self, event: &EmittedEvent) -> bool {
+        if let Some(address) = &self.address {
+            if &event.from_address != address {
+                return false;
+            }
+        }
+        self.keys.iter().enumerate().all(|(position, allowed)| {
+            allowed.is_empty()
+                || matches!(event.keys.get(position), Some(key) if allowed.contains(key))
+        })
+    }
+}
+
+/// Continuation tokens point at the next event to be returned: the block it was emitted in and
+/// its position among the events of that block.
+fn continuation_token(block_number: u64, event_index: usize) -> String {
+    format!("{block_number}-{event_index}")
+}
+
+fn parse_continuation_token(token: &str) -> Result<(u64, usize)> {
+    token
+        .split_once('-')
+        .and_then(|(block_number, event_index)| {
+            Some((block_number.parse().ok()?, event_index.parse().ok()?))
+        })
+        .ok_or_else(|| anyhow!(StarknetError::InvalidContinuationToken))
+}
+
+fn events_by_address_key(address: &Felt252) -> Vec<u8> {
+    format!("{EVENTS_BY_ADDRESS}.{}", address.to_str_radix(16)).into_bytes()
+}
+
+fn get_event_blocks(engine: &dyn StoreEngine, address: &Felt252) -> Result<Vec<u64>> {
+    engine
+        .get_value(events_by_address_key(address))?
+        .map_or(Ok(vec![]), |value| Ok(serde_json::from_slice(&value)?))
+}
+
+impl Store {
+    /// Stores the events emitted in the given block, indexing them by the contracts that emitted
+    /// them.
+    pub fn add_events(&mut self, block_number: u64, events: Vec<EmittedEvent>) -> Result<()> {
+        let engine = self.engine.clone();
+        let mut engine = engine.lock().unwrap();
+
+        let addresses: HashSet<Felt252> = events
+            .iter()
+            .map(|event| event.from_address.clone())
+            .collect();
+        for address in addresses {
+            let mut blocks = get_event_blocks(&engine, &address)?;
+            if blocks.last() != Some(&block_number) {
+                blocks.push(block_number);
+                engine.set_value(
+                    events_by_address_key(&address),
+                    serde_json::to_vec(&blocks)?,
+                )?;
+            }
+        }
+
+        engine.add_block_events(block_number, events)
+    }
+
+    /// Returns a page of the events matching the query, along with a continuation token when
+    /// there are more events left. Blocks past the last stored one are left out of the range.
+    pub fn get_events(&self, query: EventQuery) -> Result<EventsPage> {
+        // Empty pages would hand back the token they were requested with, forever
+        if query.chunk_size == 0 {
+            return Err(anyhow!("Invalid page size: pages hold at least one event"));
+        }
+        if query.chunk_size > MAX_EVENTS_CHUNK_SIZE {
+            return Err(anyhow!(StarknetError::PageSizeTooBig));
+        }
+        if query.keys.iter().map(Vec::len).sum::<usize>() > MAX_EVENT_FILTER_KEYS {
+            return Err(anyhow!(StarknetError::TooManyKeysInFilter));
+        }
+
+        let (start_block, start_index) = match &query.continuation_token {
+            Some(token) => {
+                let (block_number, event_index) = parse_continuation_token(token)?;
+                if !(query.from_block..=query.to_block).contains(&block_number) {
+                    return Err(anyhow!(StarknetError::InvalidContinuationToken));
+                }
+                (block_number, event_index)
+            }
+            None => (query.from_block, 0),
+        };
+        let to_block = query.to_block.min(self.get_height().unwrap_or(0));
+
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+
+        // When filtering by contract, only visit the blocks that contract emitted events in.
+        let blocks: Vec<u64> = match &query.address {
+            Some(address) => get_event_blocks(&engine, address)?
+                .into_iter()
+                .filter(|block_number| (start_block..=to_block).contains(block_number))
+                .collect(),
+            None => (start_block..=to_block).collect(),
+        };
+
+        let mut events = vec![];
+        for block_number in blocks {
+            let skip = if block_number == start_block {
+                start_index
+            } else {
+                0
+            };
+            let block_events = engine.get_block_events(block_number)?.unwrap_or_default();
+            for (event_index, event) in block_events.into_iter().enumerate().skip(skip) {
+                if !query.matches(&event) {
+                    continue;
+                }
+                if events.len() as u64 == query.chunk_size {
+                    return Ok(EventsPage {
+                        events,
+                        continuation_token: Some(continuation_token(block_number, event_index)),
+                    });
+                }
+                events.push(event);
+            }
+        }
+
+        Ok(EventsPage {
+            events,
+            continuation_token: None,
+        })
+    }
+}
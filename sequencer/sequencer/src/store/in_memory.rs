@@ -1,11 +1,11 @@
-use super::{Key, StoreEngine, Value};
-use anyhow::Result;
+use super::{Key, StoreEngine, TransactionLocation, Value};
+use anyhow::{bail, Result};
 use cairo_felt::Felt252;
-use std::{collections::HashMap, fmt::Debug};
-use types::{
-    InvokeTransaction, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction,
-    TransactionReceipt,
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
 };
+use types::{EmittedEvent, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction};
 
 #[derive(Clone, Default)]
 pub struct Store {
@@ -13,7 +13,9 @@ pub struct Store {
     blocks_by_hash: HashMap<Felt252, MaybePendingBlockWithTxs>,
     blocks_by_height: HashMap<u64, MaybePendingBlockWithTxs>,
     transaction_receipts: HashMap<Felt252, MaybePendingTransactionReceipt>,
-    values: HashMap<Key, Value>,
+    transaction_locations: HashMap<Felt252, TransactionLocation>,
+    events: HashMap<u64, Vec<EmittedEvent>>,
+    values: BTreeMap<Key, Value>,
 }
 
 impl Store {
@@ -23,23 +25,17 @@ impl Store {
             blocks_by_hash: HashMap::new(),
             blocks_by_height: HashMap::new(),
             transaction_receipts: HashMap::new(),
-            values: HashMap::new(),
+            transaction_locations: HashMap::new(),
+            events: HashMap::new(),
+            values: BTreeMap::new(),
         })
     }
 }
 
 impl StoreEngine for Store {
     fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
-        match &tx {
-            Transaction::Invoke(InvokeTransaction::V1(invoke_tx)) => {
-                let _ = self
-                    .transactions
-                    .insert(invoke_tx.transaction_hash.clone(), tx);
-                Ok(())
-            }
-            // Currently only InvokeTransactionV1 are supported
-            _ => todo!(),
-        }
+        let _ = self.transactions.insert(tx.transaction_hash().clone(), tx);
+        Ok(())
     }
 
     fn get_transaction(&self, tx_hash: Felt252) -> Result<Option<Transaction>> {
@@ -49,6 +45,16 @@ impl StoreEngine for Store {
     fn add_block(&mut self, block: MaybePendingBlockWithTxs) -> Result<()> {
         match &block {
             MaybePendingBlockWithTxs::Block(block_with_txs) => {
+                for (index, tx) in block_with_txs.transactions.iter().enumerate() {
+                    let _ = self.transaction_locations.insert(
+                        tx.transaction_hash().clone(),
+                        TransactionLocation {
+                            block_number: block_with_txs.block_number,
+                            block_hash: block_with_txs.block_hash.clone(),
+                            index,
+                        },
+                    );
+                }
                 let _ = self
                     .blocks_by_hash
                     .insert(block_with_txs.block_hash.clone(), block.clone());
@@ -73,6 +79,19 @@ impl StoreEngine for Store {
         Ok(self.blocks_by_height.get(&block_height).cloned())
     }
 
+    fn get_transaction_location(&self, tx_hash: Felt252) -> Result<Option<TransactionLocation>> {
+        Ok(self.transaction_locations.get(&tx_hash).cloned())
+    }
+
+    fn add_block_events(&mut self, block_number: u64, events: Vec<EmittedEvent>) -> Result<()> {
+        let _ = self.events.insert(block_number, events);
+        Ok(())
+    }
+
+    fn get_block_events(&self, block_number: u64) -> Result<Option<Vec<EmittedEvent>>> {
+        Ok(self.events.get(&block_number).cloned())
+    }
+
     fn set_value(&mut self, key: Key, value: Value) -> Result<()> {
         let _ = self.values.insert(key, value);
         Ok(())
@@ -82,19 +101,29 @@ impl StoreEngine for Store {
         Ok(self.values.get(&key).cloned())
     }
 
+    fn get_last_value_in_range(&self, from: Key, to: Key) -> Result<Option<Value>> {
+        Ok(self
+            .values
+            .range(from..=to)
+            .next_back()
+            .map(|(_, value)| value.clone()))
+    }
+
     fn add_transaction_receipt(
         &mut self,
         transaction_receipt: MaybePendingTransactionReceipt,
     ) -> Result<()> {
         match &transaction_receipt {
-            MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(tx_receipt)) => {
+            MaybePendingTransactionReceipt::Receipt(tx_receipt) => {
                 let _ = self
                     .transaction_receipts
-                    .insert(tx_receipt.transaction_hash.clone(), transaction_receipt);
+                    .insert(tx_receipt.transaction_hash().clone(), transaction_receipt);
                 Ok(())
             }
-            // Currently only InvokeTransactionReceipts are supported
-            _ => todo!(),
+            // Pending receipts are built from the pending block by the RPC endpoint
+            MaybePendingTransactionReceipt::PendingReceipt(_) => {
+                bail!("Pending receipts are not stored")
+            }
         }
     }
 
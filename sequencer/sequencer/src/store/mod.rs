@@ -3,26 +3,55 @@ use self::rocksdb::Store as RocksDBStore;
 use self::sled::Store as SledStore;
 use anyhow::Result;
 use cairo_felt::Felt252;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
-use types::{MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction};
+use tokio::sync::broadcast;
+use types::serializable_types::FeltHex;
+use types::{
+    EmittedEvent, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction,
+    TransactionLifecycle,
+};
 
+pub mod events;
 pub mod in_memory;
 pub mod rocksdb;
 pub mod sled;
+pub mod state;
+pub mod transaction_status;
 
 pub(crate) type Key = Vec<u8>;
 pub(crate) type Value = Vec<u8>;
 
 const BLOCK_HEIGHT: &str = "height";
+
+/// Where a transaction was included in the chain.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionLocation {
+    /// The number of the block including the transaction
+    pub block_number: u64,
+    /// The hash of the block including the transaction
+    #[serde_as(as = "FeltHex")]
+    pub block_hash: Felt252,
+    /// The position of the transaction within the block
+    pub index: usize,
+}
+
 pub trait StoreEngine: Debug + Send {
     fn add_transaction(&mut self, transaction: Transaction) -> Result<()>;
     fn get_transaction(&self, tx_hash: Felt252) -> Result<Option<Transaction>>;
     fn add_block(&mut self, block: MaybePendingBlockWithTxs) -> Result<()>;
     fn get_block_by_hash(&self, block_hash: Felt252) -> Result<Option<MaybePendingBlockWithTxs>>;
     fn get_block_by_height(&self, block_height: u64) -> Result<Option<MaybePendingBlockWithTxs>>;
+    fn get_transaction_location(&self, tx_hash: Felt252) -> Result<Option<TransactionLocation>>;
+    fn add_block_events(&mut self, block_number: u64, events: Vec<EmittedEvent>) -> Result<()>;
+    fn get_block_events(&self, block_number: u64) -> Result<Option<Vec<EmittedEvent>>>;
     fn set_value(&mut self, key: Key, value: Value) -> Result<()>;
     fn get_value(&self, key: Key) -> Result<Option<Value>>;
+    /// Returns the value of the greatest key between `from` and `to`, both included, if any.
+    fn get_last_value_in_range(&self, from: Key, to: Key) -> Result<Option<Value>>;
     fn add_transaction_receipt(
         &mut self,
         transaction_receipt: MaybePendingTransactionReceipt,
@@ -33,9 +62,14 @@ pub trait StoreEngine: Debug + Send {
     ) -> Result<Option<MaybePendingTransactionReceipt>>;
 }
 
+/// How many transaction status updates are kept for subscribers that fall behind.
+const STATUS_UPDATES_CAPACITY: usize = 1_000;
+
 #[derive(Debug, Clone)]
 pub struct Store {
     engine: Arc<Mutex<dyn StoreEngine>>,
+    /// Every recorded transaction status, as it is recorded
+    status_updates: broadcast::Sender<TransactionLifecycle>,
 }
 
 #[allow(dead_code)]
@@ -47,19 +81,17 @@ pub enum EngineType {
 
 impl Store {
     pub fn new(path: &str, engine_type: EngineType) -> Result<Self> {
-        let mut store = match engine_type {
-            EngineType::RocksDB => Self {
-                engine: Arc::new(Mutex::new(
-                    RocksDBStore::new(&format!("{path}.rocksdb"))
-                        .expect("could not create rocksdb store"),
-                )),
-            },
-            EngineType::Sled => Self {
-                engine: Arc::new(Mutex::new(SledStore::new(&format!("{path}.sled"))?)),
-            },
-            EngineType::InMemory => Self {
-                engine: Arc::new(Mutex::new(InMemoryStore::new()?)),
-            },
+        let engine: Arc<Mutex<dyn StoreEngine>> = match engine_type {
+            EngineType::RocksDB => Arc::new(Mutex::new(
+                RocksDBStore::new(&format!("{path}.rocksdb"))
+                    .expect("could not create rocksdb store"),
+            )),
+            EngineType::Sled => Arc::new(Mutex::new(SledStore::new(&format!("{path}.sled"))?)),
+            EngineType::InMemory => Arc::new(Mutex::new(InMemoryStore::new()?)),
+        };
+        let mut store = Self {
+            engine,
+            status_updates: broadcast::channel(STATUS_UPDATES_CAPACITY).0,
         };
         store.init();
         Ok(store)
@@ -109,6 +141,17 @@ impl Store {
             .get_block_by_hash(block_hash)
     }
 
+    pub fn get_transaction_location(
+        &self,
+        tx_hash: Felt252,
+    ) -> Result<Option<TransactionLocation>> {
+        self.engine
+            .clone()
+            .lock()
+            .unwrap()
+            .get_transaction_location(tx_hash)
+    }
+
     pub fn set_height(&mut self, value: u64) -> Result<()> {
         self.engine
             .clone()
@@ -139,28 +182,50 @@ impl Store {
             .add_transaction_receipt(transaction_receipt)
     }
 
+    /// Returns the receipt of the given transaction. Receipts may be written before the block
+    /// including their transaction, so the block information is taken from the transaction
+    /// location index whenever it is available.
     pub fn get_transaction_receipt(
         &self,
         transaction_id: Felt252,
     ) -> Result<Option<MaybePendingTransactionReceipt>> {
-        self.engine
-            .clone()
-            .lock()
-            .unwrap()
-            .get_transaction_receipt(transaction_id)
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        let receipt = engine.get_transaction_receipt(transaction_id.clone())?;
+        let location = engine.get_transaction_location(transaction_id)?;
+
+        Ok(receipt.map(|receipt| match (receipt, location) {
+            (MaybePendingTransactionReceipt::Receipt(mut receipt), Some(location)) => {
+                receipt.set_block(location.block_hash, location.block_number);
+                MaybePendingTransactionReceipt::Receipt(receipt)
+            }
+            (receipt, _) => receipt,
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::events::{EventQuery, MAX_EVENTS_CHUNK_SIZE, MAX_EVENT_FILTER_KEYS};
+    use super::state::StateDiff;
     use super::*;
     use std::{env, fs};
-    use types::{InvokeTransaction, InvokeTransactionV1};
+    use types::{
+        BlockWithTxs, CompressedLegacyContractClass, ContractClass, DeclareTransaction,
+        DeclareTransactionReceipt, DeclareTransactionV2, InvokeTransaction,
+        InvokeTransactionReceipt, InvokeTransactionV1, LegacyEntryPointsByType, StarknetError,
+        TransactionExecutionStatus, TransactionReceipt, TransactionStage, TransactionStatus,
+    };
 
     #[test]
     fn test_in_memory_store() {
         let store = Store::new("test", EngineType::InMemory).unwrap();
         test_store_tx(store.clone());
+        test_store_block(store.clone());
+        test_store_transaction_location(store.clone());
+        test_store_events(store.clone());
+        test_store_state(store.clone());
+        test_store_transaction_status(store.clone());
         test_store_height(store);
     }
 
@@ -170,6 +235,11 @@ mod tests {
         remove_test_dbs("test.sled.");
         let store = Store::new("test", EngineType::Sled).unwrap();
         test_store_tx(store.clone());
+        test_store_block(store.clone());
+        test_store_transaction_location(store.clone());
+        test_store_events(store.clone());
+        test_store_state(store.clone());
+        test_store_transaction_status(store.clone());
         test_store_height(store);
         remove_test_dbs("test.sled.");
     }
@@ -180,6 +250,11 @@ mod tests {
         remove_test_dbs("test.rocksdb.");
         let store = Store::new("test", EngineType::RocksDB).unwrap();
         test_store_tx(store.clone());
+        test_store_block(store.clone());
+        test_store_transaction_location(store.clone());
+        test_store_events(store.clone());
+        test_store_state(store.clone());
+        test_store_transaction_status(store.clone());
         test_store_height(store.clone());
 
         // FIXME patching rocksdb weird behavior
@@ -198,6 +273,372 @@ mod tests {
         assert_eq!(Some(25u64), store.get_height());
     }
 
+    fn test_store_block(mut store: Store) {
+        let block_hash = Felt252::new(9876);
+        let block = MaybePendingBlockWithTxs::Block(BlockWithTxs {
+            block_hash: block_hash.clone(),
+            block_number: 7,
+            ..Default::default()
+        });
+        store.add_block(block).unwrap();
+
+        // Test the block can be retrieved both by height and by hash
+        let stored_by_height = store.get_block_by_height(7).unwrap().unwrap();
+        let stored_by_hash = store
+            .get_block_by_hash(block_hash.clone())
+            .unwrap()
+            .unwrap();
+        for stored_block in [stored_by_height, stored_by_hash] {
+            match stored_block {
+                MaybePendingBlockWithTxs::Block(stored_block) => {
+                    assert_eq!(block_hash, stored_block.block_hash);
+                    assert_eq!(7, stored_block.block_number);
+                }
+                MaybePendingBlockWithTxs::PendingBlock(_) => panic!("Expected a block"),
+            }
+        }
+
+        // Test unknown heights are not found
+        assert!(store.get_block_by_height(8).unwrap().is_none());
+    }
+
+    fn test_store_transaction_location(mut store: Store) {
+        let tx_hash = Felt252::new(4455);
+        let tx = new_transaction(
+            tx_hash.clone(),
+            Felt252::new(1),
+            vec![],
+            Felt252::new(0),
+            Felt252::new(1),
+            vec![],
+        );
+
+        // Store the receipt before the block including the transaction exists
+        let receipt = InvokeTransactionReceipt {
+            transaction_hash: tx_hash.clone(),
+            actual_fee: Felt252::new(1),
+            status: TransactionStatus::AcceptedOnL2,
+            execution_status: TransactionExecutionStatus::Succeeded,
+            revert_reason: None,
+            block_hash: Felt252::new(0),
+            block_number: 0,
+            messages_sent: vec![],
+            events: vec![],
+        };
+        store
+            .add_transaction_receipt(MaybePendingTransactionReceipt::Receipt(
+                TransactionReceipt::Invoke(receipt),
+            ))
+            .unwrap();
+        assert!(store
+            .get_transaction_location(tx_hash.clone())
+            .unwrap()
+            .is_none());
+
+        let block_hash = Felt252::new(5566);
+        let other_tx = new_transaction(
+            Felt252::new(3344),
+            Felt252::new(1),
+            vec![],
+            Felt252::new(0),
+            Felt252::new(1),
+            vec![],
+        );
+        let block = MaybePendingBlockWithTxs::Block(BlockWithTxs {
+            block_hash: block_hash.clone(),
+            block_number: 11,
+            transactions: vec![other_tx, tx],
+            ..Default::default()
+        });
+        store.add_block(block).unwrap();
+
+        // Test the transaction is located in the block it was included in
+        assert_eq!(
+            Some(TransactionLocation {
+                block_number: 11,
+                block_hash: block_hash.clone(),
+                index: 1,
+            }),
+            store.get_transaction_location(tx_hash.clone()).unwrap()
+        );
+
+        // Test the receipt carries the information of the block
+        match store.get_transaction_receipt(tx_hash).unwrap().unwrap() {
+            MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(receipt)) => {
+                assert_eq!(block_hash, receipt.block_hash);
+                assert_eq!(11, receipt.block_number);
+            }
+            _ => panic!("Expected an invoke transaction receipt"),
+        }
+    }
+
+    fn test_store_events(mut store: Store) {
+        store.set_height(21).unwrap();
+        let (contract_a, contract_b) = (Felt252::new(100), Felt252::new(200));
+        let (key_1, key_2) = (Felt252::new(1), Felt252::new(2));
+        store
+            .add_events(
+                20,
+                vec![
+                    new_event(contract_a.clone(), key_1.clone(), 20),
+                    new_event(contract_b.clone(), key_2.clone(), 20),
+                    new_event(contract_a.clone(), key_2.clone(), 20),
+                ],
+            )
+            .unwrap();
+        store
+            .add_events(21, vec![new_event(contract_a.clone(), key_1.clone(), 21)])
+            .unwrap();
+
+        // Test events are paged, resuming from the continuation token
+        let query = EventQuery {
+            from_block: 20,
+            to_block: 21,
+            chunk_size: 3,
+            ..Default::default()
+        };
+        let first_page = store.get_events(query.clone()).unwrap();
+        assert_eq!(3, first_page.events.len());
+        assert!(first_page.continuation_token.is_some());
+        let second_page = store
+            .get_events(EventQuery {
+                continuation_token: first_page.continuation_token,
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(1, second_page.events.len());
+        assert_eq!(21, second_page.events[0].block_number);
+        assert!(second_page.continuation_token.is_none());
+
+        // Test events are filtered by contract address and keys
+        let filtered = store
+            .get_events(EventQuery {
+                address: Some(contract_a),
+                keys: vec![vec![key_1.clone()]],
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(2, filtered.events.len());
+        assert!(filtered
+            .events
+            .iter()
+            .all(|event| event.keys == [key_1.clone()]));
+
+        // Test events outside of the block range are skipped
+        let filtered = store
+            .get_events(EventQuery {
+                to_block: 20,
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(3, filtered.events.len());
+
+        // Test the range stops at the last stored block
+        let filtered = store
+            .get_events(EventQuery {
+                to_block: u64::MAX,
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(3, filtered.events.len());
+        assert!(filtered.continuation_token.is_some());
+
+        // Test invalid queries are rejected
+        assert!(store
+            .get_events(EventQuery {
+                chunk_size: 0,
+                ..query.clone()
+            })
+            .is_err());
+        let rejected_queries = [
+            (
+                EventQuery {
+                    chunk_size: MAX_EVENTS_CHUNK_SIZE + 1,
+                    ..query.clone()
+                },
+                StarknetError::PageSizeTooBig,
+            ),
+            (
+                EventQuery {
+                    keys: vec![vec![key_2; MAX_EVENT_FILTER_KEYS + 1]],
+                    ..query.clone()
+                },
+                StarknetError::TooManyKeysInFilter,
+            ),
+            (
+                EventQuery {
+                    continuation_token: Some("invalid".to_string()),
+                    ..query
+                },
+                StarknetError::InvalidContinuationToken,
+            ),
+        ];
+        for (rejected_query, expected_error) in rejected_queries {
+            let error = store.get_events(rejected_query).unwrap_err();
+            assert_eq!(Some(&expected_error), error.downcast_ref::<StarknetError>());
+        }
+    }
+
+    fn test_store_state(mut store: Store) {
+        let contract_address = Felt252::new(300);
+        let key = Felt252::new(7);
+
+        let mut state_diff = StateDiff::default();
+        state_diff.storage.insert(
+            contract_address.clone(),
+            [(key.clone(), Felt252::new(1))].into_iter().collect(),
+        );
+        state_diff
+            .nonces
+            .insert(contract_address.clone(), Felt252::new(1));
+        state_diff
+            .class_hashes
+            .insert(contract_address.clone(), Felt252::new(1234));
+        let class = ContractClass::Legacy(CompressedLegacyContractClass {
+            program: vec![1, 2, 3],
+            entry_points_by_type: LegacyEntryPointsByType {
+                constructor: vec![],
+                external: vec![],
+                l1_handler: vec![],
+            },
+            abi: None,
+        });
+        state_diff
+            .declared_classes
+            .insert(Felt252::new(1234), class.clone());
+        store.apply_state_diff(30, state_diff).unwrap();
+
+        let mut state_diff = StateDiff::default();
+        state_diff.storage.insert(
+            contract_address.clone(),
+            [(key.clone(), Felt252::new(2))].into_iter().collect(),
+        );
+        state_diff
+            .nonces
+            .insert(contract_address.clone(), Felt252::new(2));
+        store.apply_state_diff(32, state_diff).unwrap();
+
+        // A key whose hex digits start with the ones of the other
+        let mut state_diff = StateDiff::default();
+        state_diff.storage.insert(
+            contract_address.clone(),
+            [(Felt252::new(0x70), Felt252::new(9))]
+                .into_iter()
+                .collect(),
+        );
+        store.apply_state_diff(31, state_diff).unwrap();
+
+        // Test values are not set before the block that set them
+        assert!(store
+            .get_storage_at(&contract_address, &key, 29)
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get_class_hash_at(&contract_address, 29)
+            .unwrap()
+            .is_none());
+        assert!(store.get_class(&Felt252::new(1234), 29).unwrap().is_none());
+
+        // Test declared classes are stored as they were sent
+        assert_eq!(
+            class.class_hash(),
+            store
+                .get_class(&Felt252::new(1234), 30)
+                .unwrap()
+                .unwrap()
+                .class_hash()
+        );
+
+        // Test each block sees the latest value set up to it
+        for (block_number, expected) in [(30, 1), (31, 1), (32, 2), (40, 2)] {
+            assert_eq!(
+                Some(Felt252::new(expected)),
+                store
+                    .get_storage_at(&contract_address, &key, block_number)
+                    .unwrap()
+            );
+            assert_eq!(
+                Some(Felt252::new(expected)),
+                store.get_nonce(&contract_address, block_number).unwrap()
+            );
+            assert_eq!(
+                Some(Felt252::new(1234)),
+                store
+                    .get_class_hash_at(&contract_address, block_number)
+                    .unwrap()
+            );
+        }
+    }
+
+    fn test_store_transaction_status(mut store: Store) {
+        let tx_hash = Felt252::new(6677);
+        assert!(store.get_transaction_status(&tx_hash).unwrap().is_none());
+        let mut status_updates = store.subscribe_transaction_status();
+
+        store
+            .update_transaction_status(&tx_hash, TransactionStage::Batched, |status| {
+                status.batch_digest = Some("digest".to_string())
+            })
+            .unwrap();
+        store
+            .update_transaction_status(&tx_hash, TransactionStage::Committed, |status| {
+                status.round = Some(3)
+            })
+            .unwrap();
+
+        // Test a stage older than the current one is ignored
+        store
+            .update_transaction_status(&tx_hash, TransactionStage::Received, |status| {
+                status.round = None
+            })
+            .unwrap();
+
+        // Test the details of every stage are kept
+        let status = store.get_transaction_status(&tx_hash).unwrap().unwrap();
+        assert_eq!(TransactionStage::Committed, status.stage);
+        assert_eq!(Some("digest".to_string()), status.batch_digest);
+        assert_eq!(Some(3), status.round);
+
+        // Test a final stage is never left, even for a later one
+        store
+            .update_transaction_status(&tx_hash, TransactionStage::Executed, |status| {
+                status.block_number = Some(4)
+            })
+            .unwrap();
+        store
+            .update_transaction_status(&tx_hash, TransactionStage::Rejected, |status| {
+                status.reason = Some("Invalid nonce".to_string())
+            })
+            .unwrap();
+        let status = store.get_transaction_status(&tx_hash).unwrap().unwrap();
+        assert_eq!(TransactionStage::Executed, status.stage);
+        assert_eq!(None, status.reason);
+
+        // Test subscribers are notified of the recorded stages only
+        let stages: Vec<_> = std::iter::from_fn(|| status_updates.try_recv().ok())
+            .map(|status| status.stage)
+            .collect();
+        assert_eq!(
+            vec![
+                TransactionStage::Batched,
+                TransactionStage::Committed,
+                TransactionStage::Executed
+            ],
+            stages
+        );
+    }
+
+    fn new_event(from_address: Felt252, key: Felt252, block_number: u64) -> EmittedEvent {
+        EmittedEvent {
+            from_address,
+            keys: vec![key],
+            data: vec![],
+            block_hash: Felt252::new(block_number),
+            block_number,
+            transaction_hash: Felt252::new(0),
+        }
+    }
+
     fn test_store_tx(mut store: Store) {
         let tx_hash = Felt252::new(123123);
         let tx_fee = Felt252::new(89853483);
@@ -231,6 +672,46 @@ mod tests {
         assert_eq!(tx_nonce, stored_tx_nonce);
         assert_eq!(tx_sender_address, stored_tx_sender_address);
         assert_eq!(tx_calldata, stored_tx_calldata);
+
+        // Test transactions and receipts other than invokes are stored too
+        let declare_hash = Felt252::new(456456);
+        let declare_tx = Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+            transaction_hash: declare_hash.clone(),
+            max_fee: tx_fee,
+            signature: tx_signature,
+            nonce: tx_nonce,
+            class_hash: Felt252::new(789),
+            sender_address: tx_sender_address,
+            compiled_class_hash: Felt252::new(987),
+        }));
+        store.add_transaction(declare_tx).unwrap();
+        assert!(matches!(
+            store.get_transaction(declare_hash.clone()).unwrap(),
+            Some(Transaction::Declare(DeclareTransaction::V2(_)))
+        ));
+
+        let declare_receipt = DeclareTransactionReceipt {
+            transaction_hash: declare_hash.clone(),
+            actual_fee: Felt252::new(1),
+            status: TransactionStatus::AcceptedOnL2,
+            execution_status: TransactionExecutionStatus::Succeeded,
+            revert_reason: None,
+            block_hash: Felt252::new(0),
+            block_number: 0,
+            messages_sent: vec![],
+            events: vec![],
+        };
+        store
+            .add_transaction_receipt(MaybePendingTransactionReceipt::Receipt(
+                TransactionReceipt::Declare(declare_receipt),
+            ))
+            .unwrap();
+        assert!(matches!(
+            store.get_transaction_receipt(declare_hash).unwrap(),
+            Some(MaybePendingTransactionReceipt::Receipt(
+                TransactionReceipt::Declare(_)
+            ))
+        ));
     }
 
     fn new_transaction(
@@ -1,19 +1,19 @@
-use super::{Key, StoreEngine, Value};
-use anyhow::Result;
+use super::{Key, StoreEngine, TransactionLocation, Value};
+use anyhow::{bail, Result};
 use cairo_felt::Felt252;
+use rocksdb::{Direction, IteratorMode};
 use std::fmt::Debug;
 use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
 use std::thread;
 use tracing::log::error;
-use types::{
-    InvokeTransaction, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction,
-    TransactionReceipt,
-};
+use types::{EmittedEvent, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction};
 
 #[derive(Debug)]
 enum StoreCommand {
     Put(DbSelector, Key, Value, SyncSender<Result<()>>),
     Get(DbSelector, Key, SyncSender<Result<Option<Value>>>),
+    /// Gets the value of the greatest key between the two given ones, both included
+    GetLastInRange(DbSelector, Key, Key, SyncSender<Result<Option<Value>>>),
 }
 
 #[derive(Debug)]
@@ -23,6 +23,8 @@ enum DbSelector {
     BlocksByHeight,
     Values,
     TransactionReceipts,
+    TransactionLocations,
+    Events,
 }
 
 #[derive(Clone)]
@@ -38,19 +40,25 @@ impl Store {
         let values = rocksdb::DB::open_default(format!("{path}.values.db"))?;
         let transaction_receipts =
             rocksdb::DB::open_default(format!("{path}.transaction_receipts.db"))?;
+        let transaction_locations =
+            rocksdb::DB::open_default(format!("{path}.transaction_locations.db"))?;
+        let events = rocksdb::DB::open_default(format!("{path}.events.db"))?;
         let (command_sender, command_receiver): (Sender<StoreCommand>, Receiver<StoreCommand>) =
             channel();
         thread::spawn(move || {
+            let select_db = |db_selector: DbSelector| match db_selector {
+                DbSelector::Transactions => &transactions,
+                DbSelector::BlocksByHash => &blocks_by_hash,
+                DbSelector::BlocksByHeight => &blocks_by_height,
+                DbSelector::Values => &values,
+                DbSelector::TransactionReceipts => &transaction_receipts,
+                DbSelector::TransactionLocations => &transaction_locations,
+                DbSelector::Events => &events,
+            };
             while let Ok(command) = command_receiver.recv() {
                 match command {
                     StoreCommand::Put(db_selector, id, value, reply_to) => {
-                        let db = match db_selector {
-                            DbSelector::Transactions => &transactions,
-                            DbSelector::BlocksByHash => &blocks_by_hash,
-                            DbSelector::BlocksByHeight => &blocks_by_height,
-                            DbSelector::Values => &values,
-                            DbSelector::TransactionReceipts => &transaction_receipts,
-                        };
+                        let db = select_db(db_selector);
                         let result = Ok(db
                             .put(id, value)
                             .unwrap_or_else(|e| error!("failed to write to db {}", e)));
@@ -58,19 +66,30 @@ impl Store {
                         reply_to.send(result).unwrap_or_else(|e| error!("{}", e));
                     }
                     StoreCommand::Get(db_selector, id, reply_to) => {
-                        let db = match db_selector {
-                            DbSelector::Transactions => &transactions,
-                            DbSelector::BlocksByHash => &blocks_by_hash,
-                            DbSelector::BlocksByHeight => &blocks_by_height,
-                            DbSelector::Values => &values,
-                            DbSelector::TransactionReceipts => &transaction_receipts,
-                        };
+                        let db = select_db(db_selector);
                         let result = db.get(id).unwrap_or(None);
 
                         reply_to
                             .send(Ok(result))
                             .unwrap_or_else(|e| error!("{}", e));
                     }
+                    StoreCommand::GetLastInRange(db_selector, from, to, reply_to) => {
+                        let db = select_db(db_selector);
+                        // The first key from `to` backwards is the greatest one in the range,
+                        // unless it is below `from`
+                        let result = db
+                            .iterator(IteratorMode::From(&to, Direction::Reverse))
+                            .next()
+                            .transpose()
+                            .map(|entry| {
+                                entry
+                                    .filter(|(key, _)| **key >= *from)
+                                    .map(|(_, value)| value.to_vec())
+                            })
+                            .map_err(anyhow::Error::from);
+
+                        reply_to.send(result).unwrap_or_else(|e| error!("{}", e));
+                    }
                 };
             }
         });
@@ -82,19 +101,13 @@ impl StoreEngine for Store {
     fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
         let (reply_sender, reply_receiver) = sync_channel(0);
         let tx_serialized: Vec<u8> = serde_json::to_string(&tx).unwrap().as_bytes().to_vec();
-        match tx {
-            Transaction::Invoke(InvokeTransaction::V1(invoke_tx)) => {
-                self.command_sender.send(StoreCommand::Put(
-                    DbSelector::Transactions,
-                    invoke_tx.transaction_hash.to_bytes_be(),
-                    tx_serialized,
-                    reply_sender,
-                ))?;
-                reply_receiver.recv()?
-            }
-            // Currently only InvokeTransactionV1 are supported
-            _ => todo!(),
-        }
+        self.command_sender.send(StoreCommand::Put(
+            DbSelector::Transactions,
+            tx.transaction_hash().to_bytes_be(),
+            tx_serialized,
+            reply_sender,
+        ))?;
+        reply_receiver.recv()?
     }
 
     fn get_transaction(&self, tx_hash: Felt252) -> Result<Option<Transaction>> {
@@ -123,6 +136,21 @@ impl StoreEngine for Store {
         let block_serialized: Vec<u8> = serde_json::to_string(&block).unwrap().as_bytes().to_vec();
         match block {
             MaybePendingBlockWithTxs::Block(block_with_txs) => {
+                for (index, tx) in block_with_txs.transactions.iter().enumerate() {
+                    let (reply_sender, reply_receiver) = sync_channel(0);
+                    let location = TransactionLocation {
+                        block_number: block_with_txs.block_number,
+                        block_hash: block_with_txs.block_hash.clone(),
+                        index,
+                    };
+                    self.command_sender.send(StoreCommand::Put(
+                        DbSelector::TransactionLocations,
+                        tx.transaction_hash().to_bytes_be(),
+                        serde_json::to_string(&location)?.as_bytes().to_vec(),
+                        reply_sender,
+                    ))?;
+                    reply_receiver.recv()??;
+                }
                 self.command_sender.send(StoreCommand::Put(
                     DbSelector::BlocksByHash,
                     block_with_txs.block_hash.to_bytes_be(),
@@ -171,7 +199,7 @@ impl StoreEngine for Store {
 
         self.command_sender
             .send(StoreCommand::Get(
-                DbSelector::BlocksByHash,
+                DbSelector::BlocksByHeight,
                 block_height.to_be_bytes().to_vec(),
                 reply_sender,
             ))
@@ -185,6 +213,57 @@ impl StoreEngine for Store {
         })
     }
 
+    fn get_transaction_location(&self, tx_hash: Felt252) -> Result<Option<TransactionLocation>> {
+        let (reply_sender, reply_receiver) = sync_channel(0);
+
+        self.command_sender
+            .send(StoreCommand::Get(
+                DbSelector::TransactionLocations,
+                tx_hash.to_bytes_be(),
+                reply_sender,
+            ))
+            .unwrap();
+
+        // TODO: properly handle errors
+        reply_receiver.recv()??.map_or(Ok(None), |value| {
+            Ok(Some(serde_json::from_str::<TransactionLocation>(
+                &String::from_utf8(value.to_vec())?,
+            )?))
+        })
+    }
+
+    fn add_block_events(&mut self, block_number: u64, events: Vec<EmittedEvent>) -> Result<()> {
+        let (reply_sender, reply_receiver) = sync_channel(0);
+        let events_serialized: Vec<u8> = serde_json::to_string(&events)?.as_bytes().to_vec();
+
+        self.command_sender.send(StoreCommand::Put(
+            DbSelector::Events,
+            block_number.to_be_bytes().to_vec(),
+            events_serialized,
+            reply_sender,
+        ))?;
+        reply_receiver.recv()?
+    }
+
+    fn get_block_events(&self, block_number: u64) -> Result<Option<Vec<EmittedEvent>>> {
+        let (reply_sender, reply_receiver) = sync_channel(0);
+
+        self.command_sender
+            .send(StoreCommand::Get(
+                DbSelector::Events,
+                block_number.to_be_bytes().to_vec(),
+                reply_sender,
+            ))
+            .unwrap();
+
+        // TODO: properly handle errors
+        reply_receiver.recv()??.map_or(Ok(None), |value| {
+            Ok(Some(serde_json::from_str::<Vec<EmittedEvent>>(
+                &String::from_utf8(value.to_vec())?,
+            )?))
+        })
+    }
+
     fn set_value(&mut self, key: Key, value: Value) -> Result<()> {
         let (reply_sender, reply_receiver) = sync_channel(0);
         self.command_sender.send(StoreCommand::Put(
@@ -206,6 +285,19 @@ impl StoreEngine for Store {
         reply_receiver.recv()?
     }
 
+    fn get_last_value_in_range(&self, from: Key, to: Key) -> Result<Option<Value>> {
+        let (reply_sender, reply_receiver) = sync_channel(0);
+
+        self.command_sender.send(StoreCommand::GetLastInRange(
+            DbSelector::Values,
+            from,
+            to,
+            reply_sender,
+        ))?;
+
+        reply_receiver.recv()?
+    }
+
     fn add_transaction_receipt(
         &mut self,
         transaction_receipt: MaybePendingTransactionReceipt,
@@ -216,17 +308,19 @@ impl StoreEngine for Store {
             .as_bytes()
             .to_vec();
         match transaction_receipt {
-            MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(tx_receipt)) => {
+            MaybePendingTransactionReceipt::Receipt(tx_receipt) => {
                 self.command_sender.send(StoreCommand::Put(
                     DbSelector::TransactionReceipts,
-                    tx_receipt.transaction_hash.to_bytes_be(),
+                    tx_receipt.transaction_hash().to_bytes_be(),
                     tx_receipt_serialized,
                     reply_sender,
                 ))?;
                 reply_receiver.recv()?
             }
-            // Currently only InvokeTransactionReceipts are supported
-            _ => todo!(),
+            // Pending receipts are built from the pending block by the RPC endpoint
+            MaybePendingTransactionReceipt::PendingReceipt(_) => {
+                bail!("Pending receipts are not stored")
+            }
         }
     }
 
@@ -1,12 +1,9 @@
-use super::{Key, StoreEngine, Value};
-use anyhow::Result;
+use super::{Key, StoreEngine, TransactionLocation, Value};
+use anyhow::{bail, Result};
 use cairo_felt::Felt252;
 use sled::Db;
 use std::fmt::Debug;
-use types::{
-    InvokeTransaction, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction,
-    TransactionReceipt,
-};
+use types::{EmittedEvent, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, Transaction};
 
 #[derive(Clone)]
 pub struct Store {
@@ -15,6 +12,8 @@ pub struct Store {
     blocks_by_height: Db,
     values: Db,
     transaction_receipts: Db,
+    transaction_locations: Db,
+    events: Db,
 }
 
 impl Store {
@@ -25,6 +24,8 @@ impl Store {
             blocks_by_height: sled::open(format!("{path}.blocks2.db"))?,
             values: sled::open(format!("{path}.values.db"))?,
             transaction_receipts: sled::open(format!("{path}.transaction_receipts.db"))?,
+            transaction_locations: sled::open(format!("{path}.transaction_locations.db"))?,
+            events: sled::open(format!("{path}.events.db"))?,
         })
     }
 }
@@ -32,16 +33,10 @@ impl Store {
 impl StoreEngine for Store {
     fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
         let tx_serialized: Vec<u8> = serde_json::to_string(&tx)?.as_bytes().to_vec();
-        match tx {
-            Transaction::Invoke(InvokeTransaction::V1(invoke_tx)) => {
-                let _ = self
-                    .transactions
-                    .insert(invoke_tx.transaction_hash.to_bytes_be(), tx_serialized);
-                Ok(())
-            }
-            // Currently only InvokeTransactionV1 are supported
-            _ => todo!(),
-        }
+        let _ = self
+            .transactions
+            .insert(tx.transaction_hash().to_bytes_be(), tx_serialized);
+        Ok(())
     }
 
     fn get_transaction(&self, tx_hash: Felt252) -> Result<Option<Transaction>> {
@@ -58,6 +53,17 @@ impl StoreEngine for Store {
         let block_serialized: Vec<u8> = serde_json::to_string(&block)?.as_bytes().to_vec();
         match block {
             MaybePendingBlockWithTxs::Block(block_with_txs) => {
+                for (index, tx) in block_with_txs.transactions.iter().enumerate() {
+                    let location = TransactionLocation {
+                        block_number: block_with_txs.block_number,
+                        block_hash: block_with_txs.block_hash.clone(),
+                        index,
+                    };
+                    let _ = self.transaction_locations.insert(
+                        tx.transaction_hash().to_bytes_be(),
+                        serde_json::to_string(&location)?.as_bytes().to_vec(),
+                    );
+                }
                 let _ = self.blocks_by_hash.insert(
                     block_with_txs.block_hash.to_bytes_be(),
                     block_serialized.clone(),
@@ -95,6 +101,34 @@ impl StoreEngine for Store {
             })
     }
 
+    fn get_transaction_location(&self, tx_hash: Felt252) -> Result<Option<TransactionLocation>> {
+        self.transaction_locations
+            .get(tx_hash.to_bytes_be())?
+            .map_or(Ok(None), |value| {
+                Ok(Some(serde_json::from_str::<TransactionLocation>(
+                    &String::from_utf8(value.to_vec())?,
+                )?))
+            })
+    }
+
+    fn add_block_events(&mut self, block_number: u64, events: Vec<EmittedEvent>) -> Result<()> {
+        let events_serialized: Vec<u8> = serde_json::to_string(&events)?.as_bytes().to_vec();
+        let _ = self
+            .events
+            .insert(block_number.to_be_bytes(), events_serialized);
+        Ok(())
+    }
+
+    fn get_block_events(&self, block_number: u64) -> Result<Option<Vec<EmittedEvent>>> {
+        self.events
+            .get(block_number.to_be_bytes())?
+            .map_or(Ok(None), |value| {
+                Ok(Some(serde_json::from_str::<Vec<EmittedEvent>>(
+                    &String::from_utf8(value.to_vec())?,
+                )?))
+            })
+    }
+
     fn set_value(&mut self, key: Key, value: Value) -> Result<()> {
         let _ = self.values.insert(key, value);
         Ok(())
@@ -104,6 +138,15 @@ impl StoreEngine for Store {
         Ok(self.values.get(key)?.map(|value| value.to_vec()))
     }
 
+    fn get_last_value_in_range(&self, from: Key, to: Key) -> Result<Option<Value>> {
+        Ok(self
+            .values
+            .range(from..=to)
+            .next_back()
+            .transpose()?
+            .map(|(_, value)| value.to_vec()))
+    }
+
     fn add_transaction_receipt(
         &mut self,
         transaction_receipt: MaybePendingTransactionReceipt,
@@ -112,15 +155,17 @@ impl StoreEngine for Store {
             .as_bytes()
             .to_vec();
         match transaction_receipt {
-            MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(tx_receipt)) => {
+            MaybePendingTransactionReceipt::Receipt(tx_receipt) => {
                 let _ = self.transaction_receipts.insert(
-                    tx_receipt.transaction_hash.to_bytes_be(),
+                    tx_receipt.transaction_hash().to_bytes_be(),
                     tx_receipt_serialized,
                 );
                 Ok(())
             }
-            // Currently only InvokeTransactionReceipts are supported
-            _ => todo!(),
+            // Pending receipts are built from the pending block by the RPC endpoint
+            MaybePendingTransactionReceipt::PendingReceipt(_) => {
+                bail!("Pending receipts are not stored")
+            }
         }
     }
 
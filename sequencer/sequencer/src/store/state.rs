@@ -0,0 +1,197 @@
+use super::{Key, Store, StoreEngine};
+use anyhow::Result;
+use cairo_felt::Felt252;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use types::ContractClass;
+
+const STORAGE: &str = "storage";
+const NONCE: &str = "nonce";
+const CLASS_HASH: &str = "class_hash";
+const CLASS: &str = "class";
+
+/// Changes to the contracts state, keyed by contract address.
+#[derive(Debug, Clone, Default)]
+pub struct StateDiff {
+    /// New values of the storage slots, keyed by contract address and storage key
+    pub storage: HashMap<Felt252, HashMap<Felt252, Felt252>>,
+    /// New nonces
+    pub nonces: HashMap<Felt252, Felt252>,
+    /// New class hashes, either of deployed contracts or of replaced classes
+    pub class_hashes: HashMap<Felt252, Felt252>,
+    /// Newly declared classes, keyed by class hash
+    pub declared_classes: HashMap<Felt252, ContractClass>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+            && self.nonces.is_empty()
+            && self.class_hashes.is_empty()
+            && self.declared_classes.is_empty()
+    }
+
+    /// Applies the changes of a later diff on top of these ones.
+    pub fn extend(&mut self, other: StateDiff) {
+        for (contract_address, storage) in other.storage {
+            self.storage
+                .entry(contract_address)
+                .or_default()
+                .extend(storage);
+        }
+        self.nonces.extend(other.nonces);
+        self.class_hashes.extend(other.class_hashes);
+        self.declared_classes.extend(other.declared_classes);
+    }
+}
+
+/// A class along with the block it was declared in. Classes can't be declared twice, so they
+/// have a single version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeclaredClass {
+    block_number: u64,
+    class: ContractClass,
+}
+
+fn storage_key(contract_address: &Felt252, key: &Felt252) -> Key {
+    format!(
+        "{STORAGE}.{}.{}",
+        contract_address.to_str_radix(16),
+        key.to_str_radix(16)
+    )
+    .into_bytes()
+}
+
+fn nonce_key(contract_address: &Felt252) -> Key {
+    format!("{NONCE}.{}", contract_address.to_str_radix(16)).into_bytes()
+}
+
+fn class_hash_key(contract_address: &Felt252) -> Key {
+    format!("{CLASS_HASH}.{}", contract_address.to_str_radix(16)).into_bytes()
+}
+
+fn class_key(class_hash: &Felt252) -> Key {
+    format!("{CLASS}.{}", class_hash.to_str_radix(16)).into_bytes()
+}
+
+/// Key of the value set in the given block. Versions of the same value are stored next to each
+/// other in block order, as the block number is appended in big endian, after a separator that
+/// isn't a hex digit so the versions of other keys can't be mixed in.
+fn version_key(key: &Key, block_number: u64) -> Key {
+    let mut version_key = key.clone();
+    version_key.push(b'@');
+    version_key.extend(block_number.to_be_bytes());
+    version_key
+}
+
+/// Returns the value as it was at the end of the given block, if it was ever set by then.
+fn get_value_at(engine: &dyn StoreEngine, key: Key, block_number: u64) -> Result<Option<Felt252>> {
+    Ok(engine
+        .get_last_value_in_range(version_key(&key, 0), version_key(&key, block_number))?
+        .map(|value| Felt252::from_bytes_be(&value)))
+}
+
+fn set_value_at(
+    engine: &mut dyn StoreEngine,
+    key: Key,
+    block_number: u64,
+    value: Felt252,
+) -> Result<()> {
+    engine.set_value(version_key(&key, block_number), value.to_bytes_be())
+}
+
+impl Store {
+    /// Stores the state changes made by the given block. Values are versioned, with an entry per
+    /// block that changed them, so previous blocks still see the values they had.
+    pub fn apply_state_diff(&mut self, block_number: u64, state_diff: StateDiff) -> Result<()> {
+        let engine = self.engine.clone();
+        let mut engine = engine.lock().unwrap();
+
+        for (contract_address, storage) in state_diff.storage {
+            for (key, value) in storage {
+                set_value_at(
+                    &mut engine,
+                    storage_key(&contract_address, &key),
+                    block_number,
+                    value,
+                )?;
+            }
+        }
+        for (contract_address, nonce) in state_diff.nonces {
+            set_value_at(
+                &mut engine,
+                nonce_key(&contract_address),
+                block_number,
+                nonce,
+            )?;
+        }
+        for (contract_address, class_hash) in state_diff.class_hashes {
+            set_value_at(
+                &mut engine,
+                class_hash_key(&contract_address),
+                block_number,
+                class_hash,
+            )?;
+        }
+        for (class_hash, class) in state_diff.declared_classes {
+            engine.set_value(
+                class_key(&class_hash),
+                serde_json::to_vec(&DeclaredClass {
+                    block_number,
+                    class,
+                })?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a storage slot of a contract at the given block.
+    pub fn get_storage_at(
+        &self,
+        contract_address: &Felt252,
+        key: &Felt252,
+        block_number: u64,
+    ) -> Result<Option<Felt252>> {
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        get_value_at(&engine, storage_key(contract_address, key), block_number)
+    }
+
+    /// Returns the nonce of a contract at the given block.
+    pub fn get_nonce(
+        &self,
+        contract_address: &Felt252,
+        block_number: u64,
+    ) -> Result<Option<Felt252>> {
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        get_value_at(&engine, nonce_key(contract_address), block_number)
+    }
+
+    /// Returns the class hash of a contract at the given block, if it was deployed by then.
+    pub fn get_class_hash_at(
+        &self,
+        contract_address: &Felt252,
+        block_number: u64,
+    ) -> Result<Option<Felt252>> {
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        get_value_at(&engine, class_hash_key(contract_address), block_number)
+    }
+
+    /// Returns the class declared under the given hash, if it was declared by the given block.
+    pub fn get_class(
+        &self,
+        class_hash: &Felt252,
+        block_number: u64,
+    ) -> Result<Option<ContractClass>> {
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        let declared_class: Option<DeclaredClass> = engine
+            .get_value(class_key(class_hash))?
+            .map_or(Ok(None), |value| serde_json::from_slice(&value).map(Some))?;
+        Ok(declared_class
+            .filter(|declared_class| declared_class.block_number <= block_number)
+            .map(|declared_class| declared_class.class))
+    }
+}
@@ -0,0 +1,68 @@
+use super::{Key, Store, StoreEngine};
+use anyhow::Result;
+use cairo_felt::Felt252;
+use tokio::sync::broadcast;
+use types::{TransactionLifecycle, TransactionStage};
+
+const TRANSACTION_STATUS: &str = "transaction_status";
+
+fn transaction_status_key(transaction_hash: &Felt252) -> Key {
+    format!("{TRANSACTION_STATUS}.{}", transaction_hash.to_str_radix(16)).into_bytes()
+}
+
+fn get_status(
+    engine: &dyn StoreEngine,
+    transaction_hash: &Felt252,
+) -> Result<Option<TransactionLifecycle>> {
+    engine
+        .get_value(transaction_status_key(transaction_hash))?
+        .map_or(Ok(None), |value| Ok(Some(serde_json::from_slice(&value)?)))
+}
+
+impl Store {
+    /// Moves the transaction to the given stage, letting `update` record the details of it.
+    /// What was recorded in the previous stages is kept. Stages older than the current one are
+    /// ignored, as the components reporting them run concurrently, and so is every stage once
+    /// the transaction reached a final one, as copies of it may still be rejected. Recorded
+    /// stages are sent to the subscribers.
+    pub fn update_transaction_status(
+        &mut self,
+        transaction_hash: &Felt252,
+        stage: TransactionStage,
+        update: impl FnOnce(&mut TransactionLifecycle),
+    ) -> Result<()> {
+        let engine = self.engine.clone();
+        let mut engine = engine.lock().unwrap();
+
+        let mut status = get_status(&engine, transaction_hash)?
+            .unwrap_or_else(|| TransactionLifecycle::new(transaction_hash.clone()));
+        if status.stage.is_final() || stage < status.stage {
+            return Ok(());
+        }
+        status.stage = stage;
+        update(&mut status);
+
+        engine.set_value(
+            transaction_status_key(transaction_hash),
+            serde_json::to_vec(&status)?,
+        )?;
+        // Sending only fails when nobody is subscribed
+        _ = self.status_updates.send(status);
+        Ok(())
+    }
+
+    /// Returns a receiver of every transaction status recorded from now on, as it is recorded.
+    pub fn subscribe_transaction_status(&self) -> broadcast::Receiver<TransactionLifecycle> {
+        self.status_updates.subscribe()
+    }
+
+    /// Returns the lifecycle of the given transaction, if this node ever heard of it.
+    pub fn get_transaction_status(
+        &self,
+        transaction_hash: &Felt252,
+    ) -> Result<Option<TransactionLifecycle>> {
+        let engine = self.engine.clone();
+        let engine = engine.lock().unwrap();
+        get_status(&engine, transaction_hash)
+    }
+}
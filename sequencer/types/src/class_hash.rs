@@ -0,0 +1,52 @@
+use crate::{FlattenedSierraClass, SierraEntryPoint};
+use cairo_felt::Felt252;
+use sha3::{Digest, Keccak256};
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+/// Version of the Sierra classes, hashed in front of their contents.
+const SIERRA_CLASS_VERSION: &[u8] = b"CONTRACT_CLASS_V0.1.0";
+
+/// Converts a felt to the field element the Starknet crypto primitives work with.
+fn to_field_element(felt: &Felt252) -> FieldElement {
+    let bytes = felt.to_bytes_be();
+    let mut buffer = [0u8; 32];
+    buffer[32 - bytes.len()..].copy_from_slice(&bytes);
+    FieldElement::from_bytes_be(&buffer).expect("Felts are field elements")
+}
+
+/// Keccak256 of the bytes, truncated to the 250 bits that fit in a felt.
+fn starknet_keccak(bytes: &[u8]) -> FieldElement {
+    let mut hash: [u8; 32] = Keccak256::digest(bytes).into();
+    hash[0] &= 0x03;
+    FieldElement::from_bytes_be(&hash).expect("250 bits fit in a field element")
+}
+
+/// Hashes the selectors of the entry points along with the functions they point to.
+fn entry_points_hash(entry_points: &[SierraEntryPoint]) -> FieldElement {
+    let elements: Vec<FieldElement> = entry_points
+        .iter()
+        .flat_map(|entry_point| {
+            [
+                to_field_element(&entry_point.selector),
+                FieldElement::from(entry_point.function_idx),
+            ]
+        })
+        .collect();
+    poseidon_hash_many(&elements)
+}
+
+/// Returns the hash Starknet declares the Sierra class under, which commits to its entry points,
+/// its ABI and its program.
+pub(crate) fn sierra_class_hash(class: &FlattenedSierraClass) -> Felt252 {
+    let program: Vec<FieldElement> = class.sierra_program.iter().map(to_field_element).collect();
+    let hash = poseidon_hash_many(&[
+        FieldElement::from_byte_slice_be(SIERRA_CLASS_VERSION)
+            .expect("Short strings fit in a felt"),
+        entry_points_hash(&class.entry_points_by_type.external),
+        entry_points_hash(&class.entry_points_by_type.l1_handler),
+        entry_points_hash(&class.entry_points_by_type.constructor),
+        starknet_keccak(class.abi.as_bytes()),
+        poseidon_hash_many(&program),
+    ]);
+    Felt252::from_bytes_be(&hash.to_bytes_be())
+}
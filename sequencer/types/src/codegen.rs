@@ -244,6 +244,10 @@ pub struct DeclareTransactionReceipt {
     pub actual_fee: Felt252,
     /// Status
     pub status: TransactionStatus,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Block hash
     pub block_hash: Felt252,
     /// Block number
@@ -336,6 +340,10 @@ pub struct DeployAccountTransactionReceipt {
     pub actual_fee: Felt252,
     /// Status
     pub status: TransactionStatus,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Block hash
     pub block_hash: Felt252,
     /// Block number
@@ -570,6 +578,10 @@ pub struct InvokeTransactionReceipt {
     pub actual_fee: Felt252,
     /// Status
     pub status: TransactionStatus,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Block hash
     pub block_hash: Felt252,
     /// Block number
@@ -624,14 +636,174 @@ pub struct InvokeTransactionV1 {
 // TODO move this code to the uppermost Transaction enum and adapt for different
 // transaction types.
 impl InvokeTransactionV1 {
-    fn calculate_hash(&self) -> u64 {
+    /// Hashes the transaction along with the id of the chain it is meant for, so it can't be
+    /// replayed in other chains.
+    pub fn calculate_hash(&self, chain_id: &Felt252) -> Felt252 {
         let mut s = DefaultHasher::new();
         self.hash(&mut s);
-        s.finish()
+        chain_id.hash(&mut s);
+        Felt252::new(s.finish())
+    }
+
+    pub fn from_broadcasted(
+        broadcasted_tx: BroadcastedInvokeTransactionV1,
+        chain_id: &Felt252,
+    ) -> Self {
+        let mut invoke_tx_v1 = InvokeTransactionV1 {
+            transaction_hash: Felt252::new(0), //Temporary hash
+            max_fee: broadcasted_tx.max_fee,
+            signature: broadcasted_tx.signature,
+            nonce: broadcasted_tx.nonce,
+            sender_address: broadcasted_tx.sender_address,
+            calldata: broadcasted_tx.calldata,
+        };
+        invoke_tx_v1.transaction_hash = invoke_tx_v1.calculate_hash(chain_id);
+        invoke_tx_v1
+    }
+}
+
+impl DeclareTransactionV1 {
+    /// Hashes the transaction along with the id of the chain it is meant for, so it can't be
+    /// replayed in other chains. The signature isn't hashed, as the sender account checks it
+    /// signs the hash.
+    pub fn calculate_hash(&self, chain_id: &Felt252) -> Felt252 {
+        let mut s = DefaultHasher::new();
+        "DECLARE_V1".hash(&mut s);
+        self.max_fee.hash(&mut s);
+        self.nonce.hash(&mut s);
+        self.class_hash.hash(&mut s);
+        self.sender_address.hash(&mut s);
+        chain_id.hash(&mut s);
+        Felt252::new(s.finish())
+    }
+
+    pub fn from_broadcasted(
+        broadcasted_tx: BroadcastedDeclareTransactionV1,
+        chain_id: &Felt252,
+    ) -> Self {
+        let class_hash =
+            ContractClass::Legacy(broadcasted_tx.contract_class.as_ref().clone()).class_hash();
+        let mut declare_tx_v1 = DeclareTransactionV1 {
+            transaction_hash: Felt252::new(0), //Temporary hash
+            max_fee: broadcasted_tx.max_fee,
+            signature: broadcasted_tx.signature,
+            nonce: broadcasted_tx.nonce,
+            class_hash,
+            sender_address: broadcasted_tx.sender_address,
+        };
+        declare_tx_v1.transaction_hash = declare_tx_v1.calculate_hash(chain_id);
+        declare_tx_v1
+    }
+}
+
+impl DeclareTransactionV2 {
+    /// Hashes the transaction along with the id of the chain it is meant for, so it can't be
+    /// replayed in other chains. The signature isn't hashed, as the sender account checks it
+    /// signs the hash.
+    pub fn calculate_hash(&self, chain_id: &Felt252) -> Felt252 {
+        let mut s = DefaultHasher::new();
+        "DECLARE_V2".hash(&mut s);
+        self.max_fee.hash(&mut s);
+        self.nonce.hash(&mut s);
+        self.class_hash.hash(&mut s);
+        self.sender_address.hash(&mut s);
+        self.compiled_class_hash.hash(&mut s);
+        chain_id.hash(&mut s);
+        Felt252::new(s.finish())
+    }
+
+    pub fn from_broadcasted(
+        broadcasted_tx: BroadcastedDeclareTransactionV2,
+        chain_id: &Felt252,
+    ) -> Self {
+        let class_hash =
+            ContractClass::Sierra(broadcasted_tx.contract_class.as_ref().clone()).class_hash();
+        let mut declare_tx_v2 = DeclareTransactionV2 {
+            transaction_hash: Felt252::new(0), //Temporary hash
+            max_fee: broadcasted_tx.max_fee,
+            signature: broadcasted_tx.signature,
+            nonce: broadcasted_tx.nonce,
+            class_hash,
+            sender_address: broadcasted_tx.sender_address,
+            compiled_class_hash: broadcasted_tx.compiled_class_hash,
+        };
+        declare_tx_v2.transaction_hash = declare_tx_v2.calculate_hash(chain_id);
+        declare_tx_v2
+    }
+}
+
+impl DeployAccountTransaction {
+    /// Hashes the transaction along with the id of the chain it is meant for, so it can't be
+    /// replayed in other chains. The signature isn't hashed, as the account being deployed checks
+    /// it signs the hash.
+    pub fn calculate_hash(&self, chain_id: &Felt252) -> Felt252 {
+        let mut s = DefaultHasher::new();
+        "DEPLOY_ACCOUNT".hash(&mut s);
+        self.max_fee.hash(&mut s);
+        self.nonce.hash(&mut s);
+        self.contract_address_salt.hash(&mut s);
+        self.constructor_calldata.hash(&mut s);
+        self.class_hash.hash(&mut s);
+        chain_id.hash(&mut s);
+        Felt252::new(s.finish())
+    }
+
+    pub fn from_broadcasted(
+        broadcasted_tx: BroadcastedDeployAccountTransaction,
+        chain_id: &Felt252,
+    ) -> Self {
+        let mut deploy_account_tx = DeployAccountTransaction {
+            transaction_hash: Felt252::new(0), //Temporary hash
+            max_fee: broadcasted_tx.max_fee,
+            signature: broadcasted_tx.signature,
+            nonce: broadcasted_tx.nonce,
+            contract_address_salt: broadcasted_tx.contract_address_salt,
+            constructor_calldata: broadcasted_tx.constructor_calldata,
+            class_hash: broadcasted_tx.class_hash,
+        };
+        deploy_account_tx.transaction_hash = deploy_account_tx.calculate_hash(chain_id);
+        deploy_account_tx
+    }
+}
+
+impl ContractClass {
+    /// Returns the hash the class is declared under. Sierra classes are hashed the way Starknet
+    /// does, so clients can tell the hash of the classes they declare. Legacy classes aren't, so
+    /// they can't be declared.
+    pub fn class_hash(&self) -> Felt252 {
+        match self {
+            ContractClass::Sierra(class) => crate::class_hash::sierra_class_hash(class),
+            // TODO: This is quick and dirty hashing, like the one of the transactions
+            ContractClass::Legacy(_) => {
+                let mut s = DefaultHasher::new();
+                serde_json::to_string(self).unwrap().hash(&mut s);
+                Felt252::new(s.finish())
+            }
+        }
     }
 }
 
 impl Transaction {
+    /// Returns the hash identifying the transaction, whatever its type.
+    pub fn transaction_hash(&self) -> &Felt252 {
+        match self {
+            Transaction::Invoke(InvokeTransaction::V0(tx)) => &tx.transaction_hash,
+            Transaction::Invoke(InvokeTransaction::V1(tx)) => &tx.transaction_hash,
+            Transaction::L1Handler(tx) => &tx.transaction_hash,
+            Transaction::Declare(DeclareTransaction::V1(tx)) => &tx.transaction_hash,
+            Transaction::Declare(DeclareTransaction::V2(tx)) => &tx.transaction_hash,
+            Transaction::Deploy(tx) => &tx.transaction_hash,
+            Transaction::DeployAccount(tx) => &tx.transaction_hash,
+        }
+    }
+
+    /// Same as [Transaction::from_bytes], but returns `None` when the bytes are not a valid
+    /// transaction instead of panicking.
+    pub fn try_from_bytes(bytes: &[u8]) -> Option<Transaction> {
+        let tx_bytes: Vec<u8> = bytes.iter().take_while(|v| *v != &0).copied().collect();
+        serde_json::from_slice(&tx_bytes).ok()
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Transaction {
         let tx_string =
             String::from_utf8(bytes.iter().take_while(|v| *v != &0).copied().collect()).unwrap();
@@ -646,7 +818,7 @@ impl Transaction {
     ///
     /// A vector of bytes representing the transaction.
     /// if `send_fib` is true, then the execution represents fib(), otherwise it represents fact()
-    pub fn new_invoke(nonce: u64, calldata: Vec<Felt252>) -> Transaction {
+    pub fn new_invoke(nonce: u64, calldata: Vec<Felt252>, chain_id: &Felt252) -> Transaction {
         // TODO: these are default values, need to be changed
         let mut invoke_tx_v1 = InvokeTransactionV1 {
             transaction_hash: Felt252::new(0), //Temporary hash
@@ -656,7 +828,7 @@ impl Transaction {
             sender_address: Felt252::new(91232018),
             calldata,
         };
-        invoke_tx_v1.transaction_hash = Felt252::new(invoke_tx_v1.calculate_hash());
+        invoke_tx_v1.transaction_hash = invoke_tx_v1.calculate_hash(chain_id);
         Transaction::Invoke(InvokeTransaction::V1(invoke_tx_v1))
     }
 
@@ -664,12 +836,34 @@ impl Transaction {
         let starknet_transaction_str = serde_json::to_string(self).unwrap();
         starknet_transaction_str.as_bytes().to_owned()
     }
+
+    /// Same as [Transaction::as_bytes], followed by the class a declare transaction declares. The
+    /// class is separated by a zero byte, so [Transaction::from_bytes] still reads the
+    /// transaction alone.
+    pub fn declare_as_bytes(&self, contract_class: &ContractClass) -> Vec<u8> {
+        let mut bytes = self.as_bytes();
+        bytes.push(0);
+        bytes.extend(serde_json::to_vec(contract_class).unwrap());
+        bytes
+    }
+
+    /// Returns the class sent along with a transaction by [Transaction::declare_as_bytes], if
+    /// there is one.
+    pub fn contract_class_from_bytes(bytes: &[u8]) -> Option<ContractClass> {
+        let separator = bytes.iter().position(|v| *v == 0)?;
+        let class_bytes: Vec<u8> = bytes[separator + 1..]
+            .iter()
+            .take_while(|v| *v != &0)
+            .copied()
+            .collect();
+        serde_json::from_slice(&class_bytes).ok()
+    }
 }
 
+/// The signature isn't hashed, as it signs the hash.
 impl Hash for InvokeTransactionV1 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.max_fee.hash(state);
-        self.signature.hash(state);
         self.nonce.hash(state);
         self.sender_address.hash(state);
         self.calldata.hash(state);
@@ -911,6 +1105,10 @@ pub struct PendingDeclareTransactionReceipt {
     pub transaction_hash: Felt252,
     /// The fee that was charged by the sequencer
     pub actual_fee: Felt252,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Messages sent
     pub messages_sent: Vec<MsgToL1>,
     /// The events emitted as part of this transaction
@@ -924,6 +1122,10 @@ pub struct PendingDeployAccountTransactionReceipt {
     pub transaction_hash: Felt252,
     /// The fee that was charged by the sequencer
     pub actual_fee: Felt252,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Messages sent
     pub messages_sent: Vec<MsgToL1>,
     /// The events emitted as part of this transaction
@@ -952,6 +1154,10 @@ pub struct PendingInvokeTransactionReceipt {
     pub transaction_hash: Felt252,
     /// The fee that was charged by the sequencer
     pub actual_fee: Felt252,
+    /// Execution status
+    pub execution_status: TransactionExecutionStatus,
+    /// Why the execution was reverted, if it was
+    pub revert_reason: Option<String>,
     /// Messages sent
     pub messages_sent: Vec<MsgToL1>,
     /// The events emitted as part of this transaction
@@ -1031,6 +1237,9 @@ pub enum StarknetError {
     /// Contract not found
     #[error("Contract not found")]
     ContractNotFound,
+    /// Invalid message selector
+    #[error("Invalid message selector")]
+    InvalidMessageSelector,
     /// Block not found
     #[error("Block not found")]
     BlockNotFound,
@@ -1143,6 +1352,20 @@ pub struct SyncStatus {
     pub highest_block_num: u64,
 }
 
+/// Transaction execution status.
+///
+/// Whether the execution of a transaction included in a block succeeded or was reverted.
+///
+/// Receipts that don't tell are of transactions that succeeded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionExecutionStatus {
+    #[default]
+    #[serde(rename = "SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "REVERTED")]
+    Reverted,
+}
+
 /// Transaction status.
 ///
 /// The status of the transaction.
@@ -1864,6 +2087,9 @@ impl Serialize for DeclareTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
             pub status: &'a TransactionStatus,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: &'a Felt252,
             pub block_number: &'a u64,
@@ -1876,6 +2102,8 @@ impl Serialize for DeclareTransactionReceipt {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
             status: &self.status,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             block_hash: &self.block_hash,
             block_number: &self.block_number,
             messages_sent: &self.messages_sent,
@@ -1899,6 +2127,10 @@ impl<'de> Deserialize<'de> for DeclareTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
             pub status: TransactionStatus,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: Felt252,
             pub block_number: u64,
@@ -1918,6 +2150,8 @@ impl<'de> Deserialize<'de> for DeclareTransactionReceipt {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
             status: tagged.status,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             block_hash: tagged.block_hash,
             block_number: tagged.block_number,
             messages_sent: tagged.messages_sent,
@@ -2209,6 +2443,9 @@ impl Serialize for DeployAccountTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
             pub status: &'a TransactionStatus,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: &'a Felt252,
             pub block_number: &'a u64,
@@ -2223,6 +2460,8 @@ impl Serialize for DeployAccountTransactionReceipt {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
             status: &self.status,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             block_hash: &self.block_hash,
             block_number: &self.block_number,
             messages_sent: &self.messages_sent,
@@ -2246,6 +2485,10 @@ impl<'de> Deserialize<'de> for DeployAccountTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
             pub status: TransactionStatus,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: Felt252,
             pub block_number: u64,
@@ -2269,6 +2512,8 @@ impl<'de> Deserialize<'de> for DeployAccountTransactionReceipt {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
             status: tagged.status,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             block_hash: tagged.block_hash,
             block_number: tagged.block_number,
             messages_sent: tagged.messages_sent,
@@ -2437,6 +2682,9 @@ impl Serialize for InvokeTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
             pub status: &'a TransactionStatus,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: &'a Felt252,
             pub block_number: &'a u64,
@@ -2449,6 +2697,8 @@ impl Serialize for InvokeTransactionReceipt {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
             status: &self.status,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             block_hash: &self.block_hash,
             block_number: &self.block_number,
             messages_sent: &self.messages_sent,
@@ -2472,6 +2722,10 @@ impl<'de> Deserialize<'de> for InvokeTransactionReceipt {
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
             pub status: TransactionStatus,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde_as(as = "FeltHex")]
             pub block_hash: Felt252,
             pub block_number: u64,
@@ -2491,6 +2745,8 @@ impl<'de> Deserialize<'de> for InvokeTransactionReceipt {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
             status: tagged.status,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             block_hash: tagged.block_hash,
             block_number: tagged.block_number,
             messages_sent: tagged.messages_sent,
@@ -2836,6 +3092,9 @@ impl Serialize for PendingDeclareTransactionReceipt {
             pub transaction_hash: &'a Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             pub r#type: &'a str,
             pub messages_sent: &'a [MsgToL1],
             pub events: &'a [Event],
@@ -2844,6 +3103,8 @@ impl Serialize for PendingDeclareTransactionReceipt {
         let tagged = Tagged {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             r#type: "DECLARE",
             messages_sent: &self.messages_sent,
             events: &self.events,
@@ -2863,6 +3124,10 @@ impl<'de> Deserialize<'de> for PendingDeclareTransactionReceipt {
             pub transaction_hash: Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde(skip_serializing_if = "Option::is_none")]
             pub r#type: Option<String>,
             pub messages_sent: Vec<MsgToL1>,
@@ -2880,6 +3145,8 @@ impl<'de> Deserialize<'de> for PendingDeclareTransactionReceipt {
         Ok(Self {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             messages_sent: tagged.messages_sent,
             events: tagged.events,
         })
@@ -2895,6 +3162,9 @@ impl Serialize for PendingDeployAccountTransactionReceipt {
             pub transaction_hash: &'a Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             pub r#type: &'a str,
             pub messages_sent: &'a [MsgToL1],
             pub events: &'a [Event],
@@ -2903,6 +3173,8 @@ impl Serialize for PendingDeployAccountTransactionReceipt {
         let tagged = Tagged {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             r#type: "DEPLOY_ACCOUNT",
             messages_sent: &self.messages_sent,
             events: &self.events,
@@ -2922,6 +3194,10 @@ impl<'de> Deserialize<'de> for PendingDeployAccountTransactionReceipt {
             pub transaction_hash: Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde(skip_serializing_if = "Option::is_none")]
             pub r#type: Option<String>,
             pub messages_sent: Vec<MsgToL1>,
@@ -2939,6 +3215,8 @@ impl<'de> Deserialize<'de> for PendingDeployAccountTransactionReceipt {
         Ok(Self {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             messages_sent: tagged.messages_sent,
             events: tagged.events,
         })
@@ -3019,6 +3297,9 @@ impl Serialize for PendingInvokeTransactionReceipt {
             pub transaction_hash: &'a Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: &'a Felt252,
+            pub execution_status: &'a TransactionExecutionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revert_reason: &'a Option<String>,
             pub r#type: &'a str,
             pub messages_sent: &'a [MsgToL1],
             pub events: &'a [Event],
@@ -3027,6 +3308,8 @@ impl Serialize for PendingInvokeTransactionReceipt {
         let tagged = Tagged {
             transaction_hash: &self.transaction_hash,
             actual_fee: &self.actual_fee,
+            execution_status: &self.execution_status,
+            revert_reason: &self.revert_reason,
             r#type: "INVOKE",
             messages_sent: &self.messages_sent,
             events: &self.events,
@@ -3046,6 +3329,10 @@ impl<'de> Deserialize<'de> for PendingInvokeTransactionReceipt {
             pub transaction_hash: Felt252,
             #[serde_as(as = "FeltHex")]
             pub actual_fee: Felt252,
+            #[serde(default)]
+            pub execution_status: TransactionExecutionStatus,
+            #[serde(default)]
+            pub revert_reason: Option<String>,
             #[serde(skip_serializing_if = "Option::is_none")]
             pub r#type: Option<String>,
             pub messages_sent: Vec<MsgToL1>,
@@ -3063,6 +3350,8 @@ impl<'de> Deserialize<'de> for PendingInvokeTransactionReceipt {
         Ok(Self {
             transaction_hash: tagged.transaction_hash,
             actual_fee: tagged.actual_fee,
+            execution_status: tagged.execution_status,
+            revert_reason: tagged.revert_reason,
             messages_sent: tagged.messages_sent,
             events: tagged.events,
         })
@@ -1,9 +1,11 @@
 use cairo_felt::Felt252;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use serde_with::serde_as;
+use std::str::FromStr;
 
 // TODO: better namespacing of exports?
+mod class_hash;
 mod codegen;
 pub mod serializable_types;
 
@@ -24,7 +26,7 @@ pub use codegen::{
     PendingDeployTransactionReceipt, PendingInvokeTransactionReceipt,
     PendingL1HandlerTransactionReceipt, PendingStateUpdate, ReplacedClassItem, ResultPageRequest,
     SierraEntryPoint, StarknetError, StateDiff, StateUpdate, StorageEntry, SyncStatus,
-    TransactionStatus,
+    TransactionExecutionStatus, TransactionStatus,
 };
 
 use serializable_types::FeltHex;
@@ -57,6 +59,100 @@ pub enum MaybePendingStateUpdate {
     PendingUpdate(PendingStateUpdate),
 }
 
+impl From<BlockWithTxs> for BlockWithTxHashes {
+    fn from(block: BlockWithTxs) -> Self {
+        Self {
+            status: block.status,
+            block_hash: block.block_hash,
+            parent_hash: block.parent_hash,
+            block_number: block.block_number,
+            new_root: block.new_root,
+            timestamp: block.timestamp as u64,
+            sequencer_address: block.sequencer_address,
+            transactions: block
+                .transactions
+                .iter()
+                .map(|tx| tx.transaction_hash().clone())
+                .collect(),
+        }
+    }
+}
+
+impl From<PendingBlockWithTxs> for PendingBlockWithTxHashes {
+    fn from(block: PendingBlockWithTxs) -> Self {
+        Self {
+            transactions: block
+                .transactions
+                .iter()
+                .map(|tx| tx.transaction_hash().clone())
+                .collect(),
+            timestamp: block.timestamp,
+            sequencer_address: block.sequencer_address,
+            parent_hash: block.parent_hash,
+        }
+    }
+}
+
+impl From<MaybePendingBlockWithTxs> for MaybePendingBlockWithTxHashes {
+    fn from(block: MaybePendingBlockWithTxs) -> Self {
+        match block {
+            MaybePendingBlockWithTxs::Block(block) => Self::Block(block.into()),
+            MaybePendingBlockWithTxs::PendingBlock(block) => Self::PendingBlock(block.into()),
+        }
+    }
+}
+
+/// Chain ids are short strings, which fit in a felt up to this many ASCII characters.
+pub const MAX_CHAIN_ID_LENGTH: usize = 31;
+
+/// Identifies a chain, so transactions meant for one chain are not valid in the others. It is
+/// written as a short string, like `SN_KRAKEN`, and longer ids are rejected when parsed so two
+/// chains can't end up with the same felt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ChainId(String);
+
+impl Default for ChainId {
+    fn default() -> Self {
+        Self("SN_KRAKEN".to_string())
+    }
+}
+
+impl TryFrom<String> for ChainId {
+    type Error = String;
+
+    fn try_from(chain_id: String) -> Result<Self, Self::Error> {
+        if !chain_id.is_ascii() || chain_id.len() > MAX_CHAIN_ID_LENGTH {
+            return Err(format!(
+                "Invalid chain id {chain_id}: expected up to {} ASCII characters",
+                MAX_CHAIN_ID_LENGTH
+            ));
+        }
+        Ok(Self(chain_id))
+    }
+}
+
+impl FromStr for ChainId {
+    type Err = String;
+
+    fn from_str(chain_id: &str) -> Result<Self, Self::Err> {
+        Self::try_from(chain_id.to_string())
+    }
+}
+
+impl From<ChainId> for String {
+    fn from(chain_id: ChainId) -> Self {
+        chain_id.0
+    }
+}
+
+impl ChainId {
+    /// Returns the short string encoded as a felt: its ASCII bytes read as a big endian number.
+    pub fn to_felt(&self) -> Felt252 {
+        Felt252::from_bytes_be(self.0.as_bytes())
+    }
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockHashAndNumber {
@@ -65,13 +161,22 @@ pub struct BlockHashAndNumber {
     pub block_number: u64,
 }
 
-#[allow(dead_code)] //TODO: Remove this
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub enum SyncStatusType {
     Syncing(SyncStatus),
     NotSyncing,
 }
 
+impl Serialize for SyncStatusType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            SyncStatusType::Syncing(status) => status.serialize(serializer),
+            // A node that is not syncing is reported as `false`
+            SyncStatusType::NotSyncing => serializer.serialize_bool(false),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventsPage {
     /// Matching events
@@ -122,6 +227,88 @@ pub struct DeployAccountTransactionResult {
     pub contract_address: Felt252,
 }
 
+/// The result of simulating a transaction on top of a block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedTransaction {
+    /// The events the transaction would emit
+    pub events: Vec<Event>,
+    /// Why the transaction would be reverted, if it would
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// The fee the transaction would be charged
+    pub fee_estimation: FeeEstimate,
+}
+
+/// Stage of its lifecycle a transaction reached in this sequencer. Stages only move forward, in
+/// declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TransactionStage {
+    /// Received by the mempool, waiting to be sealed in a batch
+    #[serde(rename = "RECEIVED")]
+    Received,
+    /// Sealed in a batch, waiting for the consensus to commit it
+    #[serde(rename = "BATCHED")]
+    Batched,
+    /// Committed by the consensus, waiting to be executed
+    #[serde(rename = "COMMITTED")]
+    Committed,
+    /// Executed and stored in a block
+    #[serde(rename = "EXECUTED")]
+    Executed,
+    /// Stored in a block, but its execution was reverted
+    #[serde(rename = "REVERTED")]
+    Reverted,
+    /// Left out of the chain
+    #[serde(rename = "REJECTED")]
+    Rejected,
+}
+
+/// What is known about a transaction along its lifecycle in this sequencer.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionLifecycle {
+    #[serde_as(as = "FeltHex")]
+    pub transaction_hash: Felt252,
+    /// The last stage the transaction reached
+    pub stage: TransactionStage,
+    /// Digest of the batch the transaction was sealed in, if it was batched by this node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_digest: Option<String>,
+    /// Consensus round of the block that committed the transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<u64>,
+    /// Number of the block the transaction was stored in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Why the transaction was reverted or rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl TransactionStage {
+    /// Whether the transaction is done moving through the sequencer.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransactionStage::Executed | TransactionStage::Reverted | TransactionStage::Rejected
+        )
+    }
+}
+
+impl TransactionLifecycle {
+    /// A transaction that was just received.
+    pub fn new(transaction_hash: Felt252) -> Self {
+        Self {
+            transaction_hash,
+            stage: TransactionStage::Received,
+            batch_digest: None,
+            round: None,
+            block_number: None,
+            reason: None,
+        }
+    }
+}
+
 #[serde_as]
 /// Block hash, number or tag
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -220,6 +407,42 @@ pub enum TransactionReceipt {
     DeployAccount(DeployAccountTransactionReceipt),
 }
 
+impl TransactionReceipt {
+    /// Sets the block in which the receipt's transaction was included.
+    pub fn set_block(&mut self, block_hash: Felt252, block_number: u64) {
+        let (receipt_block_hash, receipt_block_number) = match self {
+            TransactionReceipt::Invoke(receipt) => {
+                (&mut receipt.block_hash, &mut receipt.block_number)
+            }
+            TransactionReceipt::L1Handler(receipt) => {
+                (&mut receipt.block_hash, &mut receipt.block_number)
+            }
+            TransactionReceipt::Declare(receipt) => {
+                (&mut receipt.block_hash, &mut receipt.block_number)
+            }
+            TransactionReceipt::Deploy(receipt) => {
+                (&mut receipt.block_hash, &mut receipt.block_number)
+            }
+            TransactionReceipt::DeployAccount(receipt) => {
+                (&mut receipt.block_hash, &mut receipt.block_number)
+            }
+        };
+        *receipt_block_hash = block_hash;
+        *receipt_block_number = block_number;
+    }
+
+    /// Returns the hash of the transaction the receipt is for, whatever its type.
+    pub fn transaction_hash(&self) -> &Felt252 {
+        match self {
+            TransactionReceipt::Invoke(receipt) => &receipt.transaction_hash,
+            TransactionReceipt::L1Handler(receipt) => &receipt.transaction_hash,
+            TransactionReceipt::Declare(receipt) => &receipt.transaction_hash,
+            TransactionReceipt::Deploy(receipt) => &receipt.transaction_hash,
+            TransactionReceipt::DeployAccount(receipt) => &receipt.transaction_hash,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum PendingTransactionReceipt {
@@ -286,6 +509,7 @@ impl TryFrom<i64> for StarknetError {
         Ok(match value {
             1 => StarknetError::FailedToReceiveTransaction,
             20 => StarknetError::ContractNotFound,
+            21 => StarknetError::InvalidMessageSelector,
             24 => StarknetError::BlockNotFound,
             25 => StarknetError::TransactionHashNotFound,
             27 => StarknetError::InvalidTransactionIndex,
@@ -301,3 +525,24 @@ impl TryFrom<i64> for StarknetError {
         })
     }
 }
+
+impl From<StarknetError> for i64 {
+    fn from(value: StarknetError) -> Self {
+        match value {
+            StarknetError::FailedToReceiveTransaction => 1,
+            StarknetError::ContractNotFound => 20,
+            StarknetError::InvalidMessageSelector => 21,
+            StarknetError::BlockNotFound => 24,
+            StarknetError::TransactionHashNotFound => 25,
+            StarknetError::InvalidTransactionIndex => 27,
+            StarknetError::ClassHashNotFound => 28,
+            StarknetError::PageSizeTooBig => 31,
+            StarknetError::NoBlocks => 32,
+            StarknetError::InvalidContinuationToken => 33,
+            StarknetError::TooManyKeysInFilter => 34,
+            StarknetError::ContractError => 40,
+            StarknetError::InvalidContractClass => 50,
+            StarknetError::ClassAlreadyDeclared => 51,
+        }
+    }
+}
@@ -166,8 +166,12 @@ impl<'de> Deserialize<'de> for MaybePendingBlockWithTxs {
 #[cfg(test)]
 mod tests {
     use crate::{
-        BlockWithTxs, InvokeTransaction, InvokeTransactionV1, MaybePendingBlockWithTxs, Transaction,
+        BlockWithTxs, BroadcastedDeclareTransactionV2, ChainId, ContractClass, DeclareTransaction,
+        DeclareTransactionV2, EntryPointsByType, FlattenedSierraClass, InvokeTransaction,
+        InvokeTransactionReceipt, InvokeTransactionV1, MaybePendingBlockWithTxs, Transaction,
+        TransactionExecutionStatus, TransactionStatus,
     };
+    use std::sync::Arc;
 
     use super::*;
 
@@ -207,6 +211,117 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transaction_hash_depends_on_chain_id() {
+        let calldata = vec![Felt252::new(0), Felt252::new(10)];
+        let mainnet = "SN_MAIN".parse::<ChainId>().unwrap().to_felt();
+        let app_chain = ChainId::default().to_felt();
+
+        let transaction = Transaction::new_invoke(1, calldata.clone(), &mainnet);
+        assert_ne!(
+            transaction.transaction_hash(),
+            Transaction::new_invoke(1, calldata, &app_chain).transaction_hash()
+        );
+
+        let Transaction::Invoke(InvokeTransaction::V1(tx)) = transaction else {
+            panic!()
+        };
+        assert_eq!(tx.transaction_hash, tx.calculate_hash(&mainnet));
+        assert_eq!(Felt252::from_bytes_be(b"SN_MAIN"), mainnet);
+    }
+
+    #[test]
+    fn chain_ids_longer_than_a_felt_are_rejected() {
+        assert!("A".repeat(31).parse::<ChainId>().is_ok());
+        assert!("A".repeat(32).parse::<ChainId>().is_err());
+        assert!("SN_KRÄKEN".parse::<ChainId>().is_err());
+        assert!(serde_json::from_str::<ChainId>(&format!("\"{}\"", "A".repeat(32))).is_err());
+    }
+
+    #[test]
+    fn declared_class_is_sent_along_with_the_transaction() {
+        let contract_class = FlattenedSierraClass {
+            sierra_program: vec![Felt252::new(1), Felt252::new(2)],
+            contract_class_version: "0.1.0".to_string(),
+            entry_points_by_type: EntryPointsByType {
+                constructor: vec![],
+                external: vec![],
+                l1_handler: vec![],
+            },
+            abi: "[]".to_string(),
+        };
+        let tx = DeclareTransactionV2::from_broadcasted(
+            BroadcastedDeclareTransactionV2 {
+                max_fee: Felt252::new(0),
+                signature: vec![],
+                nonce: Felt252::new(0),
+                contract_class: Arc::new(contract_class.clone()),
+                sender_address: Felt252::new(1),
+                compiled_class_hash: Felt252::new(0),
+            },
+            &ChainId::default().to_felt(),
+        );
+        let contract_class = ContractClass::Sierra(contract_class);
+        assert_eq!(contract_class.class_hash(), tx.class_hash);
+
+        let bytes = Transaction::Declare(DeclareTransaction::V2(tx.clone()))
+            .declare_as_bytes(&contract_class);
+
+        assert_eq!(
+            &tx.transaction_hash,
+            Transaction::from_bytes(&bytes).transaction_hash()
+        );
+        let sent_class = Transaction::contract_class_from_bytes(&bytes).unwrap();
+        assert_eq!(tx.class_hash, sent_class.class_hash());
+
+        // Other transactions carry no class
+        let invoke = Transaction::new_invoke(1, vec![], &ChainId::default().to_felt());
+        assert!(Transaction::contract_class_from_bytes(&invoke.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn reverted_receipts_tell_why() {
+        let receipt = InvokeTransactionReceipt {
+            transaction_hash: Felt252::new(1),
+            actual_fee: Felt252::new(0),
+            status: TransactionStatus::AcceptedOnL2,
+            execution_status: TransactionExecutionStatus::Reverted,
+            revert_reason: Some("Entry point not found".to_string()),
+            block_hash: Felt252::new(2),
+            block_number: 1,
+            messages_sent: vec![],
+            events: vec![],
+        };
+
+        let serialized = serde_json::to_value(&receipt).unwrap();
+        assert_eq!("REVERTED", serialized["execution_status"]);
+        assert_eq!("Entry point not found", serialized["revert_reason"]);
+        let deserialized: InvokeTransactionReceipt = serde_json::from_value(serialized).unwrap();
+        assert_eq!(receipt.revert_reason, deserialized.revert_reason);
+
+        // Successful executions have no reason
+        let receipt = InvokeTransactionReceipt {
+            execution_status: TransactionExecutionStatus::Succeeded,
+            revert_reason: None,
+            ..receipt
+        };
+        let serialized = serde_json::to_value(&receipt).unwrap();
+        assert_eq!("SUCCEEDED", serialized["execution_status"]);
+        assert!(serialized.get("revert_reason").is_none());
+
+        // Receipts stored before execution statuses were recorded are of successful executions
+        let mut serialized = serialized;
+        serialized
+            .as_object_mut()
+            .unwrap()
+            .remove("execution_status");
+        let deserialized: InvokeTransactionReceipt = serde_json::from_value(serialized).unwrap();
+        assert_eq!(
+            TransactionExecutionStatus::Succeeded,
+            deserialized.execution_status
+        );
+    }
+
     #[test]
     fn serdeserialize_block_with_txs() {
         let block = MaybePendingBlockWithTxs::Block(BlockWithTxs::default());